@@ -0,0 +1,567 @@
+//! Conversion of ship and lift files between the legacy text format and JSON.
+//!
+//! The JSON documents contain the same data as the legacy files, so a file can
+//! be converted back and forth without losing anything but the comments.  The
+//! kind of a file is told by its extension: `.shp` for ships, `.elv` for lifts
+//! and `.json` for the documents.
+
+use crate::{
+    defs::{
+        MAX_ALERTS_ON_LEVEL, MAX_DOORS_ON_LEVEL, MAX_LEVEL_RECTS, MAX_LEVELS, MAX_LIFT_ROWS,
+        MAX_LIFTS, MAX_MAP_ROWS, MAX_REFRESHES_ON_LEVEL, MAX_WP_CONNECTIONS, MAXWAYPOINTS, MapTile,
+    },
+    error::LoadError,
+    map::{self, Color},
+    structs::{Level, Lift, Ship, Waypoint},
+};
+
+use arrayvec::ArrayVec;
+use sdl::Rect;
+use serde::{Deserialize, Serialize};
+use std::{
+    array,
+    ffi::CString,
+    fmt, fs,
+    io::{self, BufWriter},
+    ops::Not,
+    path::{Path, PathBuf},
+};
+
+const SHIP_EXTENSION: &str = "shp";
+const LIFTS_EXTENSION: &str = "elv";
+const DOCUMENT_EXTENSION: &str = "json";
+
+/// A ship, as it is stored in a ship file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShipDocument {
+    pub area_name: String,
+    pub levels: Vec<LevelDocument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LevelDocument {
+    pub number: u8,
+    pub name: String,
+    pub background_song: String,
+    pub enter_comment: String,
+    pub color: Color,
+    /// Every row is a string of tile numbers separated by spaces, so that a
+    /// row of the map is a line of the document.
+    pub map: Vec<String>,
+    pub waypoints: Vec<WaypointDocument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaypointDocument {
+    pub x: u8,
+    pub y: u8,
+    pub connections: Vec<u8>,
+}
+
+/// The lifts of a ship, as they are stored in a lift file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LiftsDocument {
+    /// The rectangles of the lift rows on the side view of the ship.
+    pub rows: Vec<RectDocument>,
+    /// The rectangles of every deck on the side view of the ship, indexed by
+    /// the deck number.
+    pub decks: Vec<Vec<RectDocument>>,
+    pub lifts: Vec<LiftDocument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RectDocument {
+    pub x: i16,
+    pub y: i16,
+    pub w: u16,
+    pub h: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LiftDocument {
+    pub deck: u8,
+    pub x: i32,
+    pub y: i32,
+    /// The index of the lift one deck up, if any.
+    pub up: Option<u8>,
+    /// The index of the lift one deck down, if any.
+    pub down: Option<u8>,
+    pub row: i32,
+}
+
+#[derive(Debug)]
+pub enum ConvertError {
+    /// The kind of the files cannot be told from their extensions.
+    UnknownKind,
+    Load(LoadError),
+    Write {
+        file: PathBuf,
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKind => write!(
+                f,
+                "one of the files must be a ship file (.{SHIP_EXTENSION}) or a lift file \
+                 (.{LIFTS_EXTENSION}), the other one a ship or lift file as well or a JSON \
+                 document (.{DOCUMENT_EXTENSION})"
+            ),
+            Self::Load(err) => write!(f, "{err}"),
+            Self::Write { file, error } => {
+                write!(f, "unable to write {}: {error}", file.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+impl From<LoadError> for ConvertError {
+    fn from(err: LoadError) -> Self {
+        Self::Load(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Ship,
+    Lifts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Legacy(Kind),
+    Document,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            SHIP_EXTENSION => Some(Self::Legacy(Kind::Ship)),
+            LIFTS_EXTENSION => Some(Self::Legacy(Kind::Lifts)),
+            DOCUMENT_EXTENSION => Some(Self::Document),
+            _ => None,
+        }
+    }
+}
+
+/// Converts a ship or a lift file from the format of `input` to the format
+/// of `output`.
+pub fn convert(input: &Path, output: &Path) -> Result<(), ConvertError> {
+    let (Some(input_format), Some(output_format)) = (Format::of(input), Format::of(output)) else {
+        return Err(ConvertError::UnknownKind);
+    };
+    let kind = match (input_format, output_format) {
+        (Format::Legacy(kind), Format::Document) | (Format::Document, Format::Legacy(kind)) => kind,
+        (Format::Legacy(input_kind), Format::Legacy(output_kind)) if input_kind == output_kind => {
+            input_kind
+        }
+        _ => return Err(ConvertError::UnknownKind),
+    };
+
+    let mut ship = Ship::default();
+    match (input_format, kind) {
+        (Format::Legacy(_), Kind::Ship) => map::read_ship_file(input, &mut ship)?,
+        (Format::Legacy(_), Kind::Lifts) => map::read_lift_file(input, &mut ship)?,
+        (Format::Document, Kind::Ship) => {
+            let document: ShipDocument = read_document(input)?;
+            document.into_ship(input, &mut ship)?;
+        }
+        (Format::Document, Kind::Lifts) => {
+            let document: LiftsDocument = read_document(input)?;
+            document.into_ship(input, &mut ship)?;
+        }
+    }
+
+    let write_error = |error| ConvertError::Write {
+        file: output.to_owned(),
+        error,
+    };
+    let writer = BufWriter::new(fs::File::create(output).map_err(write_error)?);
+    match (output_format, kind) {
+        (Format::Legacy(_), Kind::Ship) => map::write_ship_file(&mut ship, writer),
+        (Format::Legacy(_), Kind::Lifts) => map::write_lift_file(&ship, writer),
+        (Format::Document, Kind::Ship) => write_document(&ShipDocument::from_ship(&ship), writer),
+        (Format::Document, Kind::Lifts) => write_document(&LiftsDocument::from_ship(&ship), writer),
+    }
+    .map_err(write_error)
+}
+
+fn read_document<T>(path: &Path) -> Result<T, LoadError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let data = fs::read(path).map_err(|error| LoadError::Io {
+        file: path.to_owned(),
+        error,
+    })?;
+    serde_json::from_slice(&data).map_err(|err| LoadError::invalid_data(path, err.to_string()))
+}
+
+fn write_document<T: Serialize>(document: &T, mut writer: impl io::Write) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, document)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+impl ShipDocument {
+    #[must_use]
+    pub fn from_ship(ship: &Ship) -> Self {
+        Self {
+            area_name: ship.area_name.to_string_lossy().into_owned(),
+            levels: ship.levels.iter().map(LevelDocument::from_level).collect(),
+        }
+    }
+
+    /// Replaces the area name and the levels of `ship`.
+    ///
+    /// `path` is only used for the errors.
+    pub fn into_ship(self, path: &Path, ship: &mut Ship) -> Result<(), LoadError> {
+        let invalid = |reason: String| LoadError::invalid_data(path, reason);
+
+        if self.area_name.contains(['"', '\n']) {
+            return Err(invalid(
+                "the area name must not contain quotes or line breaks".to_owned(),
+            ));
+        }
+        ship.area_name
+            .try_set_slice(&self.area_name)
+            .map_err(|_| invalid("the area name is too long".to_owned()))?;
+
+        if self.levels.is_empty() {
+            return Err(invalid("the ship has no levels".to_owned()));
+        }
+        if self.levels.len() > MAX_LEVELS {
+            return Err(invalid(format!(
+                "the ship has {} levels, at most {MAX_LEVELS} are allowed",
+                self.levels.len()
+            )));
+        }
+        // the levels are saved in the order of their numbers
+        for index in 0..self.levels.len() {
+            let count = self
+                .levels
+                .iter()
+                .filter(|level| usize::from(level.number) == index)
+                .count();
+            if count != 1 {
+                return Err(invalid(format!(
+                    "level number {index} is used {count} times, every number from 0 to {} \
+                     must be used once",
+                    self.levels.len() - 1
+                )));
+            }
+        }
+
+        ship.levels.clear();
+        for level in self.levels {
+            let number = level.number;
+            let level = level
+                .into_level()
+                .map_err(|reason| invalid(format!("level {number}: {reason}")))?;
+            ship.levels.push(level);
+        }
+
+        Ok(())
+    }
+}
+
+impl LevelDocument {
+    fn from_level(level: &Level) -> Self {
+        let map = level.map[..usize::from(level.ylen)]
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&tile| format!("{:02}", tile as u8))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+
+        let waypoints = level
+            .waypoints
+            .iter()
+            .map(|waypoint| WaypointDocument {
+                x: waypoint.x,
+                y: waypoint.y,
+                connections: waypoint.connections.to_vec(),
+            })
+            .collect();
+
+        Self {
+            number: level.levelnum,
+            name: level.levelname.to_string_lossy().into_owned(),
+            background_song: level.background_song_name.to_string_lossy().into_owned(),
+            enter_comment: level.enter_comment.to_string_lossy().into_owned(),
+            color: level.color,
+            map,
+            waypoints,
+        }
+    }
+
+    /// On failure the reason is returned.
+    fn into_level(self) -> Result<Level, String> {
+        fn line(name: &str, value: String) -> Result<CString, String> {
+            if value.contains('\n') {
+                return Err(format!("the {name} must not contain line breaks"));
+            }
+            CString::new(value).map_err(|_| format!("the {name} must not contain null characters"))
+        }
+
+        let ylen = u8::try_from(self.map.len())
+            .ok()
+            .filter(|&ylen| ylen <= MAX_MAP_ROWS)
+            .ok_or_else(|| format!("the map has more than {MAX_MAP_ROWS} rows"))?;
+        let mut rows = self.map.iter().enumerate().map(|(row_index, row)| {
+            row.split_whitespace()
+                .map(|tile| {
+                    tile.parse::<u8>()
+                        .ok()
+                        .and_then(|tile| MapTile::try_from(tile).ok())
+                        .ok_or_else(|| format!("invalid tile {tile} in row {row_index}"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        });
+
+        let mut tiles = array::from_fn(|_| Vec::new());
+        let mut xlen = None;
+        for (index, row) in tiles.iter_mut().take(self.map.len()).enumerate() {
+            *row = rows.next().unwrap()?;
+            let row_len = u8::try_from(row.len())
+                .map_err(|_| format!("row {index} has more than {} tiles", u8::MAX))?;
+            match xlen {
+                None => xlen = Some(row_len),
+                Some(xlen) if xlen != row_len => {
+                    return Err(format!(
+                        "row {index} has {row_len} tiles, but the first row has {xlen}"
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+        let xlen = xlen.unwrap_or(0);
+
+        if self.waypoints.len() > usize::from(MAXWAYPOINTS) {
+            return Err(format!("more than {MAXWAYPOINTS} waypoints"));
+        }
+        let waypoints = self
+            .waypoints
+            .into_iter()
+            .enumerate()
+            .map(|(index, waypoint)| {
+                let connections = ArrayVec::try_from(&*waypoint.connections).map_err(|_| {
+                    format!("waypoint {index} has more than {MAX_WP_CONNECTIONS} connections")
+                })?;
+                Ok(Waypoint {
+                    x: waypoint.x,
+                    y: waypoint.y,
+                    connections,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        let mut level = Level {
+            empty: false,
+            timer: 0.,
+            levelnum: self.number,
+            levelname: line("name", self.name)?,
+            background_song_name: line("background song", self.background_song)?,
+            enter_comment: line("enter comment", self.enter_comment)?,
+            xlen,
+            ylen,
+            color: self.color,
+            map: tiles,
+            refreshes: [None; MAX_REFRESHES_ON_LEVEL],
+            doors: [None; MAX_DOORS_ON_LEVEL],
+            alerts: [None; MAX_ALERTS_ON_LEVEL],
+            waypoints,
+        };
+        map::init_level(&mut level)?;
+        Ok(level)
+    }
+}
+
+impl LiftsDocument {
+    #[must_use]
+    pub fn from_ship(ship: &Ship) -> Self {
+        let decks_len = ship
+            .level_rects
+            .iter()
+            .rposition(|deck| deck.is_empty().not())
+            .map_or(0, |last_deck| last_deck + 1);
+
+        Self {
+            rows: ship
+                .lift_row_rects
+                .iter()
+                .map(RectDocument::from_rect)
+                .collect(),
+            decks: ship.level_rects[..decks_len]
+                .iter()
+                .map(|deck| deck.iter().map(RectDocument::from_rect).collect())
+                .collect(),
+            lifts: ship.lifts.iter().map(LiftDocument::from_lift).collect(),
+        }
+    }
+
+    /// Replaces the lift rows, the deck rectangles and the lifts of `ship`.
+    ///
+    /// `path` is only used for the errors.
+    pub fn into_ship(self, path: &Path, ship: &mut Ship) -> Result<(), LoadError> {
+        let invalid = |reason: String| LoadError::invalid_data(path, reason);
+
+        if self.rows.len() > MAX_LIFT_ROWS {
+            return Err(invalid(format!("more than {MAX_LIFT_ROWS} lift rows")));
+        }
+        ship.lift_row_rects = self.rows.iter().map(RectDocument::to_rect).collect();
+
+        if self.decks.len() > MAX_LEVELS {
+            return Err(invalid(format!("more than {MAX_LEVELS} decks")));
+        }
+        ship.level_rects.iter_mut().for_each(ArrayVec::clear);
+        for (deck_index, (deck, rects)) in ship.level_rects.iter_mut().zip(&self.decks).enumerate()
+        {
+            if rects.len() > MAX_LEVEL_RECTS {
+                return Err(invalid(format!(
+                    "deck {deck_index} has more than {MAX_LEVEL_RECTS} rectangles"
+                )));
+            }
+            deck.extend(rects.iter().map(RectDocument::to_rect));
+        }
+
+        if self.lifts.len() > MAX_LIFTS {
+            return Err(invalid(format!("more than {MAX_LIFTS} lifts")));
+        }
+        ship.lifts.clear();
+        for (index, lift) in self.lifts.iter().enumerate() {
+            let is_valid_link =
+                |link: Option<u8>| link.is_none_or(|link| usize::from(link) < self.lifts.len());
+            if is_valid_link(lift.up).not() || is_valid_link(lift.down).not() {
+                return Err(invalid(format!(
+                    "lift {index} leads to a lift that does not exist"
+                )));
+            }
+            ship.lifts.push(lift.to_lift());
+        }
+
+        Ok(())
+    }
+}
+
+impl RectDocument {
+    fn from_rect(rect: &Rect) -> Self {
+        Self {
+            x: rect.x(),
+            y: rect.y(),
+            w: rect.width(),
+            h: rect.height(),
+        }
+    }
+
+    fn to_rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.w, self.h)
+    }
+}
+
+impl LiftDocument {
+    fn from_lift(lift: &Lift) -> Self {
+        // the lift files have already been checked while they were read
+        let link = |link: i32| u8::try_from(link).ok();
+
+        Self {
+            deck: lift.level,
+            x: lift.x,
+            y: lift.y,
+            up: link(lift.up),
+            down: link(lift.down),
+            row: lift.row,
+        }
+    }
+
+    fn to_lift(&self) -> Lift {
+        let link = |link: Option<u8>| link.map_or(-1, i32::from);
+
+        Lift {
+            level: self.deck,
+            x: self.x,
+            y: self.y,
+            up: link(self.up),
+            down: link(self.down),
+            row: self.row,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{ScratchDir, test_level, waypoint};
+
+    /// Converts the legacy file `original` to a document and back, and
+    /// checks that the same file comes out again.
+    fn assert_round_trip(original: &Path, extension: &str) {
+        let document = original.with_extension(DOCUMENT_EXTENSION);
+        let converted = original.with_file_name(format!("converted.{extension}"));
+        convert(original, &document).unwrap();
+        convert(&document, &converted).unwrap();
+        assert_eq!(fs::read(&converted).unwrap(), fs::read(original).unwrap());
+    }
+
+    #[test]
+    fn ship_round_trip() {
+        let mut ship = Ship::default();
+        ship.area_name.set_slice("Test ship");
+        for levelnum in 0..2 {
+            let mut level = test_level(4, 3, MapTile::Floor);
+            level.levelnum = levelnum;
+            level.levelname = CString::new(format!("Deck {levelnum}")).unwrap();
+            level.background_song_name = CString::new("deck.mod").unwrap();
+            level.enter_comment = CString::new("Welcome aboard").unwrap();
+            level.color = Color::Green;
+            level.map[0][0] = MapTile::EckLo;
+            level.map[1][3] = MapTile::VZutuere;
+            level.map[2][1] = MapTile::Lift;
+            level.waypoints.push(waypoint(1, 1, &[1]));
+            level.waypoints.push(waypoint(2, 1, &[0]));
+            ship.levels.push(level);
+        }
+
+        let dir = ScratchDir::new("convert-ship");
+        let original = dir.join("test.shp");
+        map::write_ship_file(&mut ship, fs::File::create(&original).unwrap()).unwrap();
+        assert_round_trip(&original, SHIP_EXTENSION);
+    }
+
+    #[test]
+    fn lifts_round_trip() {
+        let mut ship = Ship::default();
+        ship.lift_row_rects.push(Rect::new(10, 20, 30, 40));
+        ship.level_rects[0].push(Rect::new(0, 0, 50, 20));
+        ship.level_rects[1].push(Rect::new(0, 20, 50, 20));
+        ship.level_rects[1].push(Rect::new(50, 20, 10, 20));
+        ship.lifts.push(Lift {
+            level: 0,
+            x: 2,
+            y: 1,
+            up: -1,
+            down: 1,
+            row: 0,
+        });
+        ship.lifts.push(Lift {
+            level: 1,
+            x: 2,
+            y: 1,
+            up: 0,
+            down: -1,
+            row: 0,
+        });
+
+        let dir = ScratchDir::new("convert-lifts");
+        let original = dir.join("test.elv");
+        map::write_lift_file(&ship, fs::File::create(&original).unwrap()).unwrap();
+        assert_round_trip(&original, LIFTS_EXTENSION);
+    }
+}
@@ -133,6 +133,18 @@ impl crate::Data<'_> {
         self.key_is_pressed_r(PointerStates::MouseButton1.to_u16())
     }
 
+    #[cfg(not(target_os = "android"))]
+    #[inline]
+    pub fn mouse_right_pressed(&mut self) -> bool {
+        self.key_is_pressed(PointerStates::MouseButton2.to_u16())
+    }
+
+    #[cfg(not(target_os = "android"))]
+    #[inline]
+    pub fn mouse_right_pressed_r(&mut self) -> bool {
+        self.key_is_pressed_r(PointerStates::MouseButton2.to_u16())
+    }
+
     #[cfg(not(target_os = "android"))]
     #[inline]
     pub fn space_pressed(&mut self) -> bool {
@@ -507,6 +519,9 @@ pub const FD_DATADIR: &str = "."; // our local fallback
 pub const LOCAL_DATADIR: &str = ".."; // local fallback
 // #endif
 
+// the installed data directories, in the order they are searched
+pub const DATA_DIRS: [&str; 2] = [LOCAL_DATADIR, FD_DATADIR];
+
 pub const GRAPHICS_DIR_C: &CStr = c"graphics/";
 pub const SOUND_DIR_C: &CStr = c"sound/";
 pub const MAP_DIR_C: &CStr = c"map/";
@@ -719,6 +734,8 @@ pub const MAX_LIFT_ROWS: usize = 15; /* the different lift "rows" */
 /* don't change this easily */
 /* corresponds to a reserved palette range !*/
 pub const MAX_LEVEL_RECTS: usize = 20; // how many rects compose a level
+/// The x position of a lift that is out of order.
+pub const LIFT_OUT_OF_ORDER_X: i32 = 99;
 
 pub const MAXWAYPOINTS: u8 = 100;
 pub const MAX_DOORS_ON_LEVEL: usize = 60;
@@ -1035,6 +1052,41 @@ impl Status {
     }
 }
 
+impl TryFrom<u8> for Status {
+    type Error = InvalidStatus;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Mobile),
+            1 => Ok(Self::Transfermode),
+            2 => Ok(Self::Weapon),
+            3 => Ok(Self::Console),
+            4 => Ok(Self::Debriefing),
+            5 => Ok(Self::Terminated),
+            6 => Ok(Self::Pause),
+            7 => Ok(Self::Cheese),
+            8 => Ok(Self::Elevator),
+            9 => Ok(Self::Briefing),
+            10 => Ok(Self::Menu),
+            11 => Ok(Self::Victory),
+            12 => Ok(Self::Activate),
+            13 => Ok(Self::Out),
+            _ => Err(InvalidStatus),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidStatus;
+
+impl Display for InvalidStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("raw status index is invalid")
+    }
+}
+
+impl std::error::Error for InvalidStatus {}
+
 pub const DECKCOMPLETEBONUS: f32 = 500.;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
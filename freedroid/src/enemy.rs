@@ -14,7 +14,6 @@ use log::warn;
 use rand::{
     Rng,
     seq::{IteratorRandom, SliceRandom},
-    thread_rng,
 };
 use sdl::convert::u8_to_usize;
 
@@ -133,13 +132,12 @@ impl crate::Data<'_> {
             return;
         }
 
-        let mut rng = thread_rng();
         #[allow(clippy::cast_precision_loss)]
-        if (0..=AGGRESSIONMAX).choose(&mut rng).unwrap()
+        if (0..=AGGRESSIONMAX).choose(&mut self.rng).unwrap()
             >= self.vars.droidmap[this_robot.ty.to_usize()].aggression
         {
             self.main.enemys[usize::try_from(enemy_num).unwrap()].firewait +=
-                rng.gen_range(0f32..=1f32) * ROBOT_MAX_WAIT_BETWEEN_SHOTS;
+                self.rng.gen_range(0f32..=1f32) * ROBOT_MAX_WAIT_BETWEEN_SHOTS;
             return;
         }
 
@@ -254,7 +252,11 @@ impl crate::Data<'_> {
 
     pub fn check_enemy_enemy_collision(&mut self, enemy_num: i32) -> i32 {
         let Self {
-            main, misc, global, ..
+            main,
+            misc,
+            global,
+            rng,
+            ..
         } = self;
 
         let curlev = main.cur_level().levelnum;
@@ -265,7 +267,6 @@ impl crate::Data<'_> {
         let check_x = cur_enemy.pos.x;
         let check_y = cur_enemy.pos.y;
 
-        let mut rng = thread_rng();
         for enemy in enemys_before.iter_mut().chain(enemys_after) {
             // check only collisions of LIVING enemys on this level
             if matches!(enemy.status, Status::Out | Status::Terminated) || enemy.levelnum != curlev
@@ -340,11 +341,28 @@ impl crate::Data<'_> {
         // then it might be time to set a new waypoint.
         //
         if restweg.x == 0. && restweg.y == 0. {
-            let mut rng = thread_rng();
             this_robot.lastwaypoint = this_robot.nextwaypoint;
-            this_robot.warten = rng.gen_range(0..=ENEMYMAXWAIT).into();
 
-            if let Some(connection) = wp_list[nextwp].connections.choose(&mut rng).copied() {
+            // a droid sent somewhere takes the shortest way there, and goes on
+            // wandering once it has arrived or finds no way
+            let level_index = self
+                .main
+                .cur_level_index
+                .expect("no current level index available");
+            let next_step = this_robot.target.and_then(|target| {
+                self.main
+                    .paths
+                    .table(level_index, wp_list)
+                    .next_step(this_robot.nextwaypoint, target)
+            });
+            if next_step.is_none() {
+                this_robot.target = None;
+                this_robot.warten = self.rng.gen_range(0..=ENEMYMAXWAIT).into();
+            }
+
+            if let Some(connection) =
+                next_step.or_else(|| wp_list[nextwp].connections.choose(&mut self.rng).copied())
+            {
                 this_robot.nextwaypoint = connection;
             }
         }
@@ -359,7 +377,6 @@ impl crate::Data<'_> {
         let num_wp = u8::try_from(cur_level.waypoints.len()).unwrap();
         let mut nth_enemy = 0;
 
-        let mut rng = thread_rng();
         for enemy in &mut self.main.enemys {
             if enemy.status == Status::Out || enemy.levelnum != cur_level_num {
                 /* dont handle dead enemys or on other level */
@@ -383,7 +400,7 @@ impl crate::Data<'_> {
 
             let mut wp;
             loop {
-                wp = rng.gen_range(0..num_wp);
+                wp = self.rng.gen_range(0..num_wp);
                 if used_wp[usize::from(wp)].not() {
                     break;
                 }
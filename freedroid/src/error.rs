@@ -0,0 +1,208 @@
+//! Errors that can happen while the data files of the game are loaded.
+//!
+//! All the loaders return a [`LoadError`] instead of terminating the game, and
+//! the errors end up in [`crate::Data::show_load_error`], which tells the
+//! player what went wrong.  The callers then fall back to the classic theme or
+//! to the default mission.  There is no fallback for the default ruleset, the
+//! standard mission and the classic theme, the game quits after
+//! [`crate::Data::show_fatal_load_error`] when one of them cannot be loaded.
+//!
+//! The loaders of the ship, crew, lift and ruleset files keep going after a
+//! problem and collect a [`DataProblem`] for each one they find.  The game only
+//! reports the first of them, the mission validator reports all of them.
+
+use bstr::ByteSlice;
+use log::error;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// A data file that could not be loaded, and why.
+#[derive(Debug)]
+#[must_use]
+pub enum LoadError {
+    /// The file is not present in any of the data directories.
+    NotFound { file: String },
+
+    /// The file is present, but it cannot be read.
+    Io { file: PathBuf, error: io::Error },
+
+    /// The string that marks the end of the file is missing, so the file is
+    /// most likely truncated.
+    Unterminated { file: PathBuf, end_string: String },
+
+    /// An entry is missing, or its value cannot be parsed.
+    InvalidEntry { file: PathBuf, entry: String },
+
+    /// The entries can be parsed, but they do not make sense together.
+    InvalidData { file: PathBuf, reason: String },
+}
+
+impl LoadError {
+    pub fn not_found(file: &[u8]) -> Self {
+        Self::NotFound {
+            file: file.to_str_lossy().into_owned(),
+        }
+    }
+
+    pub fn invalid_entry(file: &Path, entry: &[u8]) -> Self {
+        Self::InvalidEntry {
+            file: file.to_owned(),
+            entry: entry.to_str_lossy().trim().to_owned(),
+        }
+    }
+
+    pub fn invalid_data(file: &Path, reason: impl Into<String>) -> Self {
+        Self::InvalidData {
+            file: file.to_owned(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Fails with the first of the `problems` found in `file`, if there are
+    /// any.
+    pub fn first_problem(file: &Path, problems: Vec<DataProblem>) -> Result<(), Self> {
+        match problems.into_iter().next() {
+            Some(problem) => Err(Self::invalid_data(file, problem.reason)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A problem found in the data of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataProblem {
+    /// The position in the data the problem was found at, `None` if it
+    /// concerns the file as a whole.
+    pub offset: Option<usize>,
+    pub reason: String,
+}
+
+impl DataProblem {
+    pub fn at(offset: usize, reason: impl Into<String>) -> Self {
+        Self {
+            offset: Some(offset),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn in_file(reason: impl Into<String>) -> Self {
+        Self {
+            offset: None,
+            reason: reason.into(),
+        }
+    }
+
+    /// An entry searched from `offset` on is missing, or its value cannot be
+    /// parsed.
+    pub fn invalid_entry(offset: usize, entry: &[u8]) -> Self {
+        Self::at(
+            offset,
+            format!(
+                "entry \"{}\" is missing or invalid",
+                entry.to_str_lossy().trim()
+            ),
+        )
+    }
+}
+
+/// Passes `value` on, and records a missing one as an invalid `entry`
+/// searched from `offset` on.
+pub fn expect_entry<T>(
+    value: Option<T>,
+    entry: impl AsRef<[u8]>,
+    offset: usize,
+    problems: &mut Vec<DataProblem>,
+) -> Option<T> {
+    if value.is_none() {
+        problems.push(DataProblem::invalid_entry(offset, entry.as_ref()));
+    }
+    value
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { file } => write!(f, "file {file} not found"),
+            Self::Io { file, error } => write!(f, "unable to read {}: {error}", file.display()),
+            Self::Unterminated { file, end_string } => write!(
+                f,
+                "{} is not terminated by \"{end_string}\", it might be truncated",
+                file.display()
+            ),
+            Self::InvalidEntry { file, entry } => write!(
+                f,
+                "{}: entry \"{entry}\" is missing or invalid",
+                file.display()
+            ),
+            Self::InvalidData { file, reason } => write!(f, "{}: {reason}", file.display()),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl crate::Data<'_> {
+    /// Tells the player that some data could not be loaded, and waits for a
+    /// key press before the game goes on with its fallback.
+    ///
+    /// Without a screen the error is only logged.
+    pub fn show_load_error(&mut self, err: &LoadError) {
+        self.show_load_error_message(
+            err,
+            "The game will go on with its default data.\n\nPress any key to continue.",
+        );
+    }
+
+    /// Tells the player that some data the game cannot do without could not
+    /// be loaded, and makes the game quit after a key press.
+    pub fn show_fatal_load_error(&mut self, err: &LoadError) {
+        self.show_load_error_message(
+            err,
+            "Freedroid cannot go on without it.\n\nPress any key to quit.",
+        );
+        self.quit.set(true);
+    }
+
+    fn show_load_error_message(&mut self, err: &LoadError, consequence: &str) {
+        error!("{err}");
+
+        if self.main.headless || self.graphics.ne_screen.is_none() {
+            return;
+        }
+
+        let mut rect = self.vars.screen_rect;
+        self.graphics.ne_screen.as_mut().unwrap().clear_clip_rect();
+        self.clear_graph_mem();
+        self.make_grid_on_screen(Some(&rect));
+        rect.inc_x(10);
+        rect.inc_y(10);
+        rect.dec_width(20);
+
+        self.b_font
+            .current_font
+            .clone_from(&self.global.font0_b_font);
+        let message = format!("Freedroid could not load its data:\n\n{err}\n\n{consequence}");
+        self.display_text(
+            message.as_bytes(),
+            rect.x().into(),
+            rect.y().into(),
+            Some(rect),
+        );
+        assert!(self.graphics.ne_screen.as_mut().unwrap().flip());
+
+        self.wait_for_all_keys_released();
+        self.wait_for_key_pressed();
+        self.wait_for_all_keys_released();
+    }
+}
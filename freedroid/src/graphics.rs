@@ -10,8 +10,12 @@ use crate::{
         NUM_DECAL_PICS, NUM_MAP_BLOCKS, PARA_FONT_FILE, SHIP_OFF_PIC_FILE, SHIP_ON_PIC_FILE,
         SoundType, TAKEOVER_BG_PIC_FILE, Themed, scale_point,
     },
+    error::LoadError,
     global::Global,
-    misc::{read_i16_from_string, read_i32_from_string, read_u8_from_string, read_u16_from_string},
+    misc::{
+        try_read_i16_from_string, try_read_i32_from_string, try_read_u8_from_string,
+        try_read_u16_from_string,
+    },
     read_and_malloc_and_terminate_file,
     structs::ThemeList,
     takeover::TO_BLOCK_FILE,
@@ -953,7 +957,10 @@ impl crate::Data<'_> {
 
         self.init_progress("Loading pictures");
 
-        self.load_theme_configuration_file();
+        if self.load_theme_configuration_or_fallback().not() {
+            self.b_font.current_font = oldfont;
+            return false.into();
+        }
 
         self.update_progress(15);
 
@@ -1167,7 +1174,7 @@ impl crate::Data<'_> {
         );
     }
 
-    pub fn load_theme_configuration_file(&mut self) {
+    pub fn load_theme_configuration_file(&mut self) -> Result<(), LoadError> {
         use bstr::ByteSlice;
 
         const END_OF_THEME_DATA_STRING: &[u8] = b"**** End of theme data section ****";
@@ -1179,66 +1186,72 @@ impl crate::Data<'_> {
         const DIGIT_TWO_POSITION_Y_STRING: &[u8] = b"Second digit y :";
         const DIGIT_THREE_POSITION_X_STRING: &[u8] = b"Third digit x :";
         const DIGIT_THREE_POSITION_Y_STRING: &[u8] = b"Third digit y :";
+        const BULLET_TYPE_STRING: &[u8] = b"For Bullettype Nr.=";
+        const BULLET_PHASES_STRING: &[u8] = b"we will use number of phases=";
+        const BULLET_PHASE_CHANGES_STRING: &[u8] = b"and number of phase changes per second=";
+
+        let fpath = self.find_data_file(b"config.theme", Some(GRAPHICS_DIR_C), Themed::UseTheme)?;
+
+        // The pictures are loaded later on, and a missing one cannot be
+        // recovered from at that point.
+        for file_name in [
+            MAP_BLOCK_FILE,
+            DROID_BLOCK_FILE,
+            BULLET_BLOCK_FILE,
+            BLAST_BLOCK_FILE,
+            DIGIT_BLOCK_FILE,
+            TO_BLOCK_FILE,
+            SHIP_ON_PIC_FILE,
+            SHIP_OFF_PIC_FILE,
+        ] {
+            self.find_data_file(file_name, Some(GRAPHICS_DIR_C), Themed::UseTheme)?;
+        }
 
-        let fpath = Self::find_file_static(
-            &self.global,
-            &mut self.misc,
-            b"config.theme",
-            Some(GRAPHICS_DIR_C),
-            Themed::UseTheme,
-        );
-        let fpath = Path::new(
-            fpath
-                .to_str()
-                .expect("unable to convert C string to UTF-8 string"),
-        );
-
-        let data = read_and_malloc_and_terminate_file(fpath, END_OF_THEME_DATA_STRING);
+        let data = read_and_malloc_and_terminate_file(&fpath, END_OF_THEME_DATA_STRING)?;
 
         //--------------------
         // Now the file is read in entirely and
         // we can start to analyze its content,
         //
 
+        let invalid_entry = |label| LoadError::invalid_entry(&fpath, label);
+        let read_i16 =
+            |label| try_read_i16_from_string(&data, label).ok_or_else(|| invalid_entry(label));
+        let read_i32 =
+            |label| try_read_i32_from_string(&data, label).ok_or_else(|| invalid_entry(label));
+
         self.vars.blastmap[Explosion::Bulletblast].phases =
-            read_i32_from_string(&data, BLAST_ONE_NUMBER_OF_PHASES_STRING);
+            read_i32(BLAST_ONE_NUMBER_OF_PHASES_STRING)?;
 
         self.vars.blastmap[Explosion::Druidblast {
             from_influencer: false,
         }]
-        .phases = read_i32_from_string(&data, BLAST_TWO_NUMBER_OF_PHASES_STRING);
+        .phases = read_i32(BLAST_TWO_NUMBER_OF_PHASES_STRING)?;
 
         // Next we read in the number of phases that are to be used for each bullet type
         let mut reader = &*data;
-        while let Some(read_start) = reader.find(b"For Bullettype Nr.=") {
+        while let Some(read_start) = reader.find(BULLET_TYPE_STRING) {
             let read = &reader[read_start..];
-            let bullet_index = usize::from(read_u8_from_string(read, b"For Bullettype Nr.="));
-            assert!(
-                bullet_index < self.vars.bulletmap.len(),
-                "----------------------------------------------------------------------\n\
-                 Freedroid has encountered a problem:\n\
-                 In function 'char* LoadThemeConfigurationFile ( ... ):\n\
-                 \n\
-                 There was a specification for the number of phases in a bullet type\n\
-                 that does not at all exist in the ruleset.\n\
-                 \n\
-                 This might indicate that either the ruleset file is corrupt or the \n\
-                 theme.config configuration file is corrupt or (less likely) that there\n\
-                 is a severe bug in the reading function.\n\
-                 \n\
-                 Please check that your theme and ruleset files are properly set up.\n\
-                 \n\
-                 Please also don't forget, that you might have to run 'make install'\n\
-                 again after you've made modifications to the data files in the source tree.\n\
-                 \n\
-                 Freedroid will terminate now to draw attention to the data problem it could\n\
-                 not resolve.... Sorry, if that interrupts a major game of yours.....\n\
-                 ----------------------------------------------------------------------\n"
-            );
-            self.vars.bulletmap[bullet_index].phases =
-                read_u8_from_string(read, b"we will use number of phases=");
-            self.vars.bulletmap[bullet_index].phase_changes_per_second =
-                read_u16_from_string(read, b"and number of phase changes per second=");
+            let bullet_index = try_read_u8_from_string(read, BULLET_TYPE_STRING)
+                .ok_or_else(|| invalid_entry(BULLET_TYPE_STRING))?;
+            let bullet = self
+                .vars
+                .bulletmap
+                .get_mut(usize::from(bullet_index))
+                .ok_or_else(|| {
+                    LoadError::invalid_data(
+                        &fpath,
+                        format!(
+                            "the phases of bullet type {bullet_index} are specified, but the \
+                             ruleset does not contain such a bullet type"
+                        ),
+                    )
+                })?;
+            bullet.phases = try_read_u8_from_string(read, BULLET_PHASES_STRING)
+                .ok_or_else(|| invalid_entry(BULLET_PHASES_STRING))?;
+            bullet.phase_changes_per_second =
+                try_read_u16_from_string(read, BULLET_PHASE_CHANGES_STRING)
+                    .ok_or_else(|| invalid_entry(BULLET_PHASE_CHANGES_STRING))?;
             reader = &reader[read_start + 1..];
         }
 
@@ -1248,20 +1261,52 @@ impl crate::Data<'_> {
         // file of the theme
         //
 
-        self.main.first_digit_rect.as_mut().x =
-            read_i16_from_string(&data, DIGIT_ONE_POSITION_X_STRING);
-        self.main.first_digit_rect.as_mut().y =
-            read_i16_from_string(&data, DIGIT_ONE_POSITION_Y_STRING);
+        self.main.first_digit_rect.as_mut().x = read_i16(DIGIT_ONE_POSITION_X_STRING)?;
+        self.main.first_digit_rect.as_mut().y = read_i16(DIGIT_ONE_POSITION_Y_STRING)?;
+
+        self.main.second_digit_rect.as_mut().x = read_i16(DIGIT_TWO_POSITION_X_STRING)?;
+        self.main.second_digit_rect.as_mut().y = read_i16(DIGIT_TWO_POSITION_Y_STRING)?;
 
-        self.main.second_digit_rect.as_mut().x =
-            read_i16_from_string(&data, DIGIT_TWO_POSITION_X_STRING);
-        self.main.second_digit_rect.as_mut().y =
-            read_i16_from_string(&data, DIGIT_TWO_POSITION_Y_STRING);
+        self.main.third_digit_rect.as_mut().x = read_i16(DIGIT_THREE_POSITION_X_STRING)?;
+        self.main.third_digit_rect.as_mut().y = read_i16(DIGIT_THREE_POSITION_Y_STRING)?;
 
-        self.main.third_digit_rect.as_mut().x =
-            read_i16_from_string(&data, DIGIT_THREE_POSITION_X_STRING);
-        self.main.third_digit_rect.as_mut().y =
-            read_i16_from_string(&data, DIGIT_THREE_POSITION_Y_STRING);
+        Ok(())
+    }
+
+    /// Loads the configuration of the current theme, and switches to the
+    /// classic theme if that is not possible.
+    ///
+    /// Returns `false` if not even the classic theme can be loaded, the game
+    /// quits then.
+    fn load_theme_configuration_or_fallback(&mut self) -> bool {
+        let Err(err) = self.load_theme_configuration_file() else {
+            return true;
+        };
+
+        let classic_theme_index = self.graphics.classic_theme_index;
+        if self.graphics.theme_list.as_ref().unwrap().current == classic_theme_index {
+            self.show_fatal_load_error(&err);
+            return false;
+        }
+        self.show_load_error(&err);
+
+        let all_themes = self.graphics.theme_list.as_mut().unwrap();
+
+        warn!(
+            "falling back to the classic theme instead of {}",
+            self.global.game_config.theme_name.to_string_lossy()
+        );
+        all_themes.current = classic_theme_index;
+        self.global
+            .game_config
+            .theme_name
+            .set(&all_themes.names[usize::from(classic_theme_index)]);
+
+        if let Err(err) = self.load_theme_configuration_file() {
+            self.show_fatal_load_error(&err);
+            return false;
+        }
+        true
     }
 
     /// This function resizes all blocks and structures involved in assembling
@@ -0,0 +1,103 @@
+//! The headless mode of the game: `freedroid --headless --frames <N>` on the
+//! command line, [`run_headless`] from other programs.
+//!
+//! The simulation runs the same frames as the game, without drawing them, so
+//! CI can check gameplay on machines without a display.  With a seed two runs
+//! give the same report.  SDL is still started, with its dummy video driver,
+//! because the timer and the game data live on top of it.
+
+use crate::{Data, defs::Status, error::LoadError, init::Opt, init_sdl};
+
+use log::info;
+use std::{fmt, ops::Not};
+
+/// The frame rate that is assumed while simulating, so that every frame
+/// advances the game by the same amount of time.
+const HEADLESS_FPS: f32 = 50.;
+
+/// The outcome of a headless run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessReport {
+    pub frames: u32,
+    pub score: f32,
+    pub influencer_energy: f32,
+    pub influencer_alive: bool,
+    pub enemys_left: usize,
+    pub mission_complete: bool,
+}
+
+impl fmt::Display for HeadlessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Frames simulated: {}", self.frames)?;
+        writeln!(f, "Score: {:.0}", self.score)?;
+        writeln!(
+            f,
+            "Influencer: {} (energy {:.1})",
+            if self.influencer_alive {
+                "alive"
+            } else {
+                "destroyed"
+            },
+            self.influencer_energy
+        )?;
+        writeln!(f, "Enemys left: {}", self.enemys_left)?;
+        write!(f, "Mission complete: {}", self.mission_complete)
+    }
+}
+
+/// Runs the game simulation without a window, pictures or sound.
+///
+/// The default ruleset and the mission of `opt` with its ship are loaded, then
+/// the frames of the game loop are run for `opt.frames` frames, or until the
+/// game is over.  Nothing is drawn and nothing is written to disk.  Other
+/// programs get their options from [`Opt::headless`].
+///
+/// SDL must be told to use its dummy video driver before this is called, see
+/// [`use_dummy_video_driver`].  SDL can only be started once in a process.
+///
+/// # Errors
+///
+/// Fails if the default ruleset or the mission cannot be loaded.
+pub fn run_headless(opt: &Opt) -> Result<HeadlessReport, LoadError> {
+    let sdl = init_sdl();
+    let mut data = Data::new(&sdl);
+    data.init_headless(opt)?;
+
+    info!("Headless simulation of {} frames started.", opt.frames);
+
+    let mut frames = 0;
+    while frames < opt.frames && data.game_over.not() && data.quit.get().not() {
+        // every frame advances the game by the same amount of time
+        data.main.f_p_sover1 = HEADLESS_FPS;
+        data.game_frame(true);
+        frames += 1;
+    }
+
+    info!("Headless simulation stopped after {} frames.", frames);
+
+    let influencer_alive = data.vars.me.status != Status::Terminated;
+    Ok(HeadlessReport {
+        frames,
+        score: data.main.real_score,
+        influencer_energy: data.vars.me.energy,
+        influencer_alive,
+        enemys_left: data
+            .main
+            .enemys
+            .iter()
+            .filter(|enemy| matches!(enemy.status, Status::Out | Status::Terminated).not())
+            .count(),
+        mission_complete: data.game_over && influencer_alive,
+    })
+}
+
+/// Makes SDL start without a window.
+///
+/// # Safety
+///
+/// No other thread may be running, see [`std::env::set_var`].
+pub unsafe fn use_dummy_video_driver() {
+    unsafe {
+        std::env::set_var("SDL_VIDEODRIVER", "dummy");
+    }
+}
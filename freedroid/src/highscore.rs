@@ -1,164 +1,289 @@
 use crate::{
-    array_c_string::ArrayCString,
     b_font::{char_width, font_height},
     defs::{
-        self, AssembleCombatWindowFlags, DATE_LEN, DisplayBannerFlags, GRAPHICS_DIR_C,
-        HS_BACKGROUND_FILE, HS_EMPTY_ENTRY, MAX_HIGHSCORES, MAX_NAME_LEN, Status, Themed,
+        self, AssembleCombatWindowFlags, Cmds, DATE_LEN, DisplayBannerFlags, GRAPHICS_DIR_C,
+        HS_BACKGROUND_FILE, HS_EMPTY_ENTRY, MAX_HIGHSCORES, MAX_NAME_LEN, STANDARD_MISSION, Status,
+        Themed,
     },
+    error::LoadError,
     graphics::Graphics,
+    map::write_file_atomically,
 };
 
 use log::{info, warn};
 use sdl::{Rect, convert::u8_to_usize};
+use serde::{Deserialize, Serialize};
 use std::{
-    array, fmt,
-    fs::File,
-    io::{BufReader, Read, Write},
+    array,
+    cmp::Reverse,
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
     mem::{self, align_of, size_of},
     path::Path,
     rc::Rc,
 };
 
+const HIGHSCORE_FILE: &str = "highscores.json";
+const HIGHSCORE_VERSION: u32 = 2;
+
+/// The file written by the older versions: a dump of the memory layout of
+/// the entries, which depends on the platform.
+const LEGACY_HIGHSCORE_FILE: &str = "highscores";
+const LEGACY_NAME_LEN: usize = u8_to_usize(MAX_NAME_LEN) + 5;
+const LEGACY_DATE_LEN: usize = u8_to_usize(DATE_LEN) + 5;
+const LEGACY_SCORE_OFFSET: usize = LEGACY_NAME_LEN + legacy_padding(LEGACY_NAME_LEN);
+const LEGACY_DATE_OFFSET: usize = LEGACY_SCORE_OFFSET + size_of::<i64>();
+const LEGACY_ENTRY_LEN: usize =
+    LEGACY_DATE_OFFSET + LEGACY_DATE_LEN + legacy_padding(LEGACY_DATE_LEN);
+
+type Entries = [Entry; u8_to_usize(MAX_HIGHSCORES)];
+
+/// The highscore tables, by mission name.
+type Tables = BTreeMap<String, Entries>;
+
 #[derive(Debug, Default)]
 pub struct Highscore {
-    pub entries: Option<[Entry; u8_to_usize(MAX_HIGHSCORES)]>,
+    tables: Tables,
+    /// The highscore file exists but it cannot be read, maybe because it has
+    /// been written by a newer version: it must not be overwritten.
+    keep_file: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entry {
-    name: ArrayCString<{ u8_to_usize(MAX_NAME_LEN) + 5 }>,
+    name: String,
     score: i64,
-    date: ArrayCString<{ u8_to_usize(DATE_LEN) + 5 }>,
+    date: String,
+    /// The fields below are unknown for the entries migrated from the legacy
+    /// highscore file.
+    mission: Option<String>,
+    /// The droid type the influencer had at the end of the game.
+    droid: Option<String>,
+    /// In seconds.
+    play_time: Option<f32>,
+    decks_cleared: Option<u8>,
+}
+
+/// The content of the highscore file.
+#[derive(Debug, Serialize, Deserialize)]
+struct HighscoreFile<T> {
+    version: u32,
+    tables: T,
+}
+
+/// The content of the highscore file before the scores were split by mission.
+#[derive(Debug, Deserialize)]
+struct HighscoreFileV1 {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionHeader {
+    version: u32,
 }
 
 impl Default for Entry {
     fn default() -> Self {
         Self {
-            name: ArrayCString::try_from(HS_EMPTY_ENTRY).unwrap(),
-            date: ArrayCString::try_from(" --- ").unwrap(),
+            name: HS_EMPTY_ENTRY.to_owned(),
             score: -1,
+            date: " --- ".to_owned(),
+            mission: None,
+            droid: None,
+            play_time: None,
+            decks_cleared: None,
         }
     }
 }
 
 impl Entry {
-    fn new<Name, Date>(name: Name, score: i64, date: Date) -> Self
-    where
-        Name: TryInto<ArrayCString<{ u8_to_usize(MAX_NAME_LEN) + 5 }>>,
-        Date: TryInto<ArrayCString<{ u8_to_usize(DATE_LEN) + 5 }>>,
-        Name::Error: fmt::Debug,
-        Date::Error: fmt::Debug,
-    {
+    fn from_legacy_record(record: &[u8]) -> Self {
+        let c_string = |bytes: &[u8]| {
+            let len = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..len]).into_owned()
+        };
+
         Self {
-            name: name.try_into().unwrap(),
-            date: date.try_into().unwrap(),
-            score,
+            name: c_string(&record[..LEGACY_NAME_LEN]),
+            score: i64::from_le_bytes(
+                record[LEGACY_SCORE_OFFSET..LEGACY_DATE_OFFSET]
+                    .try_into()
+                    .unwrap(),
+            ),
+            date: c_string(&record[LEGACY_DATE_OFFSET..][..LEGACY_DATE_LEN]),
+            mission: None,
+            droid: None,
+            play_time: None,
+            decks_cleared: None,
         }
     }
+}
 
-    pub const fn score_padding() -> usize {
-        let used_bytes = (u8_to_usize(MAX_NAME_LEN) + 5) % align_of::<i64>();
-        if used_bytes == 0 {
-            0
-        } else {
-            align_of::<i64>() - used_bytes
-        }
+const fn legacy_padding(used_bytes: usize) -> usize {
+    let used_bytes = used_bytes % align_of::<i64>();
+    if used_bytes == 0 {
+        0
+    } else {
+        align_of::<i64>() - used_bytes
     }
+}
 
-    pub const fn end_padding() -> usize {
-        let used_bytes = (u8_to_usize(DATE_LEN) + 5) % align_of::<i64>();
-        if used_bytes == 0 {
-            0
-        } else {
-            align_of::<i64>() - used_bytes
+fn empty_table() -> Entries {
+    array::from_fn(|_| Entry::default())
+}
+
+/// Keeps the best entries, sorted by score.
+fn make_table(mut entries: Vec<Entry>) -> Entries {
+    entries.sort_by_key(|entry| Reverse(entry.score));
+    let mut entries = entries.into_iter();
+    array::from_fn(|_| entries.next().unwrap_or_default())
+}
+
+fn parse_highscores(path: &Path, data: &[u8]) -> Result<Tables, LoadError> {
+    let invalid = |err: serde_json::Error| LoadError::invalid_data(path, err.to_string());
+
+    let VersionHeader { version } = serde_json::from_slice(data).map_err(invalid)?;
+    match version {
+        1 => {
+            // the scores without a mission come from the legacy file, when
+            // there was only the standard mission
+            let file: HighscoreFileV1 = serde_json::from_slice(data).map_err(invalid)?;
+            let mut tables = BTreeMap::<_, Vec<_>>::new();
+            for entry in file.entries {
+                let mission = entry.mission.as_deref().unwrap_or(STANDARD_MISSION);
+                tables.entry(mission.to_owned()).or_default().push(entry);
+            }
+            Ok(tables
+                .into_iter()
+                .map(|(mission, entries)| (mission, make_table(entries)))
+                .collect())
+        }
+        HIGHSCORE_VERSION => {
+            let file: HighscoreFile<BTreeMap<String, Vec<Entry>>> =
+                serde_json::from_slice(data).map_err(invalid)?;
+            Ok(file
+                .tables
+                .into_iter()
+                .map(|(mission, entries)| (mission, make_table(entries)))
+                .collect())
         }
+        _ => Err(LoadError::invalid_data(
+            path,
+            format!(
+                "highscore version {version} is not supported, expected at most version \
+                 {HIGHSCORE_VERSION}"
+            ),
+        )),
     }
 }
 
+fn parse_legacy_highscores(data: &[u8]) -> Entries {
+    let mut records = data.chunks_exact(LEGACY_ENTRY_LEN);
+    array::from_fn(|_| {
+        records
+            .next()
+            .map(Entry::from_legacy_record)
+            .unwrap_or_default()
+    })
+}
+
+/// The highscores are written to a temporary file first, so that a failed
+/// write does not lose the old ones.
+fn write_highscores(path: &Path, tables: &Tables) -> io::Result<()> {
+    let highscore_file = HighscoreFile {
+        version: HIGHSCORE_VERSION,
+        tables,
+    };
+    write_file_atomically(path, |file| {
+        serde_json::to_writer_pretty(&mut *file, &highscore_file)?;
+        writeln!(file)
+    })
+}
+
 impl Highscore {
     /// Set up a new highscore list: load from disk if found
-    fn init_highscores_inner(&mut self, config_dir: Option<&Path>) {
-        let file = config_dir.and_then(|config_dir| {
-            let path = config_dir.join("highscores");
-            let file = File::open(&path).ok().map(BufReader::new);
-            if file.as_ref().is_some() {
+    fn init_highscores_inner(&mut self, data_dir: Option<&Path>) {
+        self.keep_file = false;
+        self.tables = data_dir
+            .and_then(|data_dir| self.load_tables(data_dir))
+            .unwrap_or_default();
+    }
+
+    fn load_tables(&mut self, data_dir: &Path) -> Option<Tables> {
+        let path = data_dir.join(HIGHSCORE_FILE);
+        match fs::read(&path) {
+            Ok(data) => {
                 info!("Found highscore file {}", path.display());
-            } else {
-                warn!("No highscore file found...");
+                return parse_highscores(&path, &data)
+                    .map_err(|err| {
+                        warn!("Cannot use the highscore file: {err}");
+                        self.keep_file = true;
+                    })
+                    .ok();
             }
-            file
-        });
-
-        let highscores = match file {
-            Some(mut file) => array::from_fn(|_| {
-                const SCORE_PADDING: usize = Entry::score_padding();
-                const END_PADDING: usize = Entry::end_padding();
-
-                let mut name = ArrayCString::new();
-                name.use_slice_mut(|name| {
-                    file.read_exact(name)
-                        .expect("cannot read name from highscore file");
-                });
-
-                if SCORE_PADDING != 0 {
-                    file.seek_relative(SCORE_PADDING.try_into().unwrap())
-                        .expect("cannot skip padding bytes from highscore file");
-                }
-                let score = {
-                    let mut raw_score = [0u8; size_of::<i64>()];
-                    file.read_exact(&mut raw_score)
-                        .expect("unable to read score from highscore file");
-
-                    i64::from_le_bytes(raw_score)
-                };
-
-                let mut get_rest = || {
-                    let mut date = ArrayCString::new();
-                    date.use_slice_mut(|date| file.read_exact(date).ok())?;
-
-                    if END_PADDING != 0 {
-                        file.seek_relative(END_PADDING.try_into().unwrap()).ok()?;
-                    }
-
-                    Some(Entry { name, score, date })
-                };
-                get_rest().unwrap_or_default()
-            }),
-            None => array::from_fn(|_| Entry::default()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                warn!("Cannot read highscore file {}: {err}", path.display());
+                self.keep_file = true;
+                return None;
+            }
+        }
+
+        let legacy_path = data_dir.join(LEGACY_HIGHSCORE_FILE);
+        let Ok(data) = fs::read(&legacy_path) else {
+            warn!("No highscore file found...");
+            return None;
         };
-        self.entries = Some(highscores);
+        info!(
+            "Migrating legacy highscore file {} to {}",
+            legacy_path.display(),
+            path.display()
+        );
+        let tables = Tables::from([(STANDARD_MISSION.to_owned(), parse_legacy_highscores(&data))]);
+        if let Err(err) = write_highscores(&path, &tables) {
+            warn!(
+                "Failed to write highscores file '{}': {err}",
+                path.display()
+            );
+        }
+        Some(tables)
     }
 
-    fn save_highscores_inner(&mut self, config_dir: Option<&Path>) -> Result<(), ()> {
-        if let Some(config_dir) = config_dir {
-            let path = config_dir.join("highscores");
-            let Ok(mut file) = File::create(&path) else {
-                warn!("Failed to create highscores file. Giving up...");
-                return Err(());
-            };
-
-            for entry in self.entries.as_mut().unwrap().iter_mut() {
-                const SCORE_PADDING: usize = Entry::score_padding();
-                const END_PADDING: usize = Entry::end_padding();
-
-                file.write_all(entry.name.as_buffer_bytes()).unwrap();
-                if SCORE_PADDING != 0 {
-                    file.write_all(&[0; SCORE_PADDING]).unwrap();
-                }
-                file.write_all(&entry.score.to_le_bytes()).unwrap();
-                file.write_all(entry.date.as_buffer_bytes()).unwrap();
+    /// The table of `mission`, which is created if the mission has no scores
+    /// yet.
+    fn table_mut(&mut self, mission: &str) -> &mut Entries {
+        self.tables
+            .entry(mission.to_owned())
+            .or_insert_with(empty_table)
+    }
 
-                if END_PADDING != 0 {
-                    file.write_all(&[0; END_PADDING]).unwrap();
-                }
-            }
-            file.flush().unwrap();
-            info!("Successfully updated highscores file '{}'", path.display());
+    fn save_highscores_inner(&mut self, data_dir: Option<&Path>) -> Result<(), ()> {
+        let Some(data_dir) = data_dir else {
+            warn!("No data-dir found, cannot save highscores!");
+            return Err(());
+        };
+        let path = data_dir.join(HIGHSCORE_FILE);
+        if self.keep_file {
+            warn!(
+                "Highscores file '{}' could not be read, it is left untouched",
+                path.display()
+            );
+            return Err(());
+        }
 
-            Ok(())
-        } else {
-            warn!("No config-dir found, cannot save highscores!");
-            Err(())
+        match write_highscores(&path, &self.tables) {
+            Ok(()) => {
+                info!("Successfully updated highscores file '{}'", path.display());
+                Ok(())
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to write highscores file '{}': {err}",
+                    path.display()
+                );
+                Err(())
+            }
         }
     }
 }
@@ -175,12 +300,11 @@ impl crate::Data<'_> {
 
         self.vars.me.status = Status::Debriefing;
 
+        let mission = self.mission_name().to_owned();
         #[allow(clippy::cast_possible_truncation)]
         let Some(entry_pos) = self
             .highscore
-            .entries
-            .as_ref()
-            .unwrap()
+            .table_mut(&mission)
             .iter()
             .position(|entry| entry.score < score as i64)
         else {
@@ -254,22 +378,45 @@ impl crate::Data<'_> {
         let date = format!("{}", chrono::Local::now().format("%Y/%m/%d"));
 
         #[cfg(target_os = "android")]
-        #[allow(clippy::cast_possible_truncation)]
-        let new_entry = Entry::new("Player", score as i64, &*date);
+        let name = "Player".to_owned();
 
         #[cfg(not(target_os = "android"))]
+        let name = self
+            .get_string(MAX_NAME_LEN.into(), 2)
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+
         #[allow(clippy::cast_possible_truncation)]
-        let new_entry = Entry::new(
-            &*self.get_string(MAX_NAME_LEN.into(), 2).unwrap(),
-            score as i64,
-            &*date,
-        );
+        let new_entry = Entry {
+            name,
+            score: score as i64,
+            date,
+            mission: Some(mission.clone()),
+            droid: Some(
+                self.vars.droidmap[self.vars.me.ty.to_usize()]
+                    .druidname
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            play_time: Some(self.vars.me.timer),
+            decks_cleared: Some(
+                self.main
+                    .cur_ship
+                    .levels
+                    .iter()
+                    .filter(|level| level.empty)
+                    .count()
+                    .try_into()
+                    .unwrap(),
+            ),
+        };
 
         let mut ne_screen = self.graphics.ne_screen.take().unwrap();
         self.printf_sdl(&mut ne_screen, -1, -1, format_args!("\n"));
         self.graphics.ne_screen = Some(ne_screen);
 
-        self.highscore.entries.as_mut().unwrap()[entry_pos..]
+        self.highscore.table_mut(&mission)[entry_pos..]
             .iter_mut()
             .fold(new_entry, |new_entry, cur_entry| {
                 mem::replace(cur_entry, new_entry)
@@ -280,13 +427,13 @@ impl crate::Data<'_> {
 
     pub fn init_highscores(&mut self) {
         self.highscore
-            .init_highscores_inner(self.main.get_config_dir());
+            .init_highscores_inner(self.main.get_data_dir());
     }
 
     pub fn save_highscores(&mut self) -> i32 {
         match self
             .highscore
-            .save_highscores_inner(self.main.get_config_dir())
+            .save_highscores_inner(self.main.get_data_dir())
         {
             Ok(()) => defs::OK.into(),
             Err(()) => defs::ERR.into(),
@@ -295,7 +442,40 @@ impl crate::Data<'_> {
 
     /// Display the high scores of the single player game.
     /// This function is actually a submenu of the `MainMenu`.
+    ///
+    /// The table of the current mission is shown first, left and right switch
+    /// to the tables of the other missions.
     pub fn show_highscores(&mut self) {
+        let current_mission = match self.mission_name() {
+            "" => STANDARD_MISSION,
+            mission => mission,
+        }
+        .to_owned();
+        let mut missions: Vec<_> = self.highscore.tables.keys().cloned().collect();
+        let mut index = match missions.binary_search(&current_mission) {
+            Ok(index) => index,
+            Err(index) => {
+                missions.insert(index, current_mission);
+                index
+            }
+        };
+
+        loop {
+            self.show_highscore_table(&missions[index], missions.len() > 1);
+
+            let key = self.wait_for_key_pressed();
+            let key_cmds = &self.input.key_cmds;
+            if key_cmds[Cmds::Left as usize].contains(&key) {
+                index = index.checked_sub(1).unwrap_or(missions.len() - 1);
+            } else if key_cmds[Cmds::Right as usize].contains(&key) {
+                index = (index + 1) % missions.len();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn show_highscore_table(&mut self, mission: &str, can_switch: bool) {
         let fpath = Self::try_find_file_static(
             &self.global,
             &mut self.misc,
@@ -333,8 +513,26 @@ impl crate::Data<'_> {
             y0,
             format_args!("Top {MAX_HIGHSCORES}  scores\n"),
         );
+        let mission_title = mission.strip_suffix(".mission").unwrap_or(mission);
+        let mission_title = if can_switch {
+            format!("<  {mission_title}  >")
+        } else {
+            mission_title.to_owned()
+        };
+        Self::centered_print_string(
+            &self.b_font,
+            &mut self.font_owner,
+            &mut ne_screen,
+            y0 + i32::from(height),
+            format_args!("{mission_title}\n"),
+        );
 
-        let highscore_entries = self.highscore.entries.take().unwrap();
+        let highscore_entries = self
+            .highscore
+            .tables
+            .get(mission)
+            .cloned()
+            .unwrap_or_else(empty_table);
         for (i, highscore) in highscore_entries.iter().enumerate() {
             let i = i32::try_from(i).unwrap();
             self.print_string(
@@ -348,14 +546,14 @@ impl crate::Data<'_> {
                     &mut ne_screen,
                     x1,
                     y0 + (i + 2) * i32::from(height),
-                    format_args!("{}", highscore.date.to_str().unwrap()),
+                    format_args!("{}", highscore.date),
                 );
             }
             self.print_string(
                 &mut ne_screen,
                 x2,
                 y0 + (i + 2) * i32::from(height),
-                format_args!("{}", highscore.name.to_str().unwrap()),
+                format_args!("{}", highscore.name),
             );
             if highscore.score >= 0 {
                 self.print_string(
@@ -366,12 +564,69 @@ impl crate::Data<'_> {
                 );
             }
         }
-        self.highscore.entries = Some(highscore_entries);
         assert!(ne_screen.flip());
         self.graphics.ne_screen = Some(ne_screen);
 
-        self.wait_for_key_pressed();
-
         self.b_font.current_font = prev_font;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_legacy_record() {
+        let mut data = vec![0; LEGACY_ENTRY_LEN + 3];
+        data[..4].copy_from_slice(b"Paul");
+        data[LEGACY_SCORE_OFFSET..LEGACY_DATE_OFFSET].copy_from_slice(&1234i64.to_le_bytes());
+        data[LEGACY_DATE_OFFSET..][..10].copy_from_slice(b"2004/01/02");
+
+        let entries = parse_legacy_highscores(&data);
+        assert_eq!(entries[0].name, "Paul");
+        assert_eq!(entries[0].score, 1234);
+        assert_eq!(entries[0].date, "2004/01/02");
+        assert!(entries[0].mission.is_none());
+        // the truncated record is left empty
+        assert_eq!(entries[1].name, HS_EMPTY_ENTRY);
+        assert_eq!(entries[1].score, -1);
+    }
+
+    #[test]
+    fn parse_versions() {
+        let path = Path::new("highscores.json");
+        let data = br#"{
+            "version": 2,
+            "tables": {
+                "Paradroid.mission": [{ "name": "Paul", "score": 5, "date": "2024/01/03" }],
+                "Custom.mission": [{ "name": "Anna", "score": 7, "date": "2024/01/04" }]
+            }
+        }"#;
+        let tables = parse_highscores(path, data).unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables["Paradroid.mission"][0].name, "Paul");
+        assert_eq!(tables["Custom.mission"][0].name, "Anna");
+        assert_eq!(tables["Custom.mission"][1].score, -1);
+
+        let data = br#"{
+            "version": 1,
+            "entries": [
+                { "name": "Low", "score": 10, "date": "2024/01/01" },
+                {
+                    "name": "High", "score": 20, "date": "2024/01/02", "mission": "Paradroid.mission",
+                    "droid": "629", "play_time": 12.5, "decks_cleared": 3
+                }
+            ]
+        }"#;
+        let tables = parse_highscores(path, data).unwrap();
+        let entries = &tables["Paradroid.mission"];
+        assert_eq!(entries[0].name, "High");
+        assert_eq!(entries[0].decks_cleared, Some(3));
+        assert_eq!(entries[1].name, "Low");
+        assert!(entries[1].droid.is_none());
+        assert_eq!(entries[2].score, -1);
+
+        let data = br#"{ "version": 3, "tables": "something new" }"#;
+        assert!(parse_highscores(path, data).is_err());
+    }
+}
@@ -10,7 +10,7 @@ use crate::{
 
 use defs::{BLINKENERGY, Cmds, MAX_INFLU_POSITION_HISTORY, WAIT_TRANSFERMODE};
 use log::{info, warn};
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use std::ops::Not;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -201,7 +201,6 @@ impl crate::Data<'_> {
 
     pub fn explode_influencer(&mut self) {
         self.vars.me.status = Status::Terminated;
-        let mut rng = thread_rng();
 
         for i in 0..10 {
             /* freien Blast finden */
@@ -225,9 +224,9 @@ impl crate::Data<'_> {
             #[allow(clippy::cast_precision_loss)]
             {
                 blast.px = self.vars.me.pos.x - self.global.droid_radius / 2.
-                    + f32::from(rng.gen_range(0u8..=10)) * 0.05;
+                    + f32::from(self.rng.gen_range(0u8..=10)) * 0.05;
                 blast.py = self.vars.me.pos.y - self.global.droid_radius / 2.
-                    + f32::from(rng.gen_range(0u8..=10)) * 0.05;
+                    + f32::from(self.rng.gen_range(0u8..=10)) * 0.05;
                 blast.phase = 0.2 * i as f32;
             }
         }
@@ -408,7 +407,11 @@ impl crate::Data<'_> {
         if self.vars.me.energy <= 0. {
             if self.vars.me.ty == Droid::Droid001 {
                 self.vars.me.status = Status::Terminated;
-                self.thou_art_defeated();
+                if self.main.headless || self.main.play_test {
+                    self.game_over = true;
+                } else {
+                    self.thou_art_defeated();
+                }
                 return;
             }
 
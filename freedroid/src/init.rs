@@ -2,39 +2,42 @@ use crate::{
     array_c_string::ArrayCString,
     b_font::font_height,
     defs::{
-        self, AssembleCombatWindowFlags, BulletKind, DisplayBannerFlags, Droid, Explosion,
-        FD_DATADIR, GRAPHICS_DIR_C, LOCAL_DATADIR, MAP_DIR_C, MAXBULLETS, SHOW_WAIT, SLOWMO_FACTOR,
-        Status, TITLE_PIC_FILE, Themed, WAIT_AFTER_KILLED,
+        AssembleCombatWindowFlags, BulletKind, DisplayBannerFlags, Droid, Explosion,
+        GRAPHICS_DIR_C, MAP_DIR_C, MAXBULLETS, SHOW_WAIT, SLOWMO_FACTOR, STANDARD_MISSION, Status,
+        TITLE_PIC_FILE, Themed, WAIT_AFTER_KILLED,
     },
+    error::{DataProblem, LoadError, expect_entry},
+    find_subslice,
     global::Global,
     graphics::Graphics,
     misc::{
-        count_string_occurences, locate_string_in_data, read_and_malloc_string_from_data,
-        read_float_from_string, read_i32_from_string, read_string_from_string, read_u8_from_string,
-        read_u16_from_string,
+        count_string_occurences, locate_string_in_data, try_read_and_malloc_string_from_data,
+        try_read_float_from_string, try_read_i32_from_string, try_read_string_from_string,
+        try_read_u8_from_string, try_read_u16_from_string,
     },
     read_and_malloc_and_terminate_file,
     sound::Sound,
     split_at_subslice,
     structs::{DruidSpec, TextToBeDisplayed, ThemeList},
     text,
+    user_dirs::UserDirs,
 };
 
 #[cfg(target_os = "windows")]
 use crate::input::wait_for_key_pressed;
 
 use bstr::ByteSlice;
-use clap::{ArgAction, Parser, crate_version};
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, crate_version, error::ErrorKind};
 use log::{error, info, warn};
 use nom::Finish;
-use rand::{seq::IteratorRandom, thread_rng};
+use rand::{SeedableRng, rngs::StdRng, seq::IteratorRandom};
 use std::{
     array,
     ffi::CString,
     fs::{self, DirEntry},
     num::NonZeroU8,
     ops::Not,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 #[derive(Debug, Default)]
@@ -45,6 +48,33 @@ pub struct Init {
 }
 
 const MISSION_COMPLETE_BONUS: f32 = 1000.;
+const DEFAULT_HEADLESS_FRAMES: u32 = 1000;
+pub const END_OF_MISSION_DATA_STRING: &[u8] = b"*** End of Mission File ***";
+pub const GAMEDATANAME_INDICATION_STRING: &[u8] =
+    b"Physics ('game.dat') file to use for this mission: ";
+pub const SHIPNAME_INDICATION_STRING: &[u8] = b"Ship file to use for this mission: ";
+pub const ELEVATORNAME_INDICATION_STRING: &[u8] = b"Lift file to use for this mission: ";
+pub const CREWNAME_INDICATION_STRING: &[u8] = b"Crew file to use for this mission: ";
+pub const MISSION_ENDTITLE_SONG_NAME_STRING: &[u8] =
+    b"Song name to play in the end title if the mission is completed: ";
+pub const MISSION_ENDTITLE_BEGIN_STRING: &[u8] = b"** Beginning of End Title Text Section **";
+pub const MISSION_ENDTITLE_END_STRING: &[u8] = b"** End of End Title Text Section **";
+pub const MISSION_START_POINT_STRING: &[u8] = b"Possible Start Point : ";
+pub const END_OF_GAME_DAT_STRING: &[u8] = b"*** End of game.dat File ***";
+pub const ROBOT_SECTION_BEGIN_STRING: &[u8] = b"*** Start of Robot Data Section: ***";
+pub const NEW_ROBOT_BEGIN_STRING: &[u8] = b"** Start of new Robot: **";
+pub const NEW_BULLET_TYPE_BEGIN_STRING: &[u8] =
+    b"** Start of new bullet specification subsection **";
+pub const DROIDNAME_BEGIN_STRING: &[u8] = b"Droidname: ";
+pub const GUN_BEGIN_STRING: &[u8] = b"Weapon type this droid uses: ";
+pub const MISSION_BRIEFING_BEGIN_STRING: &[u8] = b"** Start of Mission Briefing Text Section **";
+const NEXT_BRIEFING_SUBSECTION_START_STRING: &[u8] = b"* New Mission Briefing Text Subsection *";
+const END_OF_BRIEFING_SUBSECTION_STRING: &[u8] = b"* End of Mission Briefing Text Subsection *";
+const MISSION_EXTENSION: &str = "mission";
+/// The number of bullet types, every one of them needs a specification in the
+/// ruleset.
+const NUMBER_OF_BULLET_TYPES: usize = BulletKind::LaserRifle as usize + 1;
+const MAX_MISSION_TITLE_LEN: usize = 30;
 const COPYRIGHT: &str = "\nCopyright (C) 2003-2018 Johannes Prix, Reinhard Prix\n\
 Freedroid comes with NO WARRANTY to the extent permitted by law.\n\
 You may redistribute copies of Freedroid under the terms of the\n\
@@ -86,7 +116,7 @@ pub fn win32_disclaimer() {
 #[derive(Parser)]
 #[clap(version = crate_version!(), long_version = COPYRIGHT)]
 #[allow(clippy::struct_excessive_bools)]
-struct Opt {
+pub struct Opt {
     #[clap(short, long, conflicts_with = "nosound")]
     sound: bool,
 
@@ -107,6 +137,94 @@ struct Opt {
 
     #[clap(short = 'r', long)]
     scale: Option<f32>,
+
+    /// Seed for the game randomness, the same seed and inputs give the same game
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Record the input of the game into the given file
+    #[clap(long, value_name = "FILE", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Replay the input recorded in the given file instead of reading the devices
+    #[clap(long, value_name = "FILE")]
+    replay: Option<PathBuf>,
+
+    /// Set a variable of the config file, like `--set Hog_Cpu=true`; the case and the
+    /// underscores of the key do not matter
+    #[clap(long = "set", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    set: Vec<(String, String)>,
+
+    /// Do not write the variables given with --set back to the config file, unless they are
+    /// changed in the menu
+    #[clap(long, requires = "set")]
+    no_save_set: bool,
+
+    /// Keep the configuration, the highscores and the saved games in this directory,
+    /// instead of the XDG directories
+    #[clap(long, value_name = "DIR", env = "FREEDROID_CONFIG_DIR")]
+    config_dir: Option<PathBuf>,
+
+    /// Search this directory for game data before the installed one, for
+    /// mods; can be given more than once, the first one is searched first
+    #[clap(long = "data-dir", value_name = "DIR")]
+    data_dirs: Vec<PathBuf>,
+
+    /// The mission file to play, as it is found in the map directory
+    #[clap(long, value_name = "FILE", default_value = STANDARD_MISSION)]
+    mission: String,
+
+    /// Run the game simulation without a window, pictures or sound
+    #[clap(long)]
+    pub headless: bool,
+
+    /// Number of frames to simulate in headless mode
+    #[clap(long, requires = "headless", default_value_t = DEFAULT_HEADLESS_FRAMES)]
+    pub frames: u32,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Opt {
+    /// The options of a headless run of `frames` frames of `mission`, the
+    /// randomness seeded with `seed`.  Everything else is left at the defaults
+    /// of the command line.
+    #[must_use]
+    pub fn headless(mission: &str, frames: u32, seed: Option<u64>) -> Self {
+        let mut opt = Self::parse_from(["freedroid", "--headless"]);
+        mission.clone_into(&mut opt.mission);
+        opt.frames = frames;
+        opt.seed = seed;
+        opt
+    }
+
+    /// The directories searched for the game data, in order.
+    pub fn data_search_path(&self) -> Vec<PathBuf> {
+        UserDirs::locate(self.config_dir.as_deref()).data_search_path(&self.data_dirs)
+    }
+}
+
+fn parse_key_value(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+        .ok_or_else(|| format!("\"{arg}\" is not in the form KEY=VALUE"))
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check a mission and all the files it refers to, and report every problem found
+    Validate {
+        /// The mission file, as it is found in the map directory
+        mission: String,
+    },
+    /// Convert a ship (.shp) or lift (.elv) file to a JSON document (.json), or back
+    Convert {
+        /// The file to read
+        input: PathBuf,
+        /// The file to write, its extension tells the format
+        output: PathBuf,
+    },
 }
 
 impl crate::Data<'_> {
@@ -132,7 +250,9 @@ impl crate::Data<'_> {
 
         // mission complete: all droids have been killed
         self.main.real_score += MISSION_COMPLETE_BONUS;
-        self.thou_art_victorious();
+        if self.main.headless.not() {
+            self.thou_art_victorious();
+        }
         self.game_over = true;
     }
 
@@ -206,17 +326,7 @@ impl crate::Data<'_> {
         self.wait_for_all_keys_released();
     }
 
-    /// This function initializes the whole Freedroid game.
-    ///
-    /// This must not be confused with initnewgame, which
-    /// only initializes a new mission for the game.
-    pub fn init_freedroid(&mut self) {
-        self.main.all_bullets.fill_with(|| None);
-
-        self.global.skip_a_few_frames = false;
-        self.vars.me.text_visible_time = 0.;
-        self.vars.me.text_to_be_displayed = TextToBeDisplayed::None;
-
+    fn set_default_game_config(&mut self) {
         // these are the hardcoded game-defaults, they can be overloaded by the config-file if present
         self.global.game_config.current_bg_music_volume = 0.3;
         self.global.game_config.current_sound_fx_volume = 0.5;
@@ -246,12 +356,53 @@ impl crate::Data<'_> {
 
         self.global.game_config.hog_cpu = false; // default to being nice
         self.global.game_config.empty_level_speedup = 1.0; // speed up *time* in empty levels (ie also energy-loss rate)
+    }
+
+    /// Initializes the game for the headless mode.
+    ///
+    /// Unlike `init_freedroid`, this neither opens a window nor loads any
+    /// pictures, fonts or sounds, and the config-file is left alone.  The
+    /// mission is loaded and started right away, without a briefing.
+    ///
+    /// Nothing can be simulated if the default ruleset or the mission cannot be
+    /// loaded, the error is returned then.
+    pub fn init_headless(&mut self, opt: &Opt) -> Result<(), LoadError> {
+        self.main.all_bullets.fill_with(|| None);
+        self.set_default_game_config();
+        self.parse_command_line(opt);
+
+        self.main.headless = true;
+        self.main.sound_on = false;
+
+        self.init_game_data(b"freedroid.ruleset")?;
+        self.global.current_combat_scale_factor = 1.;
+
+        let mission = self.main.next_mission.clone();
+        let (_, starting_level) = self.load_new_mission(&mission)?;
+        self.start_new_mission(starting_level);
+        Ok(())
+    }
+
+    /// This function initializes the whole Freedroid game.
+    ///
+    /// This must not be confused with initnewgame, which
+    /// only initializes a new mission for the game.
+    ///
+    /// The game quits if the data it cannot do without cannot be loaded.
+    pub fn init_freedroid(&mut self, opt: &Opt) {
+        self.main.all_bullets.fill_with(|| None);
+
+        self.global.skip_a_few_frames = false;
+        self.vars.me.text_visible_time = 0.;
+        self.vars.me.text_to_be_displayed = TextToBeDisplayed::None;
+
+        self.set_default_game_config();
 
         // now load saved options from the config-file
-        self.load_game_config();
+        self.load_game_config(opt.config_dir.as_deref());
 
         // call this _after_ default settings and LoadGameConfig() ==> cmdline has highest priority!
-        self.parse_command_line();
+        self.parse_command_line(opt);
 
         self.vars.user_rect = if self.global.game_config.full_user_rect.not() {
             self.vars.classic_user_rect
@@ -292,8 +443,11 @@ impl crate::Data<'_> {
 
         self.init_joy();
 
-        self.init_game_data(b"freedroid.ruleset"); // load the default ruleset. This can be
-        // overwritten from the mission file.
+        // load the default ruleset. This can be overwritten from the mission file.
+        if let Err(err) = self.init_game_data(b"freedroid.ruleset") {
+            self.show_fatal_load_error(&err);
+            return;
+        }
 
         self.update_progress(10);
 
@@ -305,18 +459,25 @@ impl crate::Data<'_> {
         self.init_highscores();
 
         /* Now fill the pictures correctly to the structs */
-        assert!(
-            self.init_pictures() != 0,
-            "Error in InitPictures reported back..."
-        );
+        if self.init_pictures() == 0 {
+            return;
+        }
 
         self.update_progress(100); // finished init
     }
 
     /// parse command line arguments and set global switches
     /// exit on error, so we don't need to return success status
-    fn parse_command_line(&mut self) {
-        let opt = Opt::parse();
+    fn parse_command_line(&mut self, opt: &Opt) {
+        if let Some(dir) = opt.data_dirs.iter().find(|dir| dir.is_dir().not()) {
+            Opt::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!("--data-dir: {} is not a directory", dir.display()),
+                )
+                .exit();
+        }
+        self.misc.data_dirs = opt.data_search_path();
 
         if opt.nosound {
             self.main.sound_on = false;
@@ -356,6 +517,37 @@ impl crate::Data<'_> {
         } else if opt.window {
             self.global.game_config.use_fullscreen = false;
         }
+
+        if let Err(err) =
+            self.find_data_file(opt.mission.as_bytes(), Some(MAP_DIR_C), Themed::NoTheme)
+        {
+            Opt::command()
+                .error(ErrorKind::InvalidValue, format!("--mission: {err}"))
+                .exit();
+        }
+        self.main.next_mission.clone_from(&opt.mission);
+
+        for (key, value) in &opt.set {
+            if let Err(err) = self.set_game_config_variable(key, value, opt.no_save_set.not()) {
+                Opt::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("--set {key}={value}: {err}"),
+                    )
+                    .exit();
+            }
+        }
+
+        let mut seed = opt.seed.unwrap_or_else(rand::random);
+        if let Some(path) = &opt.replay {
+            if let Some(replay_seed) = self.start_replay(path) {
+                seed = replay_seed;
+            }
+        } else if let Some(path) = &opt.record {
+            self.start_recording(path, seed);
+        }
+        info!("Random seed set to {}", seed);
+        self.rng = StdRng::seed_from_u64(seed);
     }
 
     /// find all themes and put them in `AllThemes`
@@ -365,6 +557,8 @@ impl crate::Data<'_> {
         // just to make sure...
         self.graphics.theme_list = None;
 
+        let data_dirs = self.misc.data_dirs.clone();
+
         let mut add_theme_from_dir = |dir_name: &Path| {
             let dir_name = dir_name.join("graphics");
             let read_dir = match fs::read_dir(&dir_name) {
@@ -392,8 +586,9 @@ impl crate::Data<'_> {
             }
         };
 
-        add_theme_from_dir(Path::new(FD_DATADIR));
-        add_theme_from_dir(Path::new(LOCAL_DATADIR));
+        for dir in data_dirs {
+            add_theme_from_dir(&dir);
+        }
 
         // now have a look at what we found:
         let all_themes = self.graphics.theme_list.as_mut().expect(
@@ -426,6 +621,8 @@ impl crate::Data<'_> {
             all_themes.current = classic_theme_index;
         }
 
+        self.graphics.classic_theme_index = classic_theme_index;
+
         info!(
             "Game starts using theme: {}",
             self.global.game_config.theme_name.to_str().unwrap()
@@ -526,6 +723,69 @@ impl crate::Data<'_> {
 
     #[allow(clippy::similar_names)]
     pub fn init_new_mission(&mut self, mission_name: &str) {
+        let oldfont = std::mem::replace(
+            &mut self.b_font.current_font,
+            self.global.font0_b_font.clone(),
+        );
+        let loaded_mission = self.load_new_mission(mission_name);
+        self.b_font.current_font = oldfont;
+
+        let (main_mission_data, starting_level) = match loaded_mission {
+            Ok(loaded_mission) => loaded_mission,
+            Err(err) => return self.init_standard_mission_instead(mission_name, &err),
+        };
+
+        //--------------------
+        // We start with doing the briefing things...
+        // Now we search for the beginning of the mission briefing big section NOT subsection.
+        // We display the title and explanation of controls and such...
+        if let Err(err) = main_mission_data.set_title(self) {
+            return self.init_standard_mission_instead(mission_name, &err);
+        }
+
+        if self.quit.get() {
+            return;
+        }
+
+        /* Den Banner fuer das Spiel anzeigen */
+        self.clear_graph_mem();
+        self.display_banner(None, None, DisplayBannerFlags::FORCE_UPDATE);
+
+        // Switch_Background_Music_To (COMBAT_BACKGROUND_MUSIC_SOUND);
+        Self::switch_background_music_to_static(
+            self.sound.as_mut().unwrap(),
+            &self.main,
+            &self.global,
+            &mut self.misc,
+            self.sdl,
+            Some(self.main.cur_level().background_song_name.to_bytes()),
+        );
+
+        self.start_new_mission(starting_level);
+
+        info!("done."); // this matches the printf at the beginning of this function
+    }
+
+    /// Tells the player that the mission `mission_name` cannot be played, and
+    /// initializes the standard mission instead.  The game quits if the
+    /// standard mission is the one that cannot be played.
+    fn init_standard_mission_instead(&mut self, mission_name: &str, err: &LoadError) {
+        if mission_name == STANDARD_MISSION {
+            self.show_fatal_load_error(err);
+            return;
+        }
+        self.show_load_error(err);
+
+        warn!("falling back to the standard mission {STANDARD_MISSION}");
+        self.init_new_mission(STANDARD_MISSION);
+    }
+
+    /// Loads the mission file together with its ship, lifts and crew, without
+    /// showing anything on screen.
+    ///
+    /// Returns the mission data, which is still needed for the briefing, and
+    /// the index of the starting level.
+    fn load_new_mission(&mut self, mission_name: &str) -> Result<(MainMissionData, u8), LoadError> {
         // We store the mission name in case the influ
         // gets destroyed so we know where to continue in
         // case the player doesn't want to return to the very beginning
@@ -566,15 +826,10 @@ impl crate::Data<'_> {
         //For that, we must get it into memory first.
         //The procedure is the same as with LoadShip
 
-        let oldfont = std::mem::replace(
-            &mut self.b_font.current_font,
-            self.global.font0_b_font.clone(),
-        );
-
-        let main_mission_data = MainMissionData::load(self, mission_name);
-        main_mission_data.init_game_data(self);
-        main_mission_data.load_ship(self);
-        main_mission_data.get_lift_connections(self);
+        let main_mission_data = MainMissionData::load(self, mission_name)?;
+        main_mission_data.init_game_data(self)?;
+        main_mission_data.load_ship(self)?;
+        main_mission_data.get_lift_connections(self)?;
 
         //--------------------
         // We also load the comment for the influencer to say at the beginning of the mission
@@ -584,9 +839,9 @@ impl crate::Data<'_> {
             TextToBeDisplayed::String(c"Ok. I'm on board.  Let's get to work."); // taken from Paradroid.mission
         self.vars.me.text_visible_time = 0.;
 
-        main_mission_data.get_crew(self);
-        main_mission_data.set_debriefeing_song(self);
-        let starting_level = main_mission_data.set_cur_level_index_x_y(self);
+        main_mission_data.get_crew(self)?;
+        main_mission_data.set_debriefeing_song(self)?;
+        let starting_level = main_mission_data.set_cur_level_index_x_y(self)?;
 
         /* Reactivate the light on alle Levels, that might have been dark */
         for level in &mut self.main.cur_ship.levels {
@@ -599,31 +854,13 @@ impl crate::Data<'_> {
         // At this point the position history can be initialized
         //
         self.init_influ_position_history();
-        self.b_font.current_font = oldfont;
-        //--------------------
-        // We start with doing the briefing things...
-        // Now we search for the beginning of the mission briefing big section NOT subsection.
-        // We display the title and explanation of controls and such...
-        main_mission_data.set_title(self);
 
-        if self.quit.get() {
-            return;
-        }
-
-        /* Den Banner fuer das Spiel anzeigen */
-        self.clear_graph_mem();
-        self.display_banner(None, None, DisplayBannerFlags::FORCE_UPDATE);
-
-        // Switch_Background_Music_To (COMBAT_BACKGROUND_MUSIC_SOUND);
-        Self::switch_background_music_to_static(
-            self.sound.as_mut().unwrap(),
-            &self.main,
-            &self.global,
-            &mut self.misc,
-            self.sdl,
-            Some(self.main.cur_level().background_song_name.to_bytes()),
-        );
+        Ok((main_mission_data, starting_level))
+    }
 
+    /// Distributes the crew on every level and puts a fresh influencer on the
+    /// starting level.
+    fn start_new_mission(&mut self, starting_level: u8) {
         for level_index in 0..self.main.cur_ship.levels.len() {
             self.main.cur_level_index = Some(level_index);
             self.shuffle_enemys();
@@ -642,30 +879,74 @@ impl crate::Data<'_> {
         self.vars.me.status = Status::Mobile;
         self.vars.me.phase = 0.;
         self.vars.me.timer = 0.0; // set clock to 0
+    }
 
-        info!("done."); // this matches the printf at the beginning of this function
+    /// Loads everything a mission needs except for its ship and its droids: the
+    /// game data, the lift connections, the crews of the levels and the
+    /// debriefing.
+    ///
+    /// This is used when a saved game is restored, because ship and droids are
+    /// taken from the savegame in that case.
+    pub fn load_mission_rules(&mut self, mission_name: &str) -> Result<(), LoadError> {
+        self.init.previous_mission_name.clear();
+        self.init.previous_mission_name.push_str(mission_name);
+
+        info!("Loading the rules of mission {}.", mission_name);
+
+        let main_mission_data = MainMissionData::load(self, mission_name)?;
+        main_mission_data.init_game_data(self)?;
+        main_mission_data.get_lift_connections(self)?;
+        main_mission_data.read_crew(self)?;
+        main_mission_data.set_debriefeing_song(self)?;
+        self.scale_ship_rects();
+
+        Ok(())
+    }
+
+    /// The name of the mission file that has been initialized last.
+    pub fn mission_name(&self) -> &str {
+        self.init.previous_mission_name.to_str().unwrap()
+    }
+
+    /// scale Level-pic rects
+    pub fn scale_ship_rects(&mut self) {
+        let scale = self.global.game_config.scale;
+        #[allow(clippy::float_cmp)]
+        if scale != 1.0 {
+            self.main.cur_ship.level_rects[0..self.main.cur_ship.levels.len()]
+                .iter_mut()
+                .flatten()
+                .for_each(|rect| rect.scale(scale));
+
+            for rect in &mut self.main.cur_ship.lift_row_rects {
+                rect.scale(scale);
+            }
+        }
     }
 
     ///  This function does the mission briefing.  It assumes,
     ///  that a mission file has already been successfully loaded into
     ///  memory.  The briefing texts will be extracted and displayed in
     ///  scrolling font.
-    pub fn title(&mut self, mission_briefing_data: &[u8]) {
+    ///
+    ///  The briefing is part of the mission file `fpath`, an error is
+    ///  returned if it is incomplete.
+    pub fn title(&mut self, mission_briefing_data: &[u8], fpath: &Path) -> Result<(), LoadError> {
         const BRIEFING_TITLE_PICTURE_STRING: &[u8] =
             b"The title picture in the graphics subdirectory for this mission is : ";
         const BRIEFING_TITLE_SONG_STRING: &[u8] =
             b"The title song in the sound subdirectory for this mission is : ";
-        const NEXT_BRIEFING_SUBSECTION_START_STRING: &[u8] =
-            b"* New Mission Briefing Text Subsection *";
-        const END_OF_BRIEFING_SUBSECTION_STRING: &[u8] =
-            b"* End of Mission Briefing Text Subsection *";
 
-        let song_title = read_string_from_string(mission_briefing_data, BRIEFING_TITLE_SONG_STRING);
+        let read_file_name = |label| {
+            try_read_string_from_string(mission_briefing_data, label)
+                .ok_or_else(|| LoadError::invalid_entry(fpath, label))
+        };
+        let song_title = read_file_name(BRIEFING_TITLE_SONG_STRING)?;
+        let pic_title = read_file_name(BRIEFING_TITLE_PICTURE_STRING)?;
+
         self.switch_background_music_to(Some(song_title));
 
         self.graphics.ne_screen.as_mut().unwrap().clear_clip_rect();
-        let pic_title =
-            read_string_from_string(mission_briefing_data, BRIEFING_TITLE_PICTURE_STRING);
         let image = Self::find_file_static(
             &self.global,
             &mut self.misc,
@@ -691,7 +972,12 @@ impl crate::Data<'_> {
                 &next_subsection_data[(pos + NEXT_BRIEFING_SUBSECTION_START_STRING.len())..];
             let this_text_length = next_subsection_data
                 .find(END_OF_BRIEFING_SUBSECTION_STRING)
-                .expect("Title: Unterminated Subsection in Mission briefing....Terminating...");
+                .ok_or_else(|| {
+                    LoadError::invalid_data(
+                        fpath,
+                        "a subsection of the mission briefing is not terminated",
+                    )
+                })?;
 
             let mut rect = self.vars.full_user_rect;
             rect.inc_x(10);
@@ -700,283 +986,68 @@ impl crate::Data<'_> {
                 break; // User pressed 'fire'
             }
         }
+
+        Ok(())
     }
 
     /// This function loads all the constant variables of the game from
     /// a dat file, that should be optimally human readable.
-    pub fn init_game_data(&mut self, data_filename: &[u8]) {
-        const END_OF_GAME_DAT_STRING: &[u8] = b"*** End of game.dat File ***";
-        const BLAST_ONE_TOTAL_AMOUNT_OF_TIME_STRING: &[u8] =
-            b"Time in seconds for the animation of blast one :";
-        const BLAST_TWO_TOTAL_AMOUNT_OF_TIME_STRING: &[u8] =
-            b"Time in seconds for the animation of blast one :";
-
+    pub fn init_game_data(&mut self, data_filename: &[u8]) -> Result<(), LoadError> {
         /* Read the whole game data to memory */
-        let fpath = self.find_file(data_filename, Some(MAP_DIR_C), Themed::NoTheme);
-        let fpath = Path::new(
-            fpath
-                .to_str()
-                .expect("unable to convert C string to UTF-8 string"),
-        );
+        let fpath = self.find_data_file(data_filename, Some(MAP_DIR_C), Themed::NoTheme)?;
 
-        let data = read_and_malloc_and_terminate_file(fpath, END_OF_GAME_DAT_STRING);
+        let data = read_and_malloc_and_terminate_file(&fpath, END_OF_GAME_DAT_STRING)?;
 
-        self.get_general_game_constants(&data);
-        self.get_robot_data(&data);
-        self.get_bullet_data(&data);
+        let mut problems = Vec::new();
+        let ruleset = read_ruleset(&data, &mut problems);
+        LoadError::first_problem(&fpath, problems)?;
+        self.set_ruleset(ruleset);
 
-        // Now we read in the total time amount for the blast animations
-        self.vars.blastmap[Explosion::Bulletblast].total_animation_time =
-            read_float_from_string(&data, BLAST_ONE_TOTAL_AMOUNT_OF_TIME_STRING);
-        self.vars.blastmap[Explosion::Druidblast {
-            from_influencer: false,
-        }]
-        .total_animation_time =
-            read_float_from_string(&data, BLAST_TWO_TOTAL_AMOUNT_OF_TIME_STRING);
+        Ok(())
     }
 
-    /// This function loads all the constant variables of the game from
-    /// a dat file, that should be optimally human readable.
-    pub fn get_robot_data(&mut self, data_slice: &[u8]) {
-        const MAXSPEED_CALIBRATOR_STRING: &[u8] = b"Common factor for all droids maxspeed values: ";
-        const ACCELERATION_CALIBRATOR_STRING: &[u8] =
-            b"Common factor for all droids acceleration values: ";
-        const MAXENERGY_CALIBRATOR_STRING: &[u8] =
-            b"Common factor for all droids maximum energy values: ";
-        const ENERGYLOSS_CALIBRATOR_STRING: &[u8] =
-            b"Common factor for all droids energyloss values: ";
-        const AGGRESSION_CALIBRATOR_STRING: &[u8] =
-            b"Common factor for all droids aggression values: ";
-        const SCORE_CALIBRATOR_STRING: &[u8] = b"Common factor for all droids score values: ";
-
-        const ROBOT_SECTION_BEGIN_STRING: &[u8] = b"*** Start of Robot Data Section: ***";
-        const NEW_ROBOT_BEGIN_STRING: &[u8] = b"** Start of new Robot: **";
-
-        let mut robot_slice =
-            &data_slice[locate_string_in_data(data_slice, ROBOT_SECTION_BEGIN_STRING)..];
-
-        info!("Starting to read robot calibration section");
-
-        // Now we read in the speed calibration factor for all droids
-        let maxspeed_calibrator = read_float_from_string(robot_slice, MAXSPEED_CALIBRATOR_STRING);
-
-        // Now we read in the acceleration calibration factor for all droids
-        let acceleration_calibrator =
-            read_float_from_string(robot_slice, ACCELERATION_CALIBRATOR_STRING);
-
-        // Now we read in the maxenergy calibration factor for all droids
-        let maxenergy_calibrator = read_float_from_string(robot_slice, MAXENERGY_CALIBRATOR_STRING);
-
-        // Now we read in the energy_loss calibration factor for all droids
-        let energyloss_calibrator =
-            read_float_from_string(robot_slice, ENERGYLOSS_CALIBRATOR_STRING);
-
-        // Now we read in the aggression calibration factor for all droids
-        let aggression_calibrator =
-            read_float_from_string(robot_slice, AGGRESSION_CALIBRATOR_STRING);
-
-        // Now we read in the score calibration factor for all droids
-        let score_calibrator = read_float_from_string(robot_slice, SCORE_CALIBRATOR_STRING);
-
-        info!("Starting to read Robot data...");
+    /// Puts the values of a ruleset in place.
+    fn set_ruleset(&mut self, ruleset: Ruleset) {
+        let Ruleset {
+            death_count_drain_speed,
+            alert_threshold,
+            alert_bonus_per_sec,
+            collision_lose_energy_calibrator,
+            blast_radius,
+            droid_radius,
+            blast_damage_per_second,
+            time_for_each_phase_of_door_movement,
+            blast_animation_times,
+            droids,
+            bullets,
+        } = ruleset;
+
+        self.main.death_count_drain_speed = death_count_drain_speed;
+        self.main.alert_threshold = alert_threshold;
+        self.main.alert_bonus_per_sec = alert_bonus_per_sec;
+        self.global.collision_lose_energy_calibrator = collision_lose_energy_calibrator;
+        self.global.blast_radius = blast_radius;
+        self.global.droid_radius = droid_radius;
+        self.global.blast_damage_per_second = blast_damage_per_second;
+        self.global.time_for_each_phase_of_door_movement = time_for_each_phase_of_door_movement;
 
         // cleanup if previously allocated:
         self.free_druidmap();
+        self.main.number_of_droid_types = droids.len().try_into().unwrap();
+        self.vars.droidmap = droids;
 
-        // At first, we must allocate memory for the droid specifications.
-        // How much?  That depends on the number of droids defined in freedroid.ruleset.
-        // So we have to count those first.  ok.  lets do it.
-        self.main.number_of_droid_types =
-            count_string_occurences(data_slice, NEW_ROBOT_BEGIN_STRING)
-                .try_into()
-                .unwrap();
-
-        // Now that we know how many robots are defined in freedroid.ruleset, we can allocate
-        // a fitting amount of memory.
-        self.vars
-            .droidmap
-            .reserve(self.main.number_of_droid_types.into());
-        info!(
-            "We have counted {} different druid types in the game data file.",
-            self.main.number_of_droid_types,
-        );
-        info!("MEMORY HAS BEEN ALLOCATED. THE READING CAN BEGIN.");
-
-        //Now we start to read the values for each robot:
-        //Of which parts is it composed, which stats does it have?
-        loop {
-            robot_slice = match robot_slice.find(NEW_ROBOT_BEGIN_STRING) {
-                Some(pos) => &robot_slice[(pos + 1)..],
-                None => break,
-            };
-
-            info!("Found another Robot specification entry!  Lets add that to the others!");
-            self.vars.droidmap.push(read_droid_spec(robot_slice));
-        }
-
-        info!("That must have been the last robot.  We're done reading the robot data.");
-        info!("Applying the calibration factors to all droids...");
-
-        #[allow(
-            clippy::cast_possible_truncation,
-            clippy::cast_precision_loss,
-            clippy::cast_sign_loss
-        )]
-        for droid in &mut self.vars.droidmap {
-            droid.maxspeed *= maxspeed_calibrator;
-            droid.accel *= acceleration_calibrator;
-            droid.maxenergy *= maxenergy_calibrator;
-            droid.lose_health *= energyloss_calibrator;
-
-            assert!(droid.score < 2i32.pow(f32::MANTISSA_DIGITS));
-
-            droid.aggression = (f32::from(droid.aggression) * aggression_calibrator) as u8;
-            droid.score = (droid.score as f32 * score_calibrator) as i32;
+        for (cur_bullet, bullet) in self.vars.bulletmap.iter_mut().zip(bullets) {
+            cur_bullet.recharging_time = bullet.recharging_time;
+            cur_bullet.speed = bullet.speed;
+            cur_bullet.damage = bullet.damage;
         }
-    }
-
-    /// This function reads in all the bullet data from the freedroid.ruleset file,
-    /// but IT DOES NOT LOAD THE FILE, IT ASSUMES IT IS ALREADY LOADED and
-    /// it only receives a pointer to the start of the bullet section from
-    /// the calling function.
-    pub fn get_bullet_data(&mut self, data_slice: &[u8]) {
-        // const BULLET_SECTION_BEGIN_STRING: &CStr = c"*** Start of Bullet Data Section: ***";
-        // const BULLET_SECTION_END_STRING: &CStr = c"*** End of Bullet Data Section: ***";
-        const NEW_BULLET_TYPE_BEGIN_STRING: &[u8] =
-            b"** Start of new bullet specification subsection **";
-
-        const BULLET_RECHARGE_TIME_BEGIN_STRING: &[u8] =
-            b"Time is takes to recharge this bullet/weapon in seconds :";
-        const BULLET_SPEED_BEGIN_STRING: &[u8] = b"Flying speed of this bullet type :";
-        const BULLET_DAMAGE_BEGIN_STRING: &[u8] = b"Damage cause by a hit of this bullet type :";
-        // #define BULLET_NUMBER_OF_PHASES_BEGIN_STRING "Number of different phases that were designed for this bullet type :"
-        // const BULLET_ONE_SHOT_ONLY_AT_A_TIME: &CStr =
-        //     c"Cannot fire until previous bullet has been deleted : ";
-
-        const BULLET_SPEED_CALIBRATOR_STRING: &[u8] =
-            b"Common factor for all bullet's speed values: ";
-        const BULLET_DAMAGE_CALIBRATOR_STRING: &[u8] =
-            b"Common factor for all bullet's damage values: ";
-
-        info!("Starting to read bullet data...");
-        //--------------------
-        // At first, we must allocate memory for the droid specifications.
-        // How much?  That depends on the number of droids defined in freedroid.ruleset.
-        // So we have to count those first.  ok.  lets do it.
-
-        let number_of_bullet_types =
-            count_string_occurences(data_slice, NEW_BULLET_TYPE_BEGIN_STRING);
-
-        assert_eq!(
-            number_of_bullet_types,
-            self.vars.bulletmap.len(),
-            "Invalid number of bullets type found in config",
-        );
-
-        //--------------------
-        // Now we start to read the values for each bullet type:
-        //
-        let mut bullet_slice = data_slice;
-        for cur_bullet in &mut self.vars.bulletmap {
-            bullet_slice = match bullet_slice.find(NEW_BULLET_TYPE_BEGIN_STRING) {
-                Some(pos) => &bullet_slice[(pos + 1)..],
-                None => break,
-            };
-
-            info!("Found another Bullet specification entry!  Lets add that to the others!");
-
-            // Now we read in the recharging time for this bullettype(=weapontype)
-            cur_bullet.recharging_time =
-                read_float_from_string(bullet_slice, BULLET_RECHARGE_TIME_BEGIN_STRING);
-
-            // Now we read in the maximal speed this type of bullet can go.
-            cur_bullet.speed = read_float_from_string(bullet_slice, BULLET_SPEED_BEGIN_STRING);
-
-            // Now we read in the damage this bullet can do
-            cur_bullet.damage = read_u16_from_string(bullet_slice, BULLET_DAMAGE_BEGIN_STRING);
-
-            // Now we read in the number of phases that are designed for this bullet type
-            // THIS IS NOW SPECIFIED IN THE THEME CONFIG FILE
-            // ReadValueFromString( BulletPointer ,  BULLET_NUMBER_OF_PHASES_BEGIN_STRING , "%d" ,
-            // &(*Bulletmap.add(BulletIndex)).phases , EndOfBulletData );
-        }
-
-        //--------------------
-        // Now that the detailed values for the bullets have been read in,
-        // we now read in the general calibration contants and after that
-        // the start to apply them right now, so they also take effect.
-
-        info!("Starting to read bullet calibration section");
 
-        // Now we read in the speed calibration factor for all bullets
-        let bullet_speed_calibrator =
-            read_float_from_string(data_slice, BULLET_SPEED_CALIBRATOR_STRING);
-
-        // Now we read in the damage calibration factor for all bullets
-        let bullet_damage_calibrator =
-            read_float_from_string(data_slice, BULLET_DAMAGE_CALIBRATOR_STRING);
-
-        // Now that all the calibrations factors have been read in, we can start to
-        // apply them to all the bullet types
-        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
-        for bullet in &mut self.vars.bulletmap {
-            bullet.speed *= bullet_speed_calibrator;
-            let damage = f32::from(bullet.damage) * bullet_damage_calibrator;
-            assert!(damage > 0., "calibrated damage must be greater than zero");
-
-            #[allow(clippy::cast_sign_loss)]
-            {
-                bullet.damage = damage as u16;
-            }
-        }
-    }
-
-    /// This function loads all the constant variables of the game from
-    /// a dat file, that should be optimally human readable.
-    pub fn get_general_game_constants(&mut self, data: &[u8]) {
-        // const CONSTANTS_SECTION_BEGIN_STRING: &CStr =
-        //     c"*** Start of General Game Constants Section: ***";
-        // const CONSTANTS_SECTION_END_STRING: &CStr =
-        //     c"*** End of General Game Constants Section: ***";
-        const COLLISION_LOSE_ENERGY_CALIBRATOR_STRING: &[u8] =
-            b"Energy-Loss-factor for Collisions of Influ with hostile robots=";
-        const BLAST_RADIUS_SPECIFICATION_STRING: &[u8] =
-            b"Radius of explosions (as far as damage is concerned) in multiples of tiles=";
-        const DROID_RADIUS_SPECIFICATION_STRING: &[u8] = b"Droid radius:";
-        const BLAST_DAMAGE_SPECIFICATION_STRING: &[u8] =
-            b"Amount of damage done by contact to a blast per second of time=";
-        const TIME_FOR_DOOR_MOVEMENT_SPECIFICATION_STRING: &[u8] =
-            b"Time for the doors to move by one subphase of their movement=";
-
-        const DEATHCOUNT_DRAIN_SPEED_STRING: &[u8] = b"Deathcount drain speed =";
-        const ALERT_THRESHOLD_STRING: &[u8] = b"First alert threshold =";
-        const ALERT_BONUS_PER_SEC_STRING: &[u8] = b"Alert bonus per second =";
-
-        info!("Starting to read contents of General Game Constants section");
-
-        // read in Alert-related parameters:
-        self.main.death_count_drain_speed =
-            read_float_from_string(data, DEATHCOUNT_DRAIN_SPEED_STRING);
-        self.main.alert_threshold = read_u16_from_string(data, ALERT_THRESHOLD_STRING);
-        self.main.alert_bonus_per_sec = read_float_from_string(data, ALERT_BONUS_PER_SEC_STRING);
-
-        // Now we read in the speed calibration factor for all bullets
-        self.global.collision_lose_energy_calibrator =
-            read_float_from_string(data, COLLISION_LOSE_ENERGY_CALIBRATOR_STRING);
-
-        // Now we read in the blast radius
-        self.global.blast_radius = read_float_from_string(data, BLAST_RADIUS_SPECIFICATION_STRING);
-
-        // Now we read in the druid 'radius' in x direction
-        self.global.droid_radius = read_float_from_string(data, DROID_RADIUS_SPECIFICATION_STRING);
-
-        // Now we read in the blast damage amount per 'second' of contact with the blast
-        self.global.blast_damage_per_second =
-            read_float_from_string(data, BLAST_DAMAGE_SPECIFICATION_STRING);
-
-        // Now we read in the time is takes for the door to move one phase
-        self.global.time_for_each_phase_of_door_movement =
-            read_float_from_string(data, TIME_FOR_DOOR_MOVEMENT_SPECIFICATION_STRING);
+        let [bullet_blast_time, droid_blast_time] = blast_animation_times;
+        self.vars.blastmap[Explosion::Bulletblast].total_animation_time = bullet_blast_time;
+        self.vars.blastmap[Explosion::Druidblast {
+            from_influencer: false,
+        }]
+        .total_animation_time = droid_blast_time;
     }
 
     /// Show end-screen
@@ -1080,93 +1151,173 @@ impl crate::Data<'_> {
     }
 }
 
+/// A mission file found in the map directory of a data dir.
+#[derive(Debug, Clone)]
+pub struct MissionInfo {
+    /// The file name, as given to `--mission`.
+    pub file: String,
+    /// The first line of the mission briefing.
+    pub title: CString,
+}
+
+/// Lists the missions of all the `data_dirs`, sorted by file name.  When the
+/// same file is in more than one of them, the first one found is kept, as
+/// `find_file` would load it.
+pub fn find_missions(data_dirs: &[PathBuf]) -> Vec<MissionInfo> {
+    let map_dir = MAP_DIR_C.to_str().unwrap();
+    let mut missions: Vec<MissionInfo> = Vec::new();
+    for datadir in data_dirs {
+        let Ok(entries) = fs::read_dir(datadir.join(map_dir)) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != MISSION_EXTENSION)
+            {
+                continue;
+            }
+            let Some(file) = path.file_name().and_then(|file| file.to_str()) else {
+                continue;
+            };
+            if missions.iter().any(|mission| mission.file == file) {
+                continue;
+            }
+
+            let title = fs::read(&path)
+                .ok()
+                .and_then(|mission| mission_title(&mission))
+                .unwrap_or_else(|| {
+                    let stem = file.strip_suffix(MISSION_EXTENSION).unwrap_or(file);
+                    CString::new(stem.trim_end_matches('.')).unwrap()
+                });
+            missions.push(MissionInfo {
+                file: file.to_owned(),
+                title,
+            });
+        }
+    }
+
+    missions.sort_unstable_by(|a, b| a.file.cmp(&b.file));
+    missions
+}
+
+/// The first line of the first briefing subsection, which names the mission.
+fn mission_title(mission: &[u8]) -> Option<CString> {
+    let (_, briefing) = split_at_subslice(mission, MISSION_BRIEFING_BEGIN_STRING)?;
+    let (_, subsection) = split_at_subslice(briefing, NEXT_BRIEFING_SUBSECTION_START_STRING)?;
+    let subsection = find_subslice(subsection, END_OF_BRIEFING_SUBSECTION_STRING)
+        .map_or(subsection, |end| &subsection[..end]);
+    let line = subsection
+        .lines()
+        .map(ByteSlice::trim)
+        .find(|line| line.is_empty().not())?;
+
+    let title: String = line
+        .to_str_lossy()
+        .chars()
+        .filter(|&c| c != '\0')
+        .take(MAX_MISSION_TITLE_LEN)
+        .collect();
+    Some(CString::new(title).unwrap())
+}
+
 #[derive(Debug)]
-struct MainMissionData(Box<[u8]>);
+struct MainMissionData {
+    data: Box<[u8]>,
+    fpath: PathBuf,
+}
 
 impl MainMissionData {
-    fn load(data: &mut crate::Data<'_>, mission_name: &str) -> Self {
-        const END_OF_MISSION_DATA_STRING: &[u8] = b"*** End of Mission File ***";
-
+    fn load(data: &mut crate::Data<'_>, mission_name: &str) -> Result<Self, LoadError> {
         /* Read the whole mission data to memory */
-        let fpath = data.find_file(mission_name.as_bytes(), Some(MAP_DIR_C), Themed::NoTheme);
-        let fpath = Path::new(
-            fpath
-                .to_str()
-                .expect("Unable to convert C string to UTF-8 string"),
-        );
+        let fpath =
+            data.find_data_file(mission_name.as_bytes(), Some(MAP_DIR_C), Themed::NoTheme)?;
+
+        let mission_data = read_and_malloc_and_terminate_file(&fpath, END_OF_MISSION_DATA_STRING)?;
+        if find_subslice(&mission_data, MISSION_BRIEFING_BEGIN_STRING).is_none() {
+            return Err(LoadError::invalid_entry(
+                &fpath,
+                MISSION_BRIEFING_BEGIN_STRING,
+            ));
+        }
 
-        let mission_data = read_and_malloc_and_terminate_file(fpath, END_OF_MISSION_DATA_STRING);
-        Self(mission_data)
+        Ok(Self {
+            data: mission_data,
+            fpath,
+        })
     }
 
-    fn init_game_data(&self, data: &mut crate::Data<'_>) {
-        const GAMEDATANAME_INDICATION_STRING: &[u8] =
-            b"Physics ('game.dat') file to use for this mission: ";
+    fn read_file_name(&self, label: &[u8]) -> Result<&[u8], LoadError> {
+        try_read_string_from_string(&self.data, label)
+            .ok_or_else(|| LoadError::invalid_entry(&self.fpath, label))
+    }
 
-        let indication = read_string_from_string(&self.0, GAMEDATANAME_INDICATION_STRING);
+    fn init_game_data(&self, data: &mut crate::Data<'_>) -> Result<(), LoadError> {
+        let indication = self.read_file_name(GAMEDATANAME_INDICATION_STRING)?;
 
-        data.init_game_data(indication);
+        data.init_game_data(indication)
     }
 
-    fn load_ship(&self, data: &mut crate::Data<'_>) {
-        const SHIPNAME_INDICATION_STRING: &[u8] = b"Ship file to use for this mission: ";
-        let indication = read_string_from_string(&self.0, SHIPNAME_INDICATION_STRING);
+    fn load_ship(&self, data: &mut crate::Data<'_>) -> Result<(), LoadError> {
+        let indication = self.read_file_name(SHIPNAME_INDICATION_STRING)?;
 
-        assert!(
-            data.load_ship(indication) != defs::ERR.into(),
-            "Error in LoadShip"
-        );
+        data.load_ship(indication)
     }
 
-    fn get_lift_connections(&self, data: &mut crate::Data<'_>) {
-        const ELEVATORNAME_INDICATION_STRING: &[u8] = b"Lift file to use for this mission: ";
-        let indication = read_string_from_string(&self.0, ELEVATORNAME_INDICATION_STRING);
+    fn get_lift_connections(&self, data: &mut crate::Data<'_>) -> Result<(), LoadError> {
+        let indication = self.read_file_name(ELEVATORNAME_INDICATION_STRING)?;
 
-        assert!(
-            data.get_lift_connections(indication) != defs::ERR.into(),
-            "Error in GetLiftConnections"
-        );
+        data.get_lift_connections(indication)
     }
 
-    fn get_crew(&self, data: &mut crate::Data<'_>) {
-        const CREWNAME_INDICATION_STRING: &[u8] = b"Crew file to use for this mission: ";
-        let indication = read_string_from_string(&self.0, CREWNAME_INDICATION_STRING);
+    fn crew_file_name(&self) -> Result<&[u8], LoadError> {
+        self.read_file_name(CREWNAME_INDICATION_STRING)
+    }
+
+    fn get_crew(&self, data: &mut crate::Data<'_>) -> Result<(), LoadError> {
+        let indication = self.crew_file_name()?;
 
         /* initialize enemys according to crew file */
         // WARNING!! THIS REQUIRES THE freedroid.ruleset FILE TO BE READ ALREADY, BECAUSE
         // ROBOT SPECIFICATIONS ARE ALREADY REQUIRED HERE!!!!!
-        assert!(
-            data.get_crew(indication) != defs::ERR.into(),
-            "InitNewGame(): Initialization of enemys failed."
-        );
+        data.get_crew(indication)
     }
 
-    fn set_debriefeing_song(&self, data: &mut crate::Data<'_>) {
-        const MISSION_ENDTITLE_SONG_NAME_STRING: &[u8] =
-            b"Song name to play in the end title if the mission is completed: ";
-        const MISSION_ENDTITLE_BEGIN_STRING: &[u8] = b"** Beginning of End Title Text Section **";
-        const MISSION_ENDTITLE_END_STRING: &[u8] = b"** End of End Title Text Section **";
+    fn read_crew(&self, data: &mut crate::Data<'_>) -> Result<(), LoadError> {
+        let indication = self.crew_file_name()?;
 
-        let song_name = read_string_from_string(&self.0, MISSION_ENDTITLE_SONG_NAME_STRING);
+        // the droids themselves are taken from the savegame, but the level
+        // editor needs the crews
+        data.read_crew(indication)
+    }
+
+    fn set_debriefeing_song(&self, data: &mut crate::Data<'_>) -> Result<(), LoadError> {
+        let song_name = self.read_file_name(MISSION_ENDTITLE_SONG_NAME_STRING)?;
         data.init.debriefing_song.set_slice(song_name);
 
-        data.init.debriefing_text = read_and_malloc_string_from_data(
-            &self.0,
+        data.init.debriefing_text = try_read_and_malloc_string_from_data(
+            &self.data,
             MISSION_ENDTITLE_BEGIN_STRING,
             MISSION_ENDTITLE_END_STRING,
-        );
-    }
+        )
+        .ok_or_else(|| LoadError::invalid_entry(&self.fpath, MISSION_ENDTITLE_BEGIN_STRING))?;
 
-    fn set_cur_level_index_x_y(&self, data: &mut crate::Data<'_>) -> u8 {
-        const MISSION_START_POINT_STRING: &[u8] = b"Possible Start Point : ";
+        Ok(())
+    }
 
-        let mut rng = thread_rng();
-        let number_of_start_points = count_string_occurences(&self.0, MISSION_START_POINT_STRING);
+    fn set_cur_level_index_x_y(&self, data: &mut crate::Data<'_>) -> Result<u8, LoadError> {
+        let number_of_start_points =
+            count_string_occurences(&self.data, MISSION_START_POINT_STRING);
 
-        assert!(
-            number_of_start_points != 0,
-            "NOT EVEN ONE SINGLE STARTING POINT ENTRY FOUND!  TERMINATING!"
-        );
+        if number_of_start_points == 0 {
+            return Err(LoadError::invalid_entry(
+                &self.fpath,
+                MISSION_START_POINT_STRING,
+            ));
+        }
 
         info!(
             "Found {} different starting points for the mission in the mission file.",
@@ -1174,45 +1325,40 @@ impl MainMissionData {
         );
 
         let start_point_index = self
-            .0
+            .data
             .windows(MISSION_START_POINT_STRING.len())
             .enumerate()
             .filter(|&(_, slice)| slice == MISSION_START_POINT_STRING)
             .map(|(index, _)| index)
-            .choose(&mut rng)
+            .choose(&mut data.rng)
             .unwrap();
 
-        let start_point_slice = split_at_subslice(
-            &self.0[(start_point_index + MISSION_START_POINT_STRING.len())..],
-            b"Level=",
-        )
-        .expect("unable to find Level parameter in mission data")
-        .1;
-        let starting_level = nom::character::complete::u8::<_, ()>(start_point_slice)
-            .finish()
-            .unwrap()
-            .1;
+        let start_point_slice =
+            &self.data[(start_point_index + MISSION_START_POINT_STRING.len())..];
+        let [starting_level, x_pos, y_pos] = read_start_point(start_point_slice, &self.fpath)?;
+        let starting_level = u8::try_from(starting_level)
+            .ok()
+            .filter(|&level| usize::from(level) < data.main.cur_ship.levels.len())
+            .ok_or_else(|| {
+                LoadError::invalid_data(
+                    &self.fpath,
+                    format!("the starting level {starting_level} does not exist"),
+                )
+            })?;
         data.main.cur_level_index = Some(usize::from(starting_level));
 
-        let start_point_slice = split_at_subslice(start_point_slice, b"XPos=").unwrap().1;
-        let x_pos = nom::character::complete::i32::<_, ()>(start_point_slice)
-            .finish()
-            .expect("unable to find XPos parameter in mission data")
-            .1;
-        assert!(x_pos <= 2i32.pow(f32::MANTISSA_DIGITS));
-        #[allow(clippy::cast_precision_loss)]
+        if x_pos.abs() > 2i32.pow(f32::MANTISSA_DIGITS)
+            || y_pos.abs() > 2i32.pow(f32::MANTISSA_DIGITS)
         {
-            data.vars.me.pos.x = x_pos as f32;
+            return Err(LoadError::invalid_data(
+                &self.fpath,
+                format!("the starting position {x_pos}, {y_pos} is out of range"),
+            ));
         }
 
-        let start_point_slice = split_at_subslice(start_point_slice, b"YPos=").unwrap().1;
-        let y_pos = nom::character::complete::i32::<_, ()>(start_point_slice)
-            .finish()
-            .expect("unable to find YPos parameter in mission data")
-            .1;
-
         #[allow(clippy::cast_precision_loss)]
         {
+            data.vars.me.pos.x = x_pos as f32;
             data.vars.me.pos.y = y_pos as f32;
         }
 
@@ -1221,27 +1367,332 @@ impl MainMissionData {
             starting_level, x_pos, y_pos,
         );
 
-        starting_level
+        Ok(starting_level)
+    }
+
+    fn set_title(&self, data: &mut crate::Data<'_>) -> Result<(), LoadError> {
+        // The briefing section has already been checked in `load`
+        let briefing_section_pos = locate_string_in_data(&self.data, MISSION_BRIEFING_BEGIN_STRING);
+        data.title(&self.data[briefing_section_pos..], &self.fpath)
     }
+}
+
+/// Reads the level and the position of a start point from the data following
+/// its `MISSION_START_POINT_STRING`.
+pub fn read_start_point(slice: &[u8], fpath: &Path) -> Result<[i32; 3], LoadError> {
+    let (slice, level) = read_start_point_value(slice, b"Level=", fpath)?;
+    let (slice, x_pos) = read_start_point_value(slice, b"XPos=", fpath)?;
+    let (_, y_pos) = read_start_point_value(slice, b"YPos=", fpath)?;
+    Ok([level, x_pos, y_pos])
+}
+
+/// Reads one of the `Level=`, `XPos=` and `YPos=` values of a start point, and returns the
+/// data after it together with the value.
+fn read_start_point_value<'a>(
+    slice: &'a [u8],
+    label: &[u8],
+    fpath: &Path,
+) -> Result<(&'a [u8], i32), LoadError> {
+    split_at_subslice(slice, label)
+        .and_then(|(_, slice)| nom::character::complete::i32::<_, ()>(slice).finish().ok())
+        .ok_or_else(|| LoadError::invalid_entry(fpath, label))
+}
 
-    fn set_title(&self, data: &mut crate::Data<'_>) {
-        const MISSION_BRIEFING_BEGIN_STRING: &[u8] =
-            b"** Start of Mission Briefing Text Section **";
+/// The values of a ruleset, as read by [`read_ruleset`], with the calibration
+/// factors applied.
+#[derive(Debug)]
+pub struct Ruleset {
+    death_count_drain_speed: f32,
+    alert_threshold: u16,
+    alert_bonus_per_sec: f32,
+    collision_lose_energy_calibrator: f32,
+    blast_radius: f32,
+    droid_radius: f32,
+    blast_damage_per_second: f32,
+    time_for_each_phase_of_door_movement: f32,
+    blast_animation_times: [f32; 2],
+    pub droids: Vec<DruidSpec>,
+    bullets: Vec<BulletValues>,
+}
+
+/// The values of a bullet type that are specified in the ruleset.
+#[derive(Debug)]
+struct BulletValues {
+    recharging_time: f32,
+    speed: f32,
+    damage: u16,
+}
 
-        let briefing_section_pos = locate_string_in_data(&self.0, MISSION_BRIEFING_BEGIN_STRING);
-        data.title(&self.0[briefing_section_pos..]);
+/// Reads the value following `label` in `data[from..]` with `read`, a missing
+/// or invalid value is recorded at `from`.
+fn read_entry<T>(
+    data: &[u8],
+    from: usize,
+    label: &[u8],
+    read: fn(&[u8], &[u8]) -> Option<T>,
+    problems: &mut Vec<DataProblem>,
+) -> Option<T> {
+    expect_entry(read(&data[from..], label), label, from, problems)
+}
+
+/// Reads a float value that must be present in `data[from..]`, a missing one
+/// is taken as zero.
+fn read_float_entry(
+    data: &[u8],
+    from: usize,
+    label: &[u8],
+    problems: &mut Vec<DataProblem>,
+) -> f32 {
+    read_entry(data, from, label, try_read_float_from_string, problems).unwrap_or_default()
+}
+
+/// Reads the values of a ruleset from the data of a ruleset file, the
+/// problems found are added to `problems`.
+///
+/// The values that cannot be read are left at zero.
+pub fn read_ruleset(data: &[u8], problems: &mut Vec<DataProblem>) -> Ruleset {
+    const COLLISION_LOSE_ENERGY_CALIBRATOR_STRING: &[u8] =
+        b"Energy-Loss-factor for Collisions of Influ with hostile robots=";
+    const BLAST_RADIUS_SPECIFICATION_STRING: &[u8] =
+        b"Radius of explosions (as far as damage is concerned) in multiples of tiles=";
+    const DROID_RADIUS_SPECIFICATION_STRING: &[u8] = b"Droid radius:";
+    const BLAST_DAMAGE_SPECIFICATION_STRING: &[u8] =
+        b"Amount of damage done by contact to a blast per second of time=";
+    const TIME_FOR_DOOR_MOVEMENT_SPECIFICATION_STRING: &[u8] =
+        b"Time for the doors to move by one subphase of their movement=";
+
+    const DEATHCOUNT_DRAIN_SPEED_STRING: &[u8] = b"Deathcount drain speed =";
+    const ALERT_THRESHOLD_STRING: &[u8] = b"First alert threshold =";
+    const ALERT_BONUS_PER_SEC_STRING: &[u8] = b"Alert bonus per second =";
+
+    const BLAST_ONE_TOTAL_AMOUNT_OF_TIME_STRING: &[u8] =
+        b"Time in seconds for the animation of blast one :";
+    const BLAST_TWO_TOTAL_AMOUNT_OF_TIME_STRING: &[u8] =
+        b"Time in seconds for the animation of blast one :";
+
+    info!("Starting to read contents of General Game Constants section");
+
+    let droids = expect_entry(
+        find_subslice(data, ROBOT_SECTION_BEGIN_STRING),
+        ROBOT_SECTION_BEGIN_STRING,
+        0,
+        problems,
+    )
+    .map_or_else(Vec::new, |robots_start| {
+        read_droids(data, robots_start, problems)
+    });
+
+    Ruleset {
+        // read in Alert-related parameters:
+        death_count_drain_speed: read_float_entry(data, 0, DEATHCOUNT_DRAIN_SPEED_STRING, problems),
+        alert_threshold: read_entry(
+            data,
+            0,
+            ALERT_THRESHOLD_STRING,
+            try_read_u16_from_string,
+            problems,
+        )
+        .unwrap_or_default(),
+        alert_bonus_per_sec: read_float_entry(data, 0, ALERT_BONUS_PER_SEC_STRING, problems),
+        collision_lose_energy_calibrator: read_float_entry(
+            data,
+            0,
+            COLLISION_LOSE_ENERGY_CALIBRATOR_STRING,
+            problems,
+        ),
+        blast_radius: read_float_entry(data, 0, BLAST_RADIUS_SPECIFICATION_STRING, problems),
+        droid_radius: read_float_entry(data, 0, DROID_RADIUS_SPECIFICATION_STRING, problems),
+        blast_damage_per_second: read_float_entry(
+            data,
+            0,
+            BLAST_DAMAGE_SPECIFICATION_STRING,
+            problems,
+        ),
+        time_for_each_phase_of_door_movement: read_float_entry(
+            data,
+            0,
+            TIME_FOR_DOOR_MOVEMENT_SPECIFICATION_STRING,
+            problems,
+        ),
+        // Now we read in the total time amount for the blast animations
+        blast_animation_times: [
+            read_float_entry(data, 0, BLAST_ONE_TOTAL_AMOUNT_OF_TIME_STRING, problems),
+            read_float_entry(data, 0, BLAST_TWO_TOTAL_AMOUNT_OF_TIME_STRING, problems),
+        ],
+        droids,
+        bullets: read_bullets(data, problems),
     }
 }
 
-#[allow(clippy::similar_names)]
-fn read_droid_spec(robot_slice: &[u8]) -> DruidSpec {
-    const DROIDNAME_BEGIN_STRING: &[u8] = b"Droidname: ";
+/// Reads the droids of the robot section starting at `robots_start`, and
+/// applies the calibration factors to them.
+fn read_droids(
+    data: &[u8],
+    robots_start: usize,
+    problems: &mut Vec<DataProblem>,
+) -> Vec<DruidSpec> {
+    const MAXSPEED_CALIBRATOR_STRING: &[u8] = b"Common factor for all droids maxspeed values: ";
+    const ACCELERATION_CALIBRATOR_STRING: &[u8] =
+        b"Common factor for all droids acceleration values: ";
+    const MAXENERGY_CALIBRATOR_STRING: &[u8] =
+        b"Common factor for all droids maximum energy values: ";
+    const ENERGYLOSS_CALIBRATOR_STRING: &[u8] = b"Common factor for all droids energyloss values: ";
+    const AGGRESSION_CALIBRATOR_STRING: &[u8] = b"Common factor for all droids aggression values: ";
+    const SCORE_CALIBRATOR_STRING: &[u8] = b"Common factor for all droids score values: ";
+
+    info!("Starting to read robot calibration section");
+
+    let maxspeed_calibrator =
+        read_float_entry(data, robots_start, MAXSPEED_CALIBRATOR_STRING, problems);
+    let acceleration_calibrator =
+        read_float_entry(data, robots_start, ACCELERATION_CALIBRATOR_STRING, problems);
+    let maxenergy_calibrator =
+        read_float_entry(data, robots_start, MAXENERGY_CALIBRATOR_STRING, problems);
+    let energyloss_calibrator =
+        read_float_entry(data, robots_start, ENERGYLOSS_CALIBRATOR_STRING, problems);
+    let aggression_calibrator =
+        read_float_entry(data, robots_start, AGGRESSION_CALIBRATOR_STRING, problems);
+    let score_calibrator = read_float_entry(data, robots_start, SCORE_CALIBRATOR_STRING, problems);
+
+    info!("Starting to read Robot data...");
+
+    //Now we start to read the values for each robot:
+    //Of which parts is it composed, which stats does it have?
+    let mut droids = Vec::new();
+    for offset in data[robots_start..].find_iter(NEW_ROBOT_BEGIN_STRING) {
+        info!("Found another Robot specification entry!  Lets add that to the others!");
+        droids.extend(read_droid_spec(data, robots_start + offset, problems));
+    }
+
+    info!(
+        "We have counted {} different druid types in the game data file.",
+        droids.len(),
+    );
+    if droids.is_empty() {
+        problems.push(DataProblem::at(robots_start, "no droid types are defined"));
+    } else if u8::try_from(droids.len()).is_err() {
+        problems.push(DataProblem::at(robots_start, "too many droid types"));
+    }
+
+    info!("That must have been the last robot.  We're done reading the robot data.");
+    info!("Applying the calibration factors to all droids...");
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    for droid in &mut droids {
+        droid.maxspeed *= maxspeed_calibrator;
+        droid.accel *= acceleration_calibrator;
+        droid.maxenergy *= maxenergy_calibrator;
+        droid.lose_health *= energyloss_calibrator;
+        droid.aggression = (f32::from(droid.aggression) * aggression_calibrator) as u8;
+        droid.score = (droid.score as f32 * score_calibrator) as i32;
+    }
+
+    droids
+}
+
+/// Reads the specifications of the bullet types, and applies the calibration
+/// factors to them.
+fn read_bullets(data: &[u8], problems: &mut Vec<DataProblem>) -> Vec<BulletValues> {
+    const BULLET_RECHARGE_TIME_BEGIN_STRING: &[u8] =
+        b"Time is takes to recharge this bullet/weapon in seconds :";
+    const BULLET_SPEED_BEGIN_STRING: &[u8] = b"Flying speed of this bullet type :";
+    const BULLET_DAMAGE_BEGIN_STRING: &[u8] = b"Damage cause by a hit of this bullet type :";
+    // #define BULLET_NUMBER_OF_PHASES_BEGIN_STRING "Number of different phases that were designed for this bullet type :"
+    // const BULLET_ONE_SHOT_ONLY_AT_A_TIME: &CStr =
+    //     c"Cannot fire until previous bullet has been deleted : ";
+
+    const BULLET_SPEED_CALIBRATOR_STRING: &[u8] = b"Common factor for all bullet's speed values: ";
+    const BULLET_DAMAGE_CALIBRATOR_STRING: &[u8] =
+        b"Common factor for all bullet's damage values: ";
+
+    info!("Starting to read bullet data...");
+
+    let offsets: Vec<usize> = data.find_iter(NEW_BULLET_TYPE_BEGIN_STRING).collect();
+    if offsets.len() != NUMBER_OF_BULLET_TYPES {
+        problems.push(DataProblem::in_file(format!(
+            "{} bullet types found, exactly {} are needed",
+            offsets.len(),
+            NUMBER_OF_BULLET_TYPES
+        )));
+    }
+
+    info!("Starting to read bullet calibration section");
+
+    let bullet_speed_calibrator =
+        read_float_entry(data, 0, BULLET_SPEED_CALIBRATOR_STRING, problems);
+    let bullet_damage_calibrator = read_entry(
+        data,
+        0,
+        BULLET_DAMAGE_CALIBRATOR_STRING,
+        try_read_float_from_string,
+        problems,
+    );
+
+    //--------------------
+    // Now we start to read the values for each bullet type and apply the
+    // calibration factors to them right now, so they also take effect.
+    //
+    let mut bullets = Vec::new();
+    for offset in offsets.into_iter().take(NUMBER_OF_BULLET_TYPES) {
+        info!("Found another Bullet specification entry!  Lets add that to the others!");
+
+        // Now we read in the recharging time for this bullettype(=weapontype)
+        let recharging_time =
+            read_float_entry(data, offset, BULLET_RECHARGE_TIME_BEGIN_STRING, problems);
+
+        // Now we read in the maximal speed this type of bullet can go.
+        let speed = read_float_entry(data, offset, BULLET_SPEED_BEGIN_STRING, problems);
+
+        // Now we read in the damage this bullet can do
+        let damage = read_entry(
+            data,
+            offset,
+            BULLET_DAMAGE_BEGIN_STRING,
+            try_read_u16_from_string,
+            problems,
+        );
+
+        let mut calibrated_damage = 0;
+        if let (Some(damage), Some(calibrator)) = (damage, bullet_damage_calibrator) {
+            let damage = f32::from(damage) * calibrator;
+            if damage <= 0. {
+                problems.push(DataProblem::at(
+                    offset,
+                    "calibrated damage must be greater than zero",
+                ));
+            }
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            {
+                calibrated_damage = damage as u16;
+            }
+        }
+
+        bullets.push(BulletValues {
+            recharging_time,
+            speed: speed * bullet_speed_calibrator,
+            damage: calibrated_damage,
+        });
+    }
+
+    bullets
+}
+
+#[allow(clippy::similar_names, clippy::too_many_lines)]
+fn read_droid_spec(
+    data: &[u8],
+    offset: usize,
+    problems: &mut Vec<DataProblem>,
+) -> Option<DruidSpec> {
     const MAXSPEED_BEGIN_STRING: &[u8] = b"Maximum speed of this droid: ";
     const CLASS_BEGIN_STRING: &[u8] = b"Class of this droid: ";
     const ACCELERATION_BEGIN_STRING: &[u8] = b"Maximum acceleration of this droid: ";
     const MAXENERGY_BEGIN_STRING: &[u8] = b"Maximum energy of this droid: ";
     const LOSEHEALTH_BEGIN_STRING: &[u8] = b"Rate of energyloss under influence control: ";
-    const GUN_BEGIN_STRING: &[u8] = b"Weapon type this droid uses: ";
     const AGGRESSION_BEGIN_STRING: &[u8] = b"Aggression rate of this droid: ";
     const FLASHIMMUNE_BEGIN_STRING: &[u8] = b"Is this droid immune to disruptor blasts? ";
     const SCORE_BEGIN_STRING: &[u8] = b"Score gained for destroying one of this type: ";
@@ -1254,76 +1705,186 @@ fn read_droid_spec(robot_slice: &[u8]) -> DruidSpec {
     const SENSOR3_BEGIN_STRING: &[u8] = b"Sensor 3 of this droid : ";
     const NOTES_BEGIN_STRING: &[u8] = b"Notes concerning this droid : ";
 
+    let robot_slice = &data[offset..];
+    let read_float = |label, problems: &mut Vec<DataProblem>| {
+        read_entry(data, offset, label, try_read_float_from_string, problems)
+    };
+    let read_u8 = |label, problems: &mut Vec<DataProblem>| {
+        read_entry(data, offset, label, try_read_u8_from_string, problems)
+    };
+    let read_i32 = |label, problems: &mut Vec<DataProblem>| {
+        read_entry(data, offset, label, try_read_i32_from_string, problems)
+    };
+
     // Now we read in the Name of this droid.  We consider as a name the rest of the
     let mut druidname = ArrayCString::new();
-    druidname.set_slice(read_string_from_string(robot_slice, DROIDNAME_BEGIN_STRING));
+    let name = try_read_string_from_string(robot_slice, DROIDNAME_BEGIN_STRING);
+    if let Some(name) = expect_entry(name, DROIDNAME_BEGIN_STRING, offset, problems) {
+        if druidname.try_set_slice(name).is_err() {
+            problems.push(DataProblem::invalid_entry(offset, DROIDNAME_BEGIN_STRING));
+        }
+    }
 
     // Now we read in the maximal speed this droid can go.
-    let maxspeed = read_float_from_string(robot_slice, MAXSPEED_BEGIN_STRING);
+    let maxspeed = read_float(MAXSPEED_BEGIN_STRING, problems);
 
     // Now we read in the class of this droid.
-    let class = read_u8_from_string(robot_slice, CLASS_BEGIN_STRING);
+    let class = read_u8(CLASS_BEGIN_STRING, problems);
 
     // Now we read in the maximal acceleration this droid can go.
-    let accel = read_float_from_string(robot_slice, ACCELERATION_BEGIN_STRING);
+    let accel = read_float(ACCELERATION_BEGIN_STRING, problems);
 
     // Now we read in the maximal energy this droid can store.
-    let maxenergy = read_float_from_string(robot_slice, MAXENERGY_BEGIN_STRING);
+    let maxenergy = read_float(MAXENERGY_BEGIN_STRING, problems);
 
     // Now we read in the lose_health rate.
-    let lose_health = read_float_from_string(robot_slice, LOSEHEALTH_BEGIN_STRING);
+    let lose_health = read_float(LOSEHEALTH_BEGIN_STRING, problems);
 
     // Now we read in the class of this droid.
-    let gun = BulletKind::try_from(read_u8_from_string(robot_slice, GUN_BEGIN_STRING)).unwrap();
+    let gun_number = read_u8(GUN_BEGIN_STRING, problems);
+    let gun = gun_number.and_then(|gun| BulletKind::try_from(gun).ok());
+    if let (Some(gun_number), None) = (gun_number, gun) {
+        problems.push(DataProblem::at(
+            offset,
+            format!(
+                "droid {} uses the unknown weapon type {gun_number}",
+                druidname.to_string_lossy()
+            ),
+        ));
+    }
 
     // Now we read in the aggression rate of this droid.
-    let aggression = read_u8_from_string(robot_slice, AGGRESSION_BEGIN_STRING);
+    let aggression = read_u8(AGGRESSION_BEGIN_STRING, problems);
 
     // Now we read in the flash immunity of this droid.
-    let flashimmune = read_i32_from_string(robot_slice, FLASHIMMUNE_BEGIN_STRING);
+    let flashimmune = read_i32(FLASHIMMUNE_BEGIN_STRING, problems);
 
     // Now we score to be had for destroying one droid of this type
-    let score = read_i32_from_string(robot_slice, SCORE_BEGIN_STRING);
+    let score = read_i32(SCORE_BEGIN_STRING, problems);
+    if let Some(score) = score.filter(|&score| score >= 2i32.pow(f32::MANTISSA_DIGITS)) {
+        problems.push(DataProblem::at(
+            offset,
+            format!(
+                "score {score} of droid {} is too big",
+                druidname.to_string_lossy()
+            ),
+        ));
+    }
 
     // Now we read in the height of this droid of this type
-    let height = read_float_from_string(robot_slice, HEIGHT_BEGIN_STRING);
+    let height = read_float(HEIGHT_BEGIN_STRING, problems);
 
     // Now we read in the weight of this droid type
-    let weight = read_i32_from_string(robot_slice, WEIGHT_BEGIN_STRING);
+    let weight = read_i32(WEIGHT_BEGIN_STRING, problems);
 
     // Now we read in the drive of this droid of this type
-    let drive = read_i32_from_string(robot_slice, DRIVE_BEGIN_STRING);
+    let drive = read_i32(DRIVE_BEGIN_STRING, problems);
 
     // Now we read in the brain of this droid of this type
-    let brain = read_i32_from_string(robot_slice, BRAIN_BEGIN_STRING);
+    let brain = read_i32(BRAIN_BEGIN_STRING, problems);
 
     // Now we read in the sensor 1, 2 and 3 of this droid type
-    let sensor1 = read_i32_from_string(robot_slice, SENSOR1_BEGIN_STRING);
-    let sensor2 = read_i32_from_string(robot_slice, SENSOR2_BEGIN_STRING);
-    let sensor3 = read_i32_from_string(robot_slice, SENSOR3_BEGIN_STRING);
+    let sensor1 = read_i32(SENSOR1_BEGIN_STRING, problems);
+    let sensor2 = read_i32(SENSOR2_BEGIN_STRING, problems);
+    let sensor3 = read_i32(SENSOR3_BEGIN_STRING, problems);
 
     // Now we read in the notes concerning this droid.  We consider as notes all the rest of the
     // line after the NOTES_BEGIN_STRING until the "\n" is found.
-    let notes = read_and_malloc_string_from_data(robot_slice, NOTES_BEGIN_STRING, b"\n");
+    let notes = try_read_and_malloc_string_from_data(robot_slice, NOTES_BEGIN_STRING, b"\n");
+    let notes = expect_entry(notes, NOTES_BEGIN_STRING, offset, problems);
 
-    DruidSpec {
+    Some(DruidSpec {
         druidname,
-        maxspeed,
-        class,
-        accel,
-        maxenergy,
-        lose_health,
-        gun,
-        aggression,
-        flashimmune,
-        score,
-        height,
-        weight,
-        drive,
-        brain,
-        sensor1,
-        sensor2,
-        sensor3,
-        notes,
+        maxspeed: maxspeed?,
+        class: class?,
+        accel: accel?,
+        maxenergy: maxenergy?,
+        lose_health: lose_health?,
+        gun: gun?,
+        aggression: aggression?,
+        flashimmune: flashimmune?,
+        score: score?,
+        height: height?,
+        weight: weight?,
+        drive: drive?,
+        brain: brain?,
+        sensor1: sensor1?,
+        sensor2: sensor2?,
+        sensor3: sensor3?,
+        notes: notes?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ScratchDir;
+
+    fn mission(title: Option<&str>) -> Vec<u8> {
+        let mut mission = b"Ship file to use for this mission: Paradroid.shp\n".to_vec();
+        if let Some(title) = title {
+            for part in [
+                MISSION_BRIEFING_BEGIN_STRING,
+                b"\n",
+                NEXT_BRIEFING_SUBSECTION_START_STRING,
+                b"\n\n  ",
+                title.as_bytes(),
+                b"\nThe briefing goes on here.\n",
+                END_OF_BRIEFING_SUBSECTION_STRING,
+                b"\n",
+            ] {
+                mission.extend_from_slice(part);
+            }
+        }
+        mission.extend_from_slice(END_OF_MISSION_DATA_STRING);
+        mission
+    }
+
+    #[test]
+    fn missions_of_all_data_dirs() {
+        let root = ScratchDir::new("missions");
+        let dirs = [root.join("user"), root.join("missing"), root.join("game")];
+        let map_dir = MAP_DIR_C.to_str().unwrap();
+        for (dir, files) in [
+            (
+                &dirs[0],
+                [("beta.mission", Some("Beta Run")), ("alpha.mission", None)].as_slice(),
+            ),
+            (
+                &dirs[2],
+                &[
+                    ("beta.mission", Some("Other Beta")),
+                    ("gamma.mission", Some("Gamma")),
+                    ("notes.txt", Some("Notes")),
+                ],
+            ),
+        ] {
+            fs::create_dir_all(dir.join(map_dir)).unwrap();
+            for &(file, title) in files {
+                fs::write(dir.join(map_dir).join(file), mission(title)).unwrap();
+            }
+        }
+
+        let missions: Vec<_> = find_missions(&dirs)
+            .into_iter()
+            .map(|mission| (mission.file, mission.title.into_string().unwrap()))
+            .collect();
+        assert_eq!(
+            missions,
+            [
+                ("alpha.mission".to_owned(), "alpha".to_owned()),
+                ("beta.mission".to_owned(), "Beta Run".to_owned()),
+                ("gamma.mission".to_owned(), "Gamma".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn headless_options() {
+        let opt = Opt::headless("Test.mission", 120, Some(7));
+        assert!(opt.headless);
+        assert_eq!(opt.mission, "Test.mission");
+        assert_eq!((opt.frames, opt.seed), (120, Some(7)));
+        assert!(opt.command.is_none());
     }
 }
@@ -3,6 +3,7 @@ use crate::graphics::Graphics;
 use crate::{
     Sdl,
     defs::{Cmds, MenuAction, PointerStates},
+    replay::Session,
     structs::Point,
     vars::Vars,
 };
@@ -11,7 +12,7 @@ use log::info;
 use sdl::{
     Event, Joystick,
     convert::{i32_to_u8, u32_to_u16},
-    event::{self, KeyboardEventType},
+    event::KeyboardEventType,
 };
 use sdl_sys::{
     SDL_BUTTON_LEFT, SDL_BUTTON_MIDDLE, SDL_BUTTON_RIGHT, SDL_BUTTON_WHEELDOWN, SDL_BUTTON_WHEELUP,
@@ -24,7 +25,7 @@ use sdl_sys::{SDLKey_SDLK_BACKSPACE, SDLKey_SDLK_LALT, SDLKey_SDLK_LCTRL, SDLKey
 use sdl_sys::{SDLKey_SDLK_F12, SDLKey_SDLK_PAUSE, SDLKey_SDLK_RSHIFT};
 #[cfg(not(target_os = "android"))]
 use std::ffi::CStr;
-use std::{cell::Cell, fmt};
+use std::{cell::Cell, fmt, ops::ControlFlow};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 struct InputState {
@@ -69,6 +70,99 @@ pub struct Input {
     // is firing to use axis-values or not
     pub axis_is_active: bool,
     pub key_cmds: [[u16; 3]; Cmds::Last as usize],
+    // input recording or replay, if requested on the command line
+    pub replay: Option<Session>,
+}
+
+/// The part of an SDL event that is consumed by the input handling.
+///
+/// This is what gets written to and read from an input recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Quit,
+    Keyboard {
+        key: u16,
+        modifiers: SDLMod,
+        pressed: bool,
+    },
+    JoyAxis {
+        axis: u8,
+        value: i16,
+    },
+    JoyButton {
+        button: u8,
+        pressed: bool,
+    },
+    MouseMotion {
+        x: u16,
+        y: u16,
+    },
+    MouseButton {
+        button: u8,
+        pressed: bool,
+    },
+}
+
+impl InputEvent {
+    fn from_sdl(event: &Event) -> Option<Self> {
+        let event = match event {
+            Event::Quit => Self::Quit,
+            Event::Keyboard(event) => Self::Keyboard {
+                key: event.keysym.symbol.to_u16(),
+                modifiers: event.keysym.mod_.bits(),
+                pressed: matches!(event.ty, KeyboardEventType::KeyDown),
+            },
+            Event::JoyAxis(event) => Self::JoyAxis {
+                axis: event.axis,
+                value: event.value,
+            },
+            Event::JoyButton(event) => Self::JoyButton {
+                button: event.button,
+                pressed: event.state.is_pressed(),
+            },
+            Event::MouseMotion(event) => Self::MouseMotion {
+                x: event.x,
+                y: event.y,
+            },
+            Event::MouseButton(event) => Self::MouseButton {
+                button: event.button,
+                pressed: event.state.is_pressed(),
+            },
+            _ => return None,
+        };
+
+        Some(event)
+    }
+
+    fn apply(
+        self,
+        input: &mut Input,
+        vars: &Vars,
+        sdl: &Sdl,
+        quit: &Cell<bool>,
+    ) -> ControlFlow<()> {
+        match self {
+            Self::Quit => {
+                info!("User requested termination, terminating.");
+                quit.set(true);
+                return ControlFlow::Break(());
+            }
+
+            Self::Keyboard {
+                key,
+                modifiers,
+                pressed,
+            } => handle_keyboard_event(key, modifiers, pressed, input),
+            Self::JoyAxis { axis, value } => handle_joy_axis_event(axis, value, input),
+            Self::JoyButton { button, pressed } => handle_joy_button_event(button, pressed, input),
+            Self::MouseMotion { x, y } => handle_mouse_motion_event(x, y, input, vars, sdl),
+            Self::MouseButton { button, pressed } => {
+                handle_mouse_button_event(button, pressed, input, sdl);
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
 }
 
 #[cfg(not(target_os = "android"))]
@@ -319,6 +413,7 @@ impl fmt::Debug for Input {
             .field("joy_num_axes", &self.joy_num_axes)
             .field("axis_is_active", &self.axis_is_active)
             .field("key_cmds", &self.key_cmds)
+            .field("replay", &self.replay)
             .finish()
     }
 }
@@ -338,6 +433,7 @@ impl Default for Input {
             joy_num_axes: 0,
             axis_is_active: false,
             key_cmds: default_key_cmds(),
+            replay: None,
         }
     }
 }
@@ -542,27 +638,43 @@ impl crate::Data<'_> {
         // switch mouse-cursor visibility as a function of time of last activity
         input.show_cursor = sdl.ticks_ms() - input.last_mouse_event <= CURSOR_KEEP_VISIBLE;
 
+        if let Some(mut events) = input.replay.as_mut().and_then(Session::replayed_events) {
+            // While replaying, the devices are only asked whether the window has been closed
+            while let Some(event) = sdl.next_event() {
+                if matches!(event, Event::Quit) {
+                    events.insert(0, InputEvent::Quit);
+                    break;
+                }
+            }
+
+            for event in events {
+                if event.apply(input, vars, sdl, quit).is_break() {
+                    return 0;
+                }
+            }
+            return 0;
+        }
+
         loop {
             let Some(event) = sdl.next_event() else {
                 break;
             };
+            let Some(event) = InputEvent::from_sdl(&event) else {
+                break;
+            };
 
-            match event {
-                Event::Quit => {
-                    info!("User requested termination, terminating.");
-                    quit.set(true);
-                    return 0;
-                }
-
-                Event::Keyboard(event) => handle_keyboard_event(&event, input),
-                Event::JoyAxis(event) => handle_joy_axis_event(event, input),
-                Event::JoyButton(event) => handle_joy_button_event(event, input),
-                Event::MouseMotion(event) => handle_mouse_motion_event(event, input, vars, sdl),
-                Event::MouseButton(event) => handle_mouse_button_event(event, input, sdl),
+            if let Some(session) = &mut input.replay {
+                session.record(event);
+            }
 
-                _ => break,
+            if event.apply(input, vars, sdl, quit).is_break() {
+                return 0;
             }
         }
+
+        if let Some(session) = &mut input.replay {
+            session.end_poll();
+        }
         0
     }
 
@@ -911,33 +1023,29 @@ impl Input {
     }
 }
 
-fn handle_keyboard_event(event: &event::Keyboard, input: &mut Input) {
-    input.current_modifiers = event.keysym.mod_.bits();
-    match event.ty {
-        KeyboardEventType::KeyDown => {
-            input.state[event.keysym.symbol.to_usize()].set_just_pressed();
-            #[cfg(feature = "gcw0")]
-            if input.axis.x != 0 || input.axis.y != 0 {
-                input.axis_is_active = true; // 4 GCW-0 ; breaks cursor keys after axis has been active...
-            }
+fn handle_keyboard_event(key: u16, modifiers: SDLMod, pressed: bool, input: &mut Input) {
+    input.current_modifiers = modifiers;
+    if pressed {
+        input.state[usize::from(key)].set_just_pressed();
+        #[cfg(feature = "gcw0")]
+        if input.axis.x != 0 || input.axis.y != 0 {
+            input.axis_is_active = true; // 4 GCW-0 ; breaks cursor keys after axis has been active...
         }
-        KeyboardEventType::KeyUp => {
-            input.state[event.keysym.symbol.to_usize()].set_just_released();
-            #[cfg(feature = "gcw0")]
-            {
-                input.axis_is_active = false;
-            }
+    } else {
+        input.state[usize::from(key)].set_just_released();
+        #[cfg(feature = "gcw0")]
+        {
+            input.axis_is_active = false;
         }
     }
 }
 
-fn handle_joy_axis_event(event: event::JoyAxis, input: &mut Input) {
-    let axis = event.axis;
-    let get_value = || i32::from(input.joy_sensitivity) * i32::from(event.value);
+fn handle_joy_axis_event(axis: u8, value: i16, input: &mut Input) {
+    let get_value = || i32::from(input.joy_sensitivity) * i32::from(value);
     if axis == 0 || ((input.joy_num_axes >= 5) && (axis == 3))
     /* x-axis */
     {
-        input.axis.x = event.value.into();
+        input.axis.x = value.into();
 
         // this is a bit tricky, because we want to allow direction keys
         // to be soft-released. When mapping the joystick->keyboard, we
@@ -957,7 +1065,7 @@ fn handle_joy_axis_event(event: event::JoyAxis, input: &mut Input) {
         }
     } else if (axis == 1) || ((input.joy_num_axes >= 5) && (axis == 4)) {
         /* y-axis */
-        input.axis.y = event.value.into();
+        input.axis.y = value.into();
 
         if get_value() > 10000 {
             input.state[PointerStates::JoyDown as usize].set_just_pressed();
@@ -972,9 +1080,8 @@ fn handle_joy_axis_event(event: event::JoyAxis, input: &mut Input) {
     }
 }
 
-fn handle_joy_button_event(event: event::JoyButton, input: &mut Input) {
-    let is_pressed = event.state.is_pressed();
-    let input_state_index = match event.button {
+fn handle_joy_button_event(button: u8, is_pressed: bool, input: &mut Input) {
+    let input_state_index = match button {
         0 => Some(PointerStates::JoyButton1 as usize),
         1 => Some(PointerStates::JoyButton2 as usize),
         2 => Some(PointerStates::JoyButton3 as usize),
@@ -989,23 +1096,22 @@ fn handle_joy_button_event(event: event::JoyButton, input: &mut Input) {
     input.axis_is_active = is_pressed;
 }
 
-fn handle_mouse_motion_event(event: event::MouseMotion, input: &mut Input, vars: &Vars, sdl: &Sdl) {
+fn handle_mouse_motion_event(x: u16, y: u16, input: &mut Input, vars: &Vars, sdl: &Sdl) {
     let user_center = vars.get_user_center();
-    input.axis.x = i32::from(event.x) - i32::from(user_center.x()) + 16;
-    input.axis.y = i32::from(event.y) - i32::from(user_center.y()) + 16;
+    input.axis.x = i32::from(x) - i32::from(user_center.x()) + 16;
+    input.axis.y = i32::from(y) - i32::from(user_center.y()) + 16;
 
     input.last_mouse_event = sdl.ticks_ms();
 }
 
-fn handle_mouse_button_event(event: event::MouseButton, input: &mut Input, sdl: &Sdl) {
+fn handle_mouse_button_event(button: u8, is_pressed: bool, input: &mut Input, sdl: &Sdl) {
     const BUTTON_LEFT: u8 = i32_to_u8(SDL_BUTTON_LEFT);
     const BUTTON_RIGHT: u8 = i32_to_u8(SDL_BUTTON_RIGHT);
     const BUTTON_MIDDLE: u8 = i32_to_u8(SDL_BUTTON_MIDDLE);
     const BUTTON_WHEELUP: u8 = i32_to_u8(SDL_BUTTON_WHEELUP);
     const BUTTON_WHEELDOWN: u8 = i32_to_u8(SDL_BUTTON_WHEELDOWN);
 
-    let is_pressed = event.state.is_pressed();
-    let input_state_index = match event.button {
+    let input_state_index = match button {
         BUTTON_LEFT => {
             input.axis_is_active = is_pressed;
             Some(PointerStates::MouseButton1 as usize)
@@ -1,26 +1,66 @@
+mod auto_tile;
+pub mod decks;
+mod history;
+pub mod lifts;
+mod palette;
+mod play_test;
+mod selection;
+
 use crate::{
     b_font::{font_height, print_string_font},
     cur_level,
     defs::{
         AssembleCombatWindowFlags, Cmds, MAX_WP_CONNECTIONS, MAXWAYPOINTS, MapTile, NUM_MAP_BLOCKS,
     },
-    structs::{Level, Waypoint},
+    graphics::Graphics,
+    structs::{CoarsePoint, Level, Waypoint},
     view::BLACK,
+    waypoints::{WaypointProblem, check_waypoints, generate_waypoints},
 };
 
 use arrayvec::ArrayVec;
+use auto_tile::auto_tile;
+use history::History;
+use lifts::{
+    add_lift, check_lifts, find_lift, link_lifts, remove_lift, remove_lift_row, step_lift_row,
+    unlink_lift,
+};
 use log::{info, warn};
 use nom::Finish;
-use sdl::{Pixel, convert::u32_to_u16};
+use palette::Palette;
+use sdl::{Pixel, Rect, convert::u32_to_u16};
 use sdl_sys::{
-    SDLKey_SDLK_F1, SDLKey_SDLK_KP_PLUS, SDLKey_SDLK_KP0, SDLKey_SDLK_KP1, SDLKey_SDLK_KP2,
-    SDLKey_SDLK_KP3, SDLKey_SDLK_KP4, SDLKey_SDLK_KP5, SDLKey_SDLK_KP6, SDLKey_SDLK_KP7,
-    SDLKey_SDLK_KP8, SDLKey_SDLK_KP9,
+    SDL_Color, SDLKey_SDLK_DOWN, SDLKey_SDLK_ESCAPE, SDLKey_SDLK_F1, SDLKey_SDLK_F5,
+    SDLKey_SDLK_KP_PLUS, SDLKey_SDLK_KP0, SDLKey_SDLK_KP1, SDLKey_SDLK_KP2, SDLKey_SDLK_KP3,
+    SDLKey_SDLK_KP4, SDLKey_SDLK_KP5, SDLKey_SDLK_KP6, SDLKey_SDLK_KP7, SDLKey_SDLK_KP8,
+    SDLKey_SDLK_KP9, SDLKey_SDLK_LEFT, SDLKey_SDLK_RIGHT, SDLKey_SDLK_SPACE, SDLKey_SDLK_TAB,
+    SDLKey_SDLK_UP,
 };
+use selection::{Area, Mirror, Selection};
 use std::{cmp::Ordering, ops::Not};
 
 const HIGHLIGHTCOLOR: Pixel = Pixel::from_u8(255);
 const HIGHLIGHTCOLOR2: Pixel = Pixel::from_u8(100);
+const SHIP_VIEW_RECT_COLOR: SDL_Color = SDL_Color {
+    r: 255,
+    g: 0,
+    b: 0,
+    unused: 0,
+};
+const PALETTE_LEFT_COLOR: SDL_Color = SDL_Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    unused: 0,
+};
+const PALETTE_RIGHT_COLOR: SDL_Color = SDL_Color {
+    r: 255,
+    g: 255,
+    b: 0,
+    unused: 0,
+};
+/// The size of a rectangle added to the side-view of the ship.
+const NEW_SHIP_VIEW_RECT: Rect = Rect::new(0, 0, 20, 10);
 
 /// create a new empty waypoint on position x/y
 fn create_waypoint(level: &mut Level, block_x: i32, block_y: i32) {
@@ -80,10 +120,37 @@ impl crate::Data<'_> {
         let rect = self.vars.user_rect;
         self.vars.user_rect = self.vars.screen_rect; // level editor can use the full screen!
         let mut src_wp_index = None;
+        let mut history = History::default();
+        let mut selection = Selection::default();
+        let mut check_graph = false;
+        let mut lift_origin: Option<usize> = None;
+        // the block which is edited, it follows the influencer when moved with the cursor
+        // keys and the mouse when moved over the map
+        let mut cursor = self.influencer_block();
+        let mut last_mouse_event = self.input.last_mouse_event;
+        let mut palette = Palette::default();
+        // walls, doors and consoles painted while auto-tiling is on fit the walls around them
+        let mut auto_tiling = false;
 
         while done.not() {
             if self.cmd_is_active_r(Cmds::Menu) {
+                let levels_before = self.main.cur_ship.levels.clone();
                 self.show_level_editor_menu();
+                // the level or its size may have changed
+                selection.clear();
+                cursor = self.influencer_block();
+                if self.menu.le_decks_changed {
+                    // the decks were renumbered, the recorded edits and the
+                    // marked waypoints and lifts do not fit any more
+                    history = History::default();
+                    origin_waypoint = None;
+                    src_wp_index = None;
+                    lift_origin = None;
+                    self.menu.le_decks_changed = false;
+                } else {
+                    // the changes made in the menu can be taken back like any other edit
+                    history.record_levels(levels_before, &self.main.cur_ship.levels);
+                }
                 if self.menu.quit_level_editor {
                     done = true;
                     self.global.current_combat_scale_factor = 1.;
@@ -93,16 +160,22 @@ impl crate::Data<'_> {
                 continue;
             }
 
-            #[allow(clippy::cast_possible_truncation)]
-            let [block_x, block_y] = [
-                (self.vars.me.pos.x).round() as i32,
-                (self.vars.me.pos.y).round() as i32,
-            ];
+            self.update_level_editor_cursor(&mut cursor, &mut last_mouse_event);
+            let [block_x, block_y] = [cursor.x, cursor.y].map(i32::from);
 
             self.fill_rect(self.vars.user_rect, BLACK);
             self.assemble_combat_picture(AssembleCombatWindowFlags::ONLY_SHOW_MAP);
-            self.highlight_current_block();
-            self.show_waypoints();
+            self.highlight_current_block(cursor);
+            if let Some(area) = selection.area(cursor) {
+                self.highlight_area(area);
+            }
+            let problems = if check_graph {
+                let level = self.main.cur_level();
+                check_waypoints(&level.map, &level.waypoints)
+            } else {
+                Vec::new()
+            };
+            self.show_waypoints(&problems);
 
             // show line between a selected connection-origin and the current block
             if let Some(origin_waypoint) = origin_waypoint {
@@ -116,6 +189,8 @@ impl crate::Data<'_> {
                 );
             }
 
+            self.show_palette(&palette);
+
             let font0 = self
                 .global
                 .font0_b_font
@@ -128,20 +203,39 @@ impl crate::Data<'_> {
                 font0,
                 i32::from(self.vars.full_user_rect.x())
                     + i32::from(self.vars.full_user_rect.width()) / 3,
-                i32::from(self.vars.full_user_rect.y())
-                    + i32::from(self.vars.full_user_rect.height())
-                    - i32::from(font_height(&*font0)),
-                format_args!("Press F1 for keymap"),
+                i32::from(self.palette_rect().y()) - i32::from(font_height(&*font0)),
+                format_args!(
+                    "Press F1 for keymap{}",
+                    if auto_tiling { ", auto-tiling on" } else { "" }
+                ),
             );
+            self.show_lift_info(cursor, lift_origin);
 
+            if self.input.show_cursor {
+                self.sdl.cursor().show();
+            } else {
+                self.sdl.cursor().hide();
+            }
             assert!(self.graphics.ne_screen.as_mut().unwrap().flip());
 
-            self.handle_level_editor_arrow_keys();
+            if self.handle_level_editor_arrow_keys() {
+                cursor = self.influencer_block();
+            }
+
+            // paint with the mouse, the left and the right button each paint the tile chosen
+            // for them by clicking on the palette
+            self.handle_level_editor_mouse(&mut history, &mut palette, auto_tiling);
 
             if self.key_is_pressed_r(SDLKey_SDLK_F1.try_into().unwrap()) {
                 self.handle_level_editor_help();
             }
 
+            // play the deck from the cursor with F5, the menu key returns to the editor
+            if self.key_is_pressed_r(SDLKey_SDLK_F5.try_into().unwrap()) {
+                self.play_test(cursor, rect);
+                last_mouse_event = self.input.last_mouse_event;
+            }
+
             //--------------------
             // Since the level editor will not always be able to
             // immediately feature all the the map tiles that might
@@ -150,7 +244,63 @@ impl crate::Data<'_> {
             // done upon pressing the 'e' key.
             //
             if self.key_is_pressed_r(b'e'.into()) {
-                self.handle_level_editor_tile_by_number(block_x, block_y);
+                self.handle_level_editor_tile_by_number(&mut history, block_x, block_y);
+            }
+
+            // take back the last edit with Ctrl+Z, and apply it again with Ctrl+Y
+            if self.ctrl_pressed() {
+                let undo = self.key_is_pressed_r(b'z'.into());
+                let redo = self.key_is_pressed_r(b'y'.into());
+                if undo || redo {
+                    self.handle_level_editor_undo(&mut history, redo);
+                    // the waypoint numbers may have changed
+                    origin_waypoint = None;
+                    src_wp_index = None;
+                    selection.clear();
+                }
+            }
+
+            // switch auto-tiling on and off with J, which joins the walls.  A is taken by moving
+            // left.
+            if self.key_is_pressed_r(b'j'.into()) {
+                auto_tiling = auto_tiling.not();
+            }
+
+            // start or end a selection with B, the tile keys then fill all of it
+            if self.key_is_pressed_r(b'b'.into()) {
+                selection.toggle(cursor);
+            }
+
+            // copy the selection with Ctrl+C, paste it at the cursor with Ctrl+V, or with
+            // Ctrl+Shift+V together with its waypoints
+            if self.ctrl_pressed() {
+                if self.key_is_pressed_r(b'c'.into()) {
+                    selection.copy(self.main.cur_level(), cursor);
+                }
+                if self.key_is_pressed_r(b'v'.into()) {
+                    let with_waypoints = self.shift_pressed();
+                    self.edit_cur_level(&mut history, |level| {
+                        selection.paste(level, cursor, with_waypoints);
+                    });
+                    if with_waypoints {
+                        origin_waypoint = None;
+                        src_wp_index = None;
+                    }
+                }
+            }
+
+            // check the waypoint graph and the lifts with G, the problems of the waypoints stay
+            // marked until G is pressed again
+            if self.key_is_pressed_r(b'g'.into()) {
+                check_graph = check_graph.not();
+                if check_graph {
+                    self.handle_level_editor_check();
+                }
+            }
+
+            // mirror the selection with X from left to right, with Shift+X from top to bottom
+            if self.key_is_pressed_r(b'x'.into()) {
+                self.handle_level_editor_mirror(&mut history, &selection, cursor);
             }
 
             //If the person using the level editor decides he/she wants a different
@@ -169,14 +319,23 @@ impl crate::Data<'_> {
             // toggle waypoint on current square.  That means either removed or added.
             // And in case of removal, also the connections must be removed.
             if self.key_is_pressed_r(b'p'.into()) {
-                self.handle_level_editor_toggle_waypoint(block_x, block_y);
+                self.handle_level_editor_toggle_waypoint(&mut history, block_x, block_y);
+            }
+
+            // replace the waypoints of the level with generated ones with N, Ctrl+Z takes them
+            // back.  W is taken by moving up.
+            if self.key_is_pressed_r(b'n'.into()) {
+                self.handle_level_editor_generate_waypoints(&mut history);
+                origin_waypoint = None;
+                src_wp_index = None;
             }
 
             // create a connection between waypoints.  If this is the first selected waypoint, its
             // an origin and the second "C"-pressed waypoint will be used a target.
             // If origin and destination are the same, the operation is cancelled.
-            if self.key_is_pressed_r(b'c'.into()) {
+            if self.ctrl_pressed().not() && self.key_is_pressed_r(b'c'.into()) {
                 self.handle_level_editor_waypoint_connection(
+                    &mut history,
                     block_x,
                     block_y,
                     &mut origin_waypoint,
@@ -184,16 +343,44 @@ impl crate::Data<'_> {
                 );
             }
 
+            // register the lift on a lift tile with Ctrl+L, or remove it again
+            if self.ctrl_pressed() && self.key_is_pressed_r(b'l'.into()) {
+                self.handle_level_editor_toggle_lift(cursor);
+                // the lift numbers may have changed
+                lift_origin = None;
+            }
+
+            // link lifts: U marks the lift at the cursor, then U on another lift puts that one
+            // above the marked one and Shift+U below it
+            if self.key_is_pressed_r(b'u'.into()) {
+                self.handle_level_editor_lift_link(cursor, &mut lift_origin);
+            }
+
+            // move the lift at the cursor to the next lift row with K, to the previous with Shift+K
+            if self.key_is_pressed_r(b'k'.into()) {
+                let levelnum = self.main.cur_level().levelnum;
+                if let Some(lift) = find_lift(&self.main.cur_ship, levelnum, cursor.x, cursor.y) {
+                    let back = self.shift_pressed();
+                    step_lift_row(&mut self.main.cur_ship, lift, back);
+                }
+            }
+
             // If the person using the level editor pressed some editing keys, insert the
             // corresponding map tile.  This is done here:
             let map_tile = self.handle_level_editor_key_pressed();
 
             if let Some(map_tile) = map_tile {
-                self.main.cur_level_mut().map[usize::try_from(block_y).unwrap()]
-                    [usize::try_from(block_x).unwrap()] = map_tile;
+                let area = selection.area_or_cursor(cursor);
+                self.edit_cur_level(&mut history, |level| {
+                    selection::fill(level, area, map_tile);
+                    if auto_tiling {
+                        auto_tile(level, area);
+                    }
+                });
             }
         }
 
+        self.sdl.cursor().hide();
         self.shuffle_enemys(); // now make sure droids get redestributed correctly!
 
         self.vars.user_rect = rect;
@@ -201,7 +388,64 @@ impl crate::Data<'_> {
         self.clear_graph_mem();
     }
 
-    fn handle_level_editor_arrow_keys(&mut self) {
+    /// Applies `edit` to the current level, so that it can be undone.
+    fn edit_cur_level(&mut self, history: &mut History, edit: impl FnOnce(&mut Level)) {
+        let level_index = self
+            .main
+            .cur_level_index
+            .expect("no current level index available");
+        let before = self.main.cur_level().clone();
+        edit(self.main.cur_level_mut());
+        history.record(level_index, before, self.main.cur_level());
+    }
+
+    fn handle_level_editor_undo(&mut self, history: &mut History, redo: bool) {
+        let levels = &mut self.main.cur_ship.levels;
+        let changed = if redo {
+            history.redo(levels)
+        } else {
+            history.undo(levels)
+        };
+        let Some(level_index) = changed else {
+            info!("Nothing to {}.", if redo { "redo" } else { "undo" });
+            return;
+        };
+
+        // show the level that changed
+        if self.main.cur_level_index != Some(level_index) {
+            let levelnum = self.main.cur_ship.levels[level_index].levelnum;
+            self.teleport(levelnum, 3, 3);
+        }
+
+        // the size of the level may have changed
+        let level = self.main.cur_level();
+        let (max_x, max_y) = (f32::from(level.xlen - 1), f32::from(level.ylen - 1));
+        self.vars.me.pos.x = self.vars.me.pos.x.min(max_x);
+        self.vars.me.pos.y = self.vars.me.pos.y.min(max_y);
+    }
+
+    fn handle_level_editor_mirror(
+        &mut self,
+        history: &mut History,
+        selection: &Selection,
+        cursor: CoarsePoint<u8>,
+    ) {
+        let Some(area) = selection.area(cursor) else {
+            warn!("Nothing selected, there is nothing to mirror.");
+            return;
+        };
+
+        let direction = if self.shift_pressed() {
+            Mirror::TopBottom
+        } else {
+            Mirror::LeftRight
+        };
+        self.edit_cur_level(history, |level| selection::mirror(level, area, direction));
+    }
+
+    /// Moves the influencer with the cursor keys, returns if it was moved.
+    fn handle_level_editor_arrow_keys(&mut self) -> bool {
+        let before = self.vars.me.pos;
         if self.left_pressed_r() && self.vars.me.pos.x.round() > 0. {
             self.vars.me.pos.x -= 1.;
         }
@@ -225,6 +469,182 @@ impl crate::Data<'_> {
         {
             self.vars.me.pos.y += 1.;
         }
+
+        self.vars.me.pos != before
+    }
+
+    /// The block the influencer is on.
+    fn influencer_block(&self) -> CoarsePoint<u8> {
+        #[allow(clippy::cast_possible_truncation)]
+        let [x, y] = [self.vars.me.pos.x, self.vars.me.pos.y].map(|pos| pos.round() as i32);
+        CoarsePoint {
+            x: u8::try_from(x).unwrap(),
+            y: u8::try_from(y).unwrap(),
+        }
+    }
+
+    /// The block of the current level under the mouse.
+    fn block_under_mouse(&self) -> Option<CoarsePoint<u8>> {
+        // the mouse position relative to the center of the screen, see `cursor_is_on_rect`
+        #[allow(clippy::cast_precision_loss)]
+        let [x, y] = [
+            (self.input.axis.x - 16) as f32 / f32::from(self.vars.block_rect.width()),
+            (self.input.axis.y - 16) as f32 / f32::from(self.vars.block_rect.height()),
+        ];
+        let level = self.main.cur_level();
+        #[allow(clippy::cast_possible_truncation)]
+        let [x, y] = [
+            (self.vars.me.pos.x + x).round() as i32,
+            (self.vars.me.pos.y + y).round() as i32,
+        ];
+        Some(CoarsePoint {
+            x: u8::try_from(x).ok().filter(|&x| x < level.xlen)?,
+            y: u8::try_from(y).ok().filter(|&y| y < level.ylen)?,
+        })
+    }
+
+    /// Moves the cursor to the block under the mouse when the mouse has been
+    /// moved over the map, and keeps it on the current level.
+    fn update_level_editor_cursor(
+        &mut self,
+        cursor: &mut CoarsePoint<u8>,
+        last_mouse_event: &mut u32,
+    ) {
+        if self.input.last_mouse_event != *last_mouse_event {
+            *last_mouse_event = self.input.last_mouse_event;
+            let on_palette = self.cursor_is_on_rect(self.palette_rect()) != 0;
+            if let Some(block) = self.block_under_mouse().filter(|_| on_palette.not()) {
+                *cursor = block;
+            }
+        }
+
+        let level = self.main.cur_level();
+        cursor.x = cursor.x.min(level.xlen - 1);
+        cursor.y = cursor.y.min(level.ylen - 1);
+    }
+
+    /// The strip at the bottom of the screen showing the palette.
+    fn palette_rect(&self) -> Rect {
+        let user_rect = self.vars.user_rect;
+        let height = self.vars.block_rect.height();
+        Rect::new(
+            user_rect.x(),
+            user_rect.y() + i16::try_from(user_rect.height().saturating_sub(height)).unwrap(),
+            user_rect.width(),
+            height,
+        )
+    }
+
+    /// The number of tiles fitting on the palette.
+    fn palette_slots(&self) -> usize {
+        usize::from(self.vars.user_rect.width() / self.vars.block_rect.width())
+    }
+
+    /// The place of the tile `slot` on the palette.
+    fn palette_slot_rect(&self, slot: usize) -> Rect {
+        let palette_rect = self.palette_rect();
+        let block = self.vars.block_rect;
+        let x = i16::try_from(slot * usize::from(block.width())).unwrap();
+        Rect::new(
+            palette_rect.x() + x,
+            palette_rect.y(),
+            block.width(),
+            block.height(),
+        )
+    }
+
+    /// Shows the tiles of the palette, the ones chosen for the left and the
+    /// right mouse button are framed.
+    fn show_palette(&mut self, palette: &Palette) {
+        self.fill_rect(self.palette_rect(), BLACK);
+
+        let color = self.main.cur_level().color.to_usize();
+        for (slot, tile) in palette.visible(self.palette_slots()).enumerate() {
+            let rect = self.palette_slot_rect(slot);
+            let mut dst = rect;
+            let Graphics {
+                map_block_surface_pointer,
+                ne_screen,
+                ..
+            } = &mut self.graphics;
+            map_block_surface_pointer[color][tile as usize]
+                .as_ref()
+                .unwrap()
+                .borrow_mut()
+                .blit_to(ne_screen.as_mut().unwrap(), &mut dst);
+
+            if tile == palette.right {
+                self.outline_rect(rect, PALETTE_RIGHT_COLOR);
+            }
+            // the left one is framed inside the right one, in case they are the same
+            if tile == palette.left {
+                let inner = Rect::new(
+                    rect.x() + 2,
+                    rect.y() + 2,
+                    rect.width().saturating_sub(4).max(1),
+                    rect.height().saturating_sub(4).max(1),
+                );
+                self.outline_rect(inner, PALETTE_LEFT_COLOR);
+            }
+        }
+    }
+
+    /// Paints the tiles chosen on the palette with the mouse buttons, or
+    /// on the palette chooses them and scrolls it with the mouse wheel.
+    fn handle_level_editor_mouse(
+        &mut self,
+        history: &mut History,
+        palette: &mut Palette,
+        auto_tiling: bool,
+    ) {
+        let slots = self.palette_slots();
+        if self.cursor_is_on_rect(self.palette_rect()) != 0 {
+            if self.wheel_up_pressed() {
+                palette.scroll(-1, slots);
+            }
+            if self.wheel_down_pressed() {
+                palette.scroll(1, slots);
+            }
+
+            let left = self.mouse_left_pressed_r();
+            let right = self.mouse_right_pressed_r();
+            if (left || right).not() {
+                return;
+            }
+            let Some(tile) = (0..slots)
+                .find(|&slot| self.cursor_is_on_rect(self.palette_slot_rect(slot)) != 0)
+                .and_then(|slot| palette.tile(slot))
+            else {
+                return;
+            };
+            if left {
+                palette.left = tile;
+            } else {
+                palette.right = tile;
+            }
+            return;
+        }
+
+        let map_tile = if self.mouse_left_pressed() {
+            palette.left
+        } else if self.mouse_right_pressed() {
+            palette.right
+        } else {
+            return;
+        };
+        let Some(block) = self.block_under_mouse() else {
+            return;
+        };
+        let [x, y] = [block.x, block.y].map(usize::from);
+        // painting over a tile again while the button is held is no new edit
+        if self.main.cur_level().map[y][x] != map_tile || auto_tiling {
+            self.edit_cur_level(history, |level| {
+                level.map[y][x] = map_tile;
+                if auto_tiling {
+                    auto_tile(level, Area::new(block, block));
+                }
+            });
+        }
     }
 
     fn handle_level_editor_help(&mut self) {
@@ -270,14 +690,30 @@ impl crate::Data<'_> {
         k += 1;
         put_string!(b"M...Alert, E...Enter tile by number");
         k += 1;
-        put_string!(b"Space/Enter...Floor");
+        put_string!(b"Space/Enter...Floor, J...auto-tiling (Joining) of walls on/off");
+        k += 1;
+        put_string!(b"Mouse buttons...paint the tiles clicked on the palette");
         k += 2;
 
-        put_string!(b"I/O...zoom INTO/OUT OF the map");
+        put_string!(b"I/O...zoom INTO/OUT OF the map, F5...play from here");
         k += 2;
         put_string!(b"P...toggle wayPOINT on/off");
         k += 1;
-        put_string!(b"C...start/end waypoint CONNECTION");
+        put_string!(b"C...start/end waypoint CONNECTION, N...geNerate waypoints");
+        k += 2;
+        put_string!(b"CTRL + Z...undo, CTRL + Y...redo");
+        k += 1;
+        put_string!(b"B...start/end selection, X/SHIFT + X...mirror it");
+        k += 1;
+        put_string!(b"CTRL + C...copy, CTRL (+ SHIFT) + V...paste (waypoints)");
+        k += 1;
+        put_string!(b"G...check the waypoint graph and the lifts on/off");
+        k += 1;
+        put_string!(b"CTRL + L...add/remove the lift on a lift tile");
+        k += 1;
+        put_string!(b"U...mark lift, U/SHIFT + U...put lift above/below it");
+        k += 1;
+        put_string!(b"SHIFT + U...unlink lift, K/SHIFT + K...change lift row");
 
         assert!(ne_screen.flip());
         self.graphics.ne_screen = Some(ne_screen);
@@ -286,7 +722,81 @@ impl crate::Data<'_> {
         }
     }
 
-    fn handle_level_editor_tile_by_number(&mut self, block_x: i32, block_y: i32) {
+    /// Lists the problems of the waypoint graph of the current level and of
+    /// the lifts of the ship.
+    fn handle_level_editor_check(&mut self) {
+        const LIST_OFFSET: i32 = 15;
+
+        let level = self.main.cur_level();
+        let problems: Vec<_> = check_waypoints(&level.map, &level.waypoints)
+            .iter()
+            .map(ToString::to_string)
+            .chain(
+                check_lifts(&self.main.cur_ship)
+                    .iter()
+                    .map(ToString::to_string),
+            )
+            .collect();
+
+        let mut k = 3;
+        self.make_grid_on_screen(None);
+        let mut ne_screen = self.graphics.ne_screen.take().unwrap();
+        let menu_b_font = self
+            .global
+            .menu_b_font
+            .as_ref()
+            .unwrap()
+            .ro(&self.font_owner);
+        let font_height = i32::from(font_height(menu_b_font));
+
+        let title: &[u8] = if problems.is_empty() {
+            b"No Waypoint or Lift Problems Found"
+        } else {
+            b"Waypoint and Lift Problems"
+        };
+        self.centered_put_string(&mut ne_screen, k * font_height, title);
+        k += 2;
+
+        let max_lines = i32::from(self.vars.screen_rect.height()) / font_height - k - 1;
+        let max_lines = usize::try_from(max_lines).unwrap_or(0);
+        // keep the last line for the number of problems which do not fit
+        let shown = if problems.len() > max_lines {
+            max_lines.saturating_sub(1)
+        } else {
+            problems.len()
+        };
+        for problem in &problems[..shown] {
+            self.put_string(
+                &mut ne_screen,
+                LIST_OFFSET,
+                k * font_height,
+                problem.as_bytes(),
+            );
+            k += 1;
+        }
+        if shown < problems.len() {
+            let text = format!("... and {} more", problems.len() - shown);
+            self.put_string(
+                &mut ne_screen,
+                LIST_OFFSET,
+                k * font_height,
+                text.as_bytes(),
+            );
+        }
+
+        assert!(ne_screen.flip());
+        self.graphics.ne_screen = Some(ne_screen);
+        while !self.fire_pressed_r() && !self.escape_pressed_r() && !self.return_pressed_r() {
+            self.sdl.delay_ms(1);
+        }
+    }
+
+    fn handle_level_editor_tile_by_number(
+        &mut self,
+        history: &mut History,
+        block_x: i32,
+        block_y: i32,
+    ) {
         use nom::{
             character::complete::{i32, space0},
             sequence::preceded,
@@ -319,27 +829,47 @@ impl crate::Data<'_> {
         if special_map_value >= NUM_MAP_BLOCKS.try_into().unwrap() {
             special_map_value = 0;
         }
-        self.main.cur_level_mut().map[usize::try_from(block_y).unwrap()]
-            [usize::try_from(block_x).unwrap()] = special_map_value.try_into().unwrap();
+        self.edit_cur_level(history, |level| {
+            level.map[usize::try_from(block_y).unwrap()][usize::try_from(block_x).unwrap()] =
+                special_map_value.try_into().unwrap();
+        });
     }
 
-    fn handle_level_editor_toggle_waypoint(&mut self, block_x: i32, block_y: i32) {
+    fn handle_level_editor_toggle_waypoint(
+        &mut self,
+        history: &mut History,
+        block_x: i32,
+        block_y: i32,
+    ) {
         // find out if there is a waypoint on the current square
         let i = self.main.cur_level().waypoints.iter().position(|waypoint| {
             i32::from(waypoint.x) == block_x && i32::from(waypoint.y) == block_y
         });
 
-        // if its waypoint already, this waypoint must be deleted.
-        if let Some(i) = i {
-            delete_waypoint(self.main.cur_level_mut(), i.try_into().unwrap());
-        } else {
-            // if its not a waypoint already, it must be made into one
-            create_waypoint(self.main.cur_level_mut(), block_x, block_y);
-        }
+        self.edit_cur_level(history, |level| {
+            // if its waypoint already, this waypoint must be deleted.
+            if let Some(i) = i {
+                delete_waypoint(level, i.try_into().unwrap());
+            } else {
+                // if its not a waypoint already, it must be made into one
+                create_waypoint(level, block_x, block_y);
+            }
+        });
+    }
+
+    fn handle_level_editor_generate_waypoints(&mut self, history: &mut History) {
+        self.edit_cur_level(history, |level| {
+            level.waypoints = generate_waypoints(&level.map).into_iter().collect();
+        });
+        info!(
+            "Generated {} waypoints for this level.",
+            self.main.cur_level().waypoints.len()
+        );
     }
 
     fn handle_level_editor_waypoint_connection(
         &mut self,
+        history: &mut History,
         block_x: i32,
         block_y: i32,
         origin_waypoint: &mut Option<usize>,
@@ -375,12 +905,248 @@ impl crate::Data<'_> {
         } else {
             info!("Target-waypoint {} selected. Connection established!", i);
             let waypoint_index = src_wp_index.take().unwrap();
-            let waypoint = &mut cur_level!(mut self.main).waypoints[waypoint_index];
-            waypoint.connections.push(i.try_into().unwrap());
+            self.edit_cur_level(history, |level| {
+                level.waypoints[waypoint_index]
+                    .connections
+                    .push(i.try_into().unwrap());
+            });
             *origin_waypoint = None;
         }
     }
 
+    fn handle_level_editor_toggle_lift(&mut self, cursor: CoarsePoint<u8>) {
+        let level = self.main.cur_level();
+        let levelnum = level.levelnum;
+        let tile = level.map[usize::from(cursor.y)][usize::from(cursor.x)];
+
+        let ship = &mut self.main.cur_ship;
+        if let Some(lift) = find_lift(ship, levelnum, cursor.x, cursor.y) {
+            remove_lift(ship, lift);
+            info!("Lift {lift} removed, the following lifts have been renumbered.");
+        } else if tile == MapTile::Lift {
+            if let Some(lift) = add_lift(ship, levelnum, cursor.x, cursor.y) {
+                info!("Lift {lift} added.");
+            }
+        } else {
+            warn!("Lifts can only be placed on lift tiles.");
+        }
+    }
+
+    fn handle_level_editor_lift_link(
+        &mut self,
+        cursor: CoarsePoint<u8>,
+        lift_origin: &mut Option<usize>,
+    ) {
+        let levelnum = self.main.cur_level().levelnum;
+        let ship = &mut self.main.cur_ship;
+        let Some(lift) = find_lift(ship, levelnum, cursor.x, cursor.y) else {
+            warn!("There is no lift at the cursor.");
+            return;
+        };
+
+        let below = self.shift_pressed();
+        match *lift_origin {
+            None if below => {
+                unlink_lift(ship, lift);
+                info!("Lift {lift} unlinked.");
+            }
+            None => *lift_origin = Some(lift),
+            Some(origin) if origin == lift => *lift_origin = None,
+            Some(origin) => {
+                if below {
+                    link_lifts(ship, lift, origin);
+                    info!("Lift {lift} is below lift {origin} now.");
+                } else {
+                    link_lifts(ship, origin, lift);
+                    info!("Lift {lift} is above lift {origin} now.");
+                }
+                *lift_origin = None;
+            }
+        }
+    }
+
+    /// Shows the lift at the cursor and the lift marked for linking, if any.
+    fn show_lift_info(&mut self, cursor: CoarsePoint<u8>, lift_origin: Option<usize>) {
+        let levelnum = self.main.cur_level().levelnum;
+        let ship = &self.main.cur_ship;
+        let describe = |link: i32| {
+            usize::try_from(link)
+                .ok()
+                .and_then(|link| Some((link, ship.lifts.get(link)?)))
+                .map_or_else(
+                    || "-".to_string(),
+                    |(link, lift)| format!("{link} (deck {})", lift.level),
+                )
+        };
+
+        let mut text = String::new();
+        if let Some(index) = find_lift(ship, levelnum, cursor.x, cursor.y) {
+            let lift = &ship.lifts[index];
+            text = format!(
+                "Lift {index}, row {}, up: {}, down: {}",
+                lift.row,
+                describe(lift.up),
+                describe(lift.down)
+            );
+        }
+        if let Some(origin) = lift_origin {
+            if text.is_empty().not() {
+                text.push_str(" - ");
+            }
+            text.push_str(&format!("linking lift {origin}"));
+        }
+        if text.is_empty() {
+            return;
+        }
+
+        let font0 = self
+            .global
+            .font0_b_font
+            .as_ref()
+            .unwrap()
+            .rw(&mut self.font_owner);
+        print_string_font(
+            self.graphics.ne_screen.as_mut().unwrap(),
+            font0,
+            i32::from(self.vars.full_user_rect.x()) + 2,
+            i32::from(self.vars.full_user_rect.y()) + 2,
+            format_args!("{text}"),
+        );
+    }
+
+    /// Edits the rectangles of the side-view of the ship shown by the
+    /// consoles and the lifts: the ones lit for the current deck, or after
+    /// pressing Tab the lift rows.  Space selects the next rectangle, the
+    /// arrow keys move it and together with Shift resize it, A adds a
+    /// rectangle and D deletes the selected one.  Escape ends the editing.
+    pub fn edit_ship_view(&mut self) {
+        let levelnum = self.main.cur_level().levelnum;
+        let mut lift_rows = false;
+        let mut selected = 0;
+
+        loop {
+            let count = self.ship_view_rects(levelnum, lift_rows).len();
+            selected = selected.min(count.saturating_sub(1));
+            let liftrow = if lift_rows && count > 0 {
+                i32::try_from(selected).unwrap()
+            } else {
+                -1
+            };
+            self.draw_lifts(levelnum, liftrow);
+            if let Some(&rect) = self.ship_view_rects(levelnum, lift_rows).get(selected) {
+                let (origin_x, origin_y) = self.ship_view_origin();
+                let rect = rect.with_xy(rect.x() + origin_x, rect.y() + origin_y);
+                self.outline_rect(rect, SHIP_VIEW_RECT_COLOR);
+            }
+
+            let font0 = self
+                .global
+                .font0_b_font
+                .as_ref()
+                .unwrap()
+                .rw(&mut self.font_owner);
+            let what = if lift_rows {
+                "Lift row".to_string()
+            } else {
+                format!("Deck {levelnum} rectangle")
+            };
+            print_string_font(
+                self.graphics.ne_screen.as_mut().unwrap(),
+                font0,
+                i32::from(self.vars.full_user_rect.x()) + 2,
+                i32::from(self.vars.full_user_rect.y()) + 2,
+                format_args!(
+                    "{what} {selected} of {count} - Tab: decks/lift rows, Space: next, \
+                     Arrows: move, Shift+Arrows: resize, A: add, D: delete, Esc: done"
+                ),
+            );
+            assert!(self.graphics.ne_screen.as_mut().unwrap().flip());
+
+            if self.key_is_pressed_r(u32_to_u16(SDLKey_SDLK_ESCAPE)) {
+                break;
+            }
+            if self.key_is_pressed_r(u32_to_u16(SDLKey_SDLK_TAB)) {
+                lift_rows = lift_rows.not();
+                selected = 0;
+            }
+            if self.key_is_pressed_r(u32_to_u16(SDLKey_SDLK_SPACE)) {
+                selected = (selected + 1) % count.max(1);
+            }
+            if self.key_is_pressed_r(b'a'.into()) {
+                let ship = &mut self.main.cur_ship;
+                let added = if lift_rows {
+                    ship.lift_row_rects.try_push(NEW_SHIP_VIEW_RECT).is_ok()
+                } else {
+                    ship.level_rects[usize::from(levelnum)]
+                        .try_push(NEW_SHIP_VIEW_RECT)
+                        .is_ok()
+                };
+                if added {
+                    selected = count;
+                } else {
+                    warn!("Maximal number of rectangles reached. Cannot add any more.");
+                }
+            }
+            if self.key_is_pressed_r(b'd'.into()) && count > 0 {
+                if lift_rows {
+                    remove_lift_row(&mut self.main.cur_ship, selected);
+                } else {
+                    self.main.cur_ship.level_rects[usize::from(levelnum)].remove(selected);
+                }
+            }
+
+            let resize = self.shift_pressed();
+            for (key, dx, dy) in [
+                (SDLKey_SDLK_LEFT, -1, 0),
+                (SDLKey_SDLK_RIGHT, 1, 0),
+                (SDLKey_SDLK_UP, 0, -1),
+                (SDLKey_SDLK_DOWN, 0, 1),
+            ] {
+                if self.key_is_pressed_r(u32_to_u16(key)).not() {
+                    continue;
+                }
+                let Some(rect) = self.ship_view_rects(levelnum, lift_rows).get_mut(selected) else {
+                    continue;
+                };
+                if resize {
+                    rect.set_width(rect.width().saturating_add_signed(dx).max(1));
+                    rect.set_height(rect.height().saturating_add_signed(dy).max(1));
+                } else {
+                    rect.set_x(rect.x() + dx);
+                    rect.set_y(rect.y() + dy);
+                }
+            }
+
+            self.sdl.delay_ms(1);
+        }
+    }
+
+    /// The deck rectangles of the deck `levelnum`, or the lift rows.
+    fn ship_view_rects(&mut self, levelnum: u8, lift_rows: bool) -> &mut [Rect] {
+        let ship = &mut self.main.cur_ship;
+        if lift_rows {
+            &mut ship.lift_row_rects
+        } else {
+            &mut ship.level_rects[usize::from(levelnum)]
+        }
+    }
+
+    /// Draws a frame of one pixel just inside `rect`.
+    fn outline_rect(&mut self, rect: Rect, color: SDL_Color) {
+        let [x, y] = [rect.x(), rect.y()];
+        let [width, height] = [rect.width(), rect.height()];
+        let right = x + i16::try_from(width).unwrap() - 1;
+        let bottom = y + i16::try_from(height).unwrap() - 1;
+        for line in [
+            Rect::new(x, y, width, 1),
+            Rect::new(x, bottom, width, 1),
+            Rect::new(x, y, 1, height),
+            Rect::new(right, y, 1, height),
+        ] {
+            self.fill_rect(line, color);
+        }
+    }
+
     fn handle_level_editor_key_pressed(&mut self) -> Option<MapTile> {
         let mut map_tile = None;
         if self.key_is_pressed_r(b'f'.into()) {
@@ -401,7 +1167,7 @@ impl crate::Data<'_> {
         if self.key_is_pressed_r(b'5'.into()) {
             map_tile = Some(MapTile::Block5);
         }
-        if self.key_is_pressed_r(b'l'.into()) {
+        if self.ctrl_pressed().not() && self.key_is_pressed_r(b'l'.into()) {
             map_tile = Some(MapTile::Lift);
         }
         if self.key_is_pressed_r(u32_to_u16(SDLKey_SDLK_KP_PLUS)) {
@@ -470,7 +1236,7 @@ impl crate::Data<'_> {
                 map_tile = Some(MapTile::HZutuere);
             }
         }
-        if self.space_pressed() || self.mouse_left_pressed() {
+        if self.space_pressed() {
             map_tile = Some(MapTile::Floor);
         }
 
@@ -478,8 +1244,16 @@ impl crate::Data<'_> {
     }
 
     /// This function is used by the Level Editor integrated into
-    /// freedroid.  It marks all waypoints with a cross.
-    fn show_waypoints(&mut self) {
+    /// freedroid.  It marks all waypoints with a cross, those with one of
+    /// the given `problems` in red.
+    fn show_waypoints(&mut self, problems: &[WaypointProblem]) {
+        let problem_color = self
+            .graphics
+            .ne_screen
+            .as_ref()
+            .unwrap()
+            .format()
+            .map_rgb(255, 0, 0);
         let block_x = self.vars.me.pos.x.round();
         let block_y = self.vars.me.pos.y.round();
 
@@ -490,9 +1264,17 @@ impl crate::Data<'_> {
         let block_rect_width_f = f32::from(self.vars.block_rect.width());
         let block_rect_height_f = f32::from(self.vars.block_rect.height());
 
-        for waypoint in &self.main.cur_level().waypoints {
+        for (index, waypoint) in (0..=u8::MAX).zip(&self.main.cur_level().waypoints) {
             let wp_x = f32::from(waypoint.x);
             let wp_y = f32::from(waypoint.y);
+            let color = if problems
+                .iter()
+                .any(|problem| problem.waypoints().contains(&index))
+            {
+                problem_color
+            } else {
+                HIGHLIGHTCOLOR
+            };
 
             // Draw the cross in the middle of the middle of the tile
             for i in i32::from(self.vars.block_rect.width() / 4)
@@ -524,7 +1306,7 @@ impl crate::Data<'_> {
                 let mut ne_screen = self.graphics.ne_screen.as_mut().unwrap().lock().unwrap();
                 ne_screen
                     .pixels()
-                    .set(x.try_into().unwrap(), y.try_into().unwrap(), HIGHLIGHTCOLOR)
+                    .set(x.try_into().unwrap(), y.try_into().unwrap(), color)
                     .unwrap();
 
                 #[allow(clippy::cast_possible_truncation)]
@@ -539,7 +1321,7 @@ impl crate::Data<'_> {
 
                 ne_screen
                     .pixels()
-                    .set(x.try_into().unwrap(), y.try_into().unwrap(), HIGHLIGHTCOLOR)
+                    .set(x.try_into().unwrap(), y.try_into().unwrap(), color)
                     .unwrap();
 
                 // This draws a line at the lower border of the current block
@@ -555,7 +1337,7 @@ impl crate::Data<'_> {
 
                 ne_screen
                     .pixels()
-                    .set(x.try_into().unwrap(), y.try_into().unwrap(), HIGHLIGHTCOLOR)
+                    .set(x.try_into().unwrap(), y.try_into().unwrap(), color)
                     .unwrap();
 
                 #[allow(clippy::cast_possible_truncation)]
@@ -570,22 +1352,25 @@ impl crate::Data<'_> {
 
                 ne_screen
                     .pixels()
-                    .set(x.try_into().unwrap(), y.try_into().unwrap(), HIGHLIGHTCOLOR)
+                    .set(x.try_into().unwrap(), y.try_into().unwrap(), color)
                     .unwrap();
             }
 
             // Draw the connections to other waypoints, BUT ONLY FOR THE WAYPOINT CURRENTLY TARGETED
             if (block_x - wp_x).abs() <= f32::EPSILON && (block_y - wp_y).abs() <= f32::EPSILON {
                 for &connection in &waypoint.connections {
-                    let connection = usize::from(connection);
-                    let waypoint = &cur_level!(self.main).waypoints[connection];
+                    // connections to waypoints which do not exist are left to the check
+                    let Some(target) = cur_level!(self.main).waypoints.get(usize::from(connection))
+                    else {
+                        continue;
+                    };
                     Self::draw_line_between_tiles_static(
                         &self.vars,
                         &mut self.graphics,
-                        waypoint.x.into(),
-                        waypoint.y.into(),
-                        waypoint.x.into(),
-                        waypoint.y.into(),
+                        wp_x,
+                        wp_y,
+                        target.x.into(),
+                        target.y.into(),
                         HIGHLIGHTCOLOR,
                     );
                 }
@@ -593,105 +1378,32 @@ impl crate::Data<'_> {
         }
     }
 
+    /// Draws a frame around the selected rectangle of tiles.
+    fn highlight_area(&mut self, area: Area) {
+        let [left, top] = [f32::from(area.left) - 0.5, f32::from(area.top) - 0.5];
+        let [right, bottom] = [f32::from(area.right) + 0.5, f32::from(area.bottom) + 0.5];
+        self.draw_line_between_tiles(left, top, right, top, HIGHLIGHTCOLOR2);
+        self.draw_line_between_tiles(left, bottom, right, bottom, HIGHLIGHTCOLOR2);
+        self.draw_line_between_tiles(left, top, left, bottom, HIGHLIGHTCOLOR2);
+        self.draw_line_between_tiles(right, top, right, bottom, HIGHLIGHTCOLOR2);
+    }
+
     /// This function is used by the Level Editor integrated into
     /// freedroid.  It highlights the map position that is currently
     /// edited or would be edited, if the user pressed something.  I.e.
     /// it provides a "cursor" for the Level Editor.
-    #[allow(clippy::similar_names)]
-    fn highlight_current_block(&mut self) {
-        let mut ne_screen = self.graphics.ne_screen.as_mut().unwrap().lock().unwrap();
-        let mut pixels = ne_screen.pixels();
-
-        macro_rules! set_pixels {
-            ($x:expr, $y:expr $(,)?) => {
-                #[allow(clippy::cast_possible_truncation)]
-                pixels
-                    .set(
-                        u16::try_from($x).unwrap(),
-                        u16::try_from($y).unwrap(),
-                        HIGHLIGHTCOLOR,
-                    )
-                    .unwrap();
-            };
-        }
-
-        let user_rect_x = i32::from(self.vars.user_rect.x());
-        let user_rect_width = i32::from(self.vars.user_rect.width());
-        let block_rect_width = f32::from(self.vars.block_rect.width());
-        let block_rect_height = f32::from(self.vars.block_rect.height());
-        let pos_x_r = (self.vars.me.pos.x).round();
-        let pos_y_r = (self.vars.me.pos.y).round();
-
-        let user_center = self.vars.get_user_center();
-        let user_center_y = i32::from(user_center.y());
-        for i in 0..i32::from(self.vars.block_rect.width()) {
-            // This draws a (double) line at the upper border of the current block
-            set_pixels!(
-                i + user_rect_x
-                    + user_rect_width / 2
-                    + ((pos_x_r - self.vars.me.pos.x - 0.5) * block_rect_width) as i32,
-                user_center_y + ((pos_y_r - self.vars.me.pos.y - 0.5) * block_rect_height) as i32,
-            );
-            set_pixels!(
-                i + user_rect_x
-                    + user_rect_width / 2
-                    + ((pos_x_r - self.vars.me.pos.x - 0.5) * block_rect_width) as i32,
-                user_center_y
-                    + ((pos_y_r - self.vars.me.pos.y - 0.5) * block_rect_height) as i32
-                    + 1,
-            );
-
-            // This draws a line at the lower border of the current block
-            set_pixels!(
-                i + user_rect_x
-                    + user_rect_width / 2
-                    + ((pos_x_r - self.vars.me.pos.x - 0.5) * block_rect_width) as i32,
-                user_center_y + ((pos_y_r - self.vars.me.pos.y + 0.5) * block_rect_height) as i32
-                    - 1,
-            );
-            set_pixels!(
-                i + user_rect_x
-                    + user_rect_width / 2
-                    + ((pos_x_r - self.vars.me.pos.x - 0.5) * block_rect_width) as i32,
-                user_center_y + ((pos_y_r - self.vars.me.pos.y + 0.5) * block_rect_height) as i32
-                    - 2,
-            );
-
-            // This draws a line at the left border of the current block
-            set_pixels!(
-                user_rect_x
-                    + user_rect_width / 2
-                    + ((pos_x_r - self.vars.me.pos.x - 0.5) * block_rect_width) as i32,
-                user_center_y
-                    + ((pos_y_r - self.vars.me.pos.y - 0.5) * block_rect_height) as i32
-                    + i,
-            );
-            set_pixels!(
-                1 + user_rect_x
-                    + user_rect_width / 2
-                    + ((pos_x_r - self.vars.me.pos.x - 0.5) * block_rect_width) as i32,
-                user_center_y
-                    + ((pos_y_r - self.vars.me.pos.y - 0.5) * block_rect_height) as i32
-                    + i,
-            );
-
-            // This draws a line at the right border of the current block
-            set_pixels!(
-                -1 + user_rect_x
-                    + user_rect_width / 2
-                    + ((pos_x_r - self.vars.me.pos.x + 0.5) * block_rect_width) as i32,
-                user_center_y
-                    + ((pos_y_r - self.vars.me.pos.y - 0.5) * block_rect_height) as i32
-                    + i,
-            );
-            set_pixels!(
-                -2 + user_rect_x
-                    + user_rect_width / 2
-                    + ((pos_x_r - self.vars.me.pos.x + 0.5) * block_rect_width) as i32,
-                user_center_y
-                    + ((pos_y_r - self.vars.me.pos.y - 0.5) * block_rect_height) as i32
-                    + i,
-            );
+    fn highlight_current_block(&mut self, cursor: CoarsePoint<u8>) {
+        let [left, top] = [f32::from(cursor.x) - 0.5, f32::from(cursor.y) - 0.5];
+        // a double line, the second one a pixel further inside
+        let inset = [self.vars.block_rect.width(), self.vars.block_rect.height()]
+            .map(|len| 1. / f32::from(len));
+        for [inset_x, inset_y] in [[0., 0.], inset] {
+            let [left, top] = [left + inset_x, top + inset_y];
+            let [right, bottom] = [left + 1. - 2. * inset_x, top + 1. - 2. * inset_y];
+            self.draw_line_between_tiles(left, top, right, top, HIGHLIGHTCOLOR);
+            self.draw_line_between_tiles(left, bottom, right, bottom, HIGHLIGHTCOLOR);
+            self.draw_line_between_tiles(left, top, left, bottom, HIGHLIGHTCOLOR);
+            self.draw_line_between_tiles(right, top, right, bottom, HIGHLIGHTCOLOR);
         }
     }
 }
@@ -0,0 +1,239 @@
+//! Auto-tiling of the level editor: painted walls, doors and consoles turn
+//! into the pieces that fit the walls around them, and so do the walls,
+//! doors and consoles next to them.
+
+use super::selection::Area;
+use crate::{defs::MapTile, structs::Level};
+
+use std::{array, ops::Not};
+
+/// The neighbours of a tile, in the order up, right, down, left.
+const NEIGHBOURS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn is_wall(tile: MapTile) -> bool {
+    use MapTile as M;
+
+    matches!(
+        tile,
+        M::EckLu
+            | M::Tu
+            | M::EckRu
+            | M::Tl
+            | M::Kreuz
+            | M::Tr
+            | M::EckLo
+            | M::To
+            | M::EckRo
+            | M::HWall
+            | M::VWall
+    )
+}
+
+/// The door in a wall from left to right, in the same state as `tile`, if
+/// `tile` is a door.
+fn horizontal_door(tile: MapTile) -> Option<MapTile> {
+    use MapTile as M;
+
+    Some(match tile {
+        M::HZutuere | M::VZutuere => M::HZutuere,
+        M::HHalbtuere1 | M::VHalbtuere1 => M::HHalbtuere1,
+        M::HHalbtuere2 | M::VHalbtuere2 => M::HHalbtuere2,
+        M::HHalbtuere3 | M::VHalbtuere3 => M::HHalbtuere3,
+        M::HGanztuere | M::VGanztuere => M::HGanztuere,
+        _ => return None,
+    })
+}
+
+/// The door in a wall from top to bottom, in the same state as `tile`, if
+/// `tile` is a door.
+fn vertical_door(tile: MapTile) -> Option<MapTile> {
+    use MapTile as M;
+
+    Some(match tile {
+        M::HZutuere | M::VZutuere => M::VZutuere,
+        M::HHalbtuere1 | M::VHalbtuere1 => M::VHalbtuere1,
+        M::HHalbtuere2 | M::VHalbtuere2 => M::VHalbtuere2,
+        M::HHalbtuere3 | M::VHalbtuere3 => M::VHalbtuere3,
+        M::HGanztuere | M::VGanztuere => M::VGanztuere,
+        _ => return None,
+    })
+}
+
+fn is_console(tile: MapTile) -> bool {
+    use MapTile as M;
+
+    matches!(tile, M::KonsoleL | M::KonsoleR | M::KonsoleO | M::KonsoleU)
+}
+
+/// Whether a wall on a tile next to `tile` joins it, `side` being the index
+/// of `tile` in [`NEIGHBOURS`] as seen from the wall.  Doors join the walls
+/// they are set in.
+fn joins(tile: MapTile, side: usize) -> bool {
+    let door = if side % 2 == 1 {
+        horizontal_door(tile)
+    } else {
+        vertical_door(tile)
+    };
+    is_wall(tile) || door == Some(tile)
+}
+
+/// The wall piece joining the walls on the given sides.
+fn wall_piece([up, right, down, left]: [bool; 4]) -> MapTile {
+    use MapTile as M;
+
+    match (up, right, down, left) {
+        (true, true, true, true) => M::Kreuz,
+        (true, true, false, true) => M::Tu,
+        (false, true, true, true) => M::To,
+        (true, true, true, false) => M::Tl,
+        (true, false, true, true) => M::Tr,
+        (false, true, true, false) => M::EckLo,
+        (false, false, true, true) => M::EckRo,
+        (true, true, false, false) => M::EckLu,
+        (true, false, false, true) => M::EckRu,
+        (_, false, _, false) if up || down => M::VWall,
+        _ => M::HWall,
+    }
+}
+
+/// The piece of a door or a console fitting the walls on the given sides,
+/// `tile` is kept if the walls leave a choice.
+fn fitting_piece(tile: MapTile, [up, right, down, left]: [bool; 4]) -> MapTile {
+    use MapTile as M;
+
+    if let Some(horizontal) = horizontal_door(tile) {
+        let vertical = vertical_door(tile).unwrap();
+        return match (up || down, left || right) {
+            (true, false) => vertical,
+            (false, true) => horizontal,
+            _ => tile,
+        };
+    }
+
+    // a console stands in front of the wall behind it
+    match (up, right, down, left) {
+        (true, _, false, _) => M::KonsoleO,
+        (false, _, true, _) => M::KonsoleU,
+        (_, false, _, true) => M::KonsoleL,
+        (_, true, _, false) => M::KonsoleR,
+        _ => tile,
+    }
+}
+
+/// Turns the walls, doors and consoles in `area` and next to it into the
+/// pieces that fit the walls around them.
+pub fn auto_tile(level: &mut Level, area: Area) {
+    let tile_at = |level: &Level, x: i32, y: i32| {
+        let (x, y) = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        level.map.get(y)?.get(x).copied()
+    };
+    let positions = || {
+        let (left, right) = (i32::from(area.left) - 1, i32::from(area.right) + 1);
+        let (top, bottom) = (i32::from(area.top) - 1, i32::from(area.bottom) + 1);
+        (top..=bottom).flat_map(move |y| (left..=right).map(move |x| (x, y)))
+    };
+    let set = |level: &mut Level, x: i32, y: i32, tile: MapTile| {
+        level.map[usize::try_from(y).unwrap()][usize::try_from(x).unwrap()] = tile;
+    };
+
+    // the doors and consoles first, the walls join the doors depending on
+    // the way they are set
+    for (x, y) in positions() {
+        let Some(tile) = tile_at(level, x, y) else {
+            continue;
+        };
+        if horizontal_door(tile).is_none() && is_console(tile).not() {
+            continue;
+        }
+
+        let walls = NEIGHBOURS.map(|(dx, dy)| tile_at(level, x + dx, y + dy).is_some_and(is_wall));
+        set(level, x, y, fitting_piece(tile, walls));
+    }
+
+    for (x, y) in positions() {
+        if tile_at(level, x, y).is_some_and(is_wall).not() {
+            continue;
+        }
+
+        let joined = array::from_fn(|side| {
+            let (dx, dy) = NEIGHBOURS[side];
+            tile_at(level, x + dx, y + dy).is_some_and(|tile| joins(tile, side))
+        });
+        set(level, x, y, wall_piece(joined));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{structs::CoarsePoint, test_utils::test_level};
+
+    use MapTile::{
+        EckLo, EckLu, EckRo, EckRu, Floor, HWall, HZutuere, KonsoleO, KonsoleU, Kreuz, Tl, To, Tr,
+        Tu, VWall, VZutuere,
+    };
+
+    fn paint(level: &mut Level, x: u8, y: u8, tile: MapTile) {
+        level.map[usize::from(y)][usize::from(x)] = tile;
+        let cursor = CoarsePoint { x, y };
+        auto_tile(level, Area::new(cursor, cursor));
+    }
+
+    fn rows(level: &Level) -> Vec<&[MapTile]> {
+        level.map[..usize::from(level.ylen)]
+            .iter()
+            .map(Vec::as_slice)
+            .collect()
+    }
+
+    #[test]
+    fn walls_join() {
+        let mut level = test_level(3, 3, Floor);
+        for (x, y) in [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 1),
+        ] {
+            paint(&mut level, x, y, HWall);
+        }
+        assert_eq!(
+            rows(&level),
+            [
+                [EckLo, HWall, EckRo],
+                [VWall, Floor, VWall],
+                [EckLu, HWall, EckRu]
+            ]
+        );
+
+        paint(&mut level, 1, 1, HWall);
+        assert_eq!(
+            rows(&level),
+            [[EckLo, To, EckRo], [Tl, Kreuz, Tr], [EckLu, Tu, EckRu]]
+        );
+    }
+
+    #[test]
+    fn doors_and_consoles_turn() {
+        let mut level = test_level(3, 2, Floor);
+        paint(&mut level, 0, 0, HWall);
+        paint(&mut level, 2, 0, HWall);
+        paint(&mut level, 1, 0, VZutuere);
+        paint(&mut level, 1, 1, KonsoleU);
+        assert_eq!(
+            rows(&level),
+            [[HWall, HZutuere, HWall], [Floor, KonsoleU, Floor]]
+        );
+
+        paint(&mut level, 1, 0, HWall);
+        paint(&mut level, 1, 1, KonsoleU);
+        assert_eq!(
+            rows(&level),
+            [[HWall, HWall, HWall], [Floor, KonsoleO, Floor]]
+        );
+    }
+}
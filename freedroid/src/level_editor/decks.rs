@@ -0,0 +1,208 @@
+//! Adding, deleting and renumbering the decks of the ship, and starting new
+//! ships.
+//!
+//! The decks are numbered from zero on without gaps, in the order of
+//! `Ship::levels`.  The lifts, the crews and the deck rectangles refer to the
+//! decks by number, so they are renumbered along with them.
+
+use super::{NEW_SHIP_VIEW_RECT, lifts::remove_lift};
+use crate::{
+    defs::{MAX_ENEMYS_ON_SHIP, MAX_LEVELS, MapTile},
+    map::new_level,
+    structs::{Crew, Level, Ship},
+};
+
+use log::warn;
+use std::ffi::CString;
+
+/// The size of a new deck.
+const NEW_DECK_XLEN: u8 = 20;
+const NEW_DECK_YLEN: u8 = 12;
+
+/// A new deck: a floor surrounded by walls.
+pub fn new_deck(levelnum: u8, background_song_name: CString) -> Level {
+    use MapTile as M;
+
+    let mut level = new_level(levelnum, NEW_DECK_XLEN, NEW_DECK_YLEN, M::Floor);
+    level.levelname = CString::new(format!("Deck {levelnum}")).unwrap();
+    level.background_song_name = background_song_name;
+
+    let [right, bottom] = [NEW_DECK_XLEN, NEW_DECK_YLEN].map(|len| usize::from(len - 1));
+    for (y, row) in level.map[..=bottom].iter_mut().enumerate() {
+        for (x, tile) in row.iter_mut().enumerate() {
+            *tile = match (x, y) {
+                (0, 0) => M::EckLo,
+                (0, _) if y == bottom => M::EckLu,
+                (_, 0) if x == right => M::EckRo,
+                _ if x == right && y == bottom => M::EckRu,
+                _ if x == 0 || x == right => M::VWall,
+                _ if y == 0 || y == bottom => M::HWall,
+                _ => M::Floor,
+            };
+        }
+    }
+    level
+}
+
+/// A new ship with a single deck, a lift row and no lifts.
+pub fn new_ship(background_song_name: CString) -> Ship {
+    let mut ship = Ship::default();
+    ship.area_name.push_str("New Ship");
+    ship.levels.push(new_deck(0, background_song_name));
+    ship.lift_row_rects.push(NEW_SHIP_VIEW_RECT);
+    ship
+}
+
+/// Adds a new deck after the last one, returning its number.
+pub fn add_deck(ship: &mut Ship, background_song_name: CString) -> Option<u8> {
+    let levelnum = u8::try_from(ship.levels.len()).unwrap();
+    if ship
+        .levels
+        .try_push(new_deck(levelnum, background_song_name))
+        .is_err()
+    {
+        warn!("Maximal number of decks ({MAX_LEVELS}) reached. Cannot add any more.");
+        return None;
+    }
+    ship.crews[usize::from(levelnum)] = Crew::default();
+    ship.level_rects[usize::from(levelnum)].clear();
+    Some(levelnum)
+}
+
+/// Deletes the deck `levelnum` together with its lifts, the following decks
+/// move down by one.
+pub fn delete_deck(ship: &mut Ship, levelnum: u8) {
+    let index = usize::from(levelnum);
+    ship.levels.remove(index);
+    for level in &mut ship.levels[index..] {
+        level.levelnum -= 1;
+    }
+
+    // backwards, so that the indices of the lifts still to be removed stay valid
+    for lift in (0..ship.lifts.len()).rev() {
+        if ship.lifts[lift].level == levelnum {
+            remove_lift(ship, lift);
+        }
+    }
+    for lift in &mut ship.lifts {
+        if lift.level > levelnum {
+            lift.level -= 1;
+        }
+    }
+
+    ship.crews[index..].rotate_left(1);
+    ship.crews[MAX_LEVELS - 1] = Crew::default();
+    ship.level_rects[index..].rotate_left(1);
+    ship.level_rects[MAX_LEVELS - 1].clear();
+}
+
+/// Exchanges the numbers of the decks `a` and `b`.
+pub fn swap_decks(ship: &mut Ship, a: u8, b: u8) {
+    let [index_a, index_b] = [a, b].map(usize::from);
+    ship.levels.swap(index_a, index_b);
+    ship.levels[index_a].levelnum = a;
+    ship.levels[index_b].levelnum = b;
+
+    for lift in &mut ship.lifts {
+        if lift.level == a {
+            lift.level = b;
+        } else if lift.level == b {
+            lift.level = a;
+        }
+    }
+
+    ship.crews.swap(index_a, index_b);
+    ship.level_rects.swap(index_a, index_b);
+}
+
+/// The most random droids the crew of the deck `levelnum` can have, so that
+/// the random droids of all the decks fit into the ship together.
+pub fn max_random_droids(ship: &Ship, levelnum: u8) -> i32 {
+    let others: i32 = ship
+        .crews
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| index != usize::from(levelnum))
+        .map(|(_, crew)| crew.max_rand.max(0))
+        .sum();
+    (i32::try_from(MAX_ENEMYS_ON_SHIP).unwrap() - others).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_editor::lifts::{add_lift, link_lifts};
+
+    use sdl::Rect;
+
+    fn ship() -> Ship {
+        let mut ship = new_ship(CString::default());
+        for _ in 0..2 {
+            add_deck(&mut ship, CString::default()).unwrap();
+        }
+        for levelnum in 0..3 {
+            let index = usize::from(levelnum);
+            add_lift(&mut ship, levelnum, 1, 1).unwrap();
+            ship.crews[index].max_rand = i32::from(levelnum);
+            ship.level_rects[index].push(Rect::new(levelnum.into(), 0, 1, 1));
+        }
+        link_lifts(&mut ship, 0, 1);
+        link_lifts(&mut ship, 1, 2);
+        ship
+    }
+
+    #[test]
+    fn new_deck_is_walled() {
+        let level = new_deck(0, CString::default());
+        assert_eq!(level.map[0][0], MapTile::EckLo);
+        assert_eq!(level.map[0][1], MapTile::HWall);
+        assert_eq!(level.map[1][0], MapTile::VWall);
+        assert_eq!(level.map[1][1], MapTile::Floor);
+        let [right, bottom] = [NEW_DECK_XLEN, NEW_DECK_YLEN].map(|len| usize::from(len - 1));
+        assert_eq!(level.map[bottom][right], MapTile::EckRu);
+    }
+
+    #[test]
+    fn delete_middle_deck() {
+        let mut ship = ship();
+        delete_deck(&mut ship, 1);
+
+        let levelnums: Vec<_> = ship.levels.iter().map(|level| level.levelnum).collect();
+        assert_eq!(levelnums, [0, 1]);
+        let lifts: Vec<_> = ship
+            .lifts
+            .iter()
+            .map(|lift| (lift.level, lift.up, lift.down))
+            .collect();
+        assert_eq!(lifts, [(0, -1, -1), (1, -1, -1)]);
+        assert_eq!(ship.crews[1].max_rand, 2);
+        assert_eq!(ship.crews[2], Crew::default());
+        assert_eq!(ship.level_rects[1][0].x(), 2);
+        assert!(ship.level_rects[2].is_empty());
+    }
+
+    #[test]
+    fn swap_first_decks() {
+        let mut ship = ship();
+        swap_decks(&mut ship, 0, 1);
+
+        let levelnums: Vec<_> = ship.levels.iter().map(|level| level.levelnum).collect();
+        assert_eq!(levelnums, [0, 1, 2]);
+        let lift_levels: Vec<_> = ship.lifts.iter().map(|lift| lift.level).collect();
+        assert_eq!(lift_levels, [1, 0, 2]);
+        assert_eq!(ship.crews[0].max_rand, 1);
+        assert_eq!(ship.level_rects[0][0].x(), 1);
+    }
+
+    #[test]
+    fn random_droids_share_the_ship() {
+        let mut ship = ship();
+        let all = i32::try_from(MAX_ENEMYS_ON_SHIP).unwrap();
+        assert_eq!(max_random_droids(&ship, 0), all - 3);
+        assert_eq!(max_random_droids(&ship, 2), all - 1);
+
+        ship.crews[1].max_rand = all;
+        assert_eq!(max_random_droids(&ship, 0), 0);
+        assert_eq!(max_random_droids(&ship, 1), all - 2);
+    }
+}
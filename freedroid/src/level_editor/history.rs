@@ -0,0 +1,132 @@
+//! The undo and redo history of the level editor.
+//!
+//! Every edit keeps a copy of the level as it was before, so a single
+//! history covers the tiles, the waypoints and their connections alike.  The
+//! changes made in the level editor menu, like the name, the color or the size
+//! of a level, are recorded as edits too, and every level changed there is
+//! taken back on its own.
+
+use crate::structs::Level;
+
+use std::{collections::VecDeque, mem};
+
+/// The number of edits that can be undone.
+const MAX_UNDO_STEPS: usize = 100;
+
+/// A level as it was before or after an edit.
+#[derive(Debug)]
+struct Snapshot {
+    level_index: usize,
+    level: Level,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    /// Records an edit of the level at `level_index`, given its state
+    /// `before` the edit.  Nothing is recorded if the edit changed nothing.
+    pub fn record(&mut self, level_index: usize, before: Level, after: &Level) {
+        if before == *after {
+            return;
+        }
+
+        if self.undo.len() == MAX_UNDO_STEPS {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(Snapshot {
+            level_index,
+            level: before,
+        });
+        self.redo.clear();
+    }
+
+    /// Records the edits of all the `levels`, given their states `before`.
+    pub fn record_levels(&mut self, before: impl IntoIterator<Item = Level>, levels: &[Level]) {
+        for (level_index, (before, after)) in before.into_iter().zip(levels).enumerate() {
+            self.record(level_index, before, after);
+        }
+    }
+
+    /// Takes back the last edit, returning the index of the level that
+    /// changed.
+    pub fn undo(&mut self, levels: &mut [Level]) -> Option<usize> {
+        let snapshot = self.undo.pop_back()?;
+        let snapshot = restore(levels, snapshot);
+        let level_index = snapshot.level_index;
+        self.redo.push(snapshot);
+        Some(level_index)
+    }
+
+    /// Applies again the last edit taken back, returning the index of the
+    /// level that changed.
+    pub fn redo(&mut self, levels: &mut [Level]) -> Option<usize> {
+        let snapshot = self.redo.pop()?;
+        let snapshot = restore(levels, snapshot);
+        let level_index = snapshot.level_index;
+        self.undo.push_back(snapshot);
+        Some(level_index)
+    }
+}
+
+/// Puts the `snapshot` back into `levels`, returning the replaced level.
+fn restore(levels: &mut [Level], mut snapshot: Snapshot) -> Snapshot {
+    mem::swap(&mut levels[snapshot.level_index], &mut snapshot.level);
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defs::MapTile, test_utils::test_level};
+
+    fn level(tile: MapTile) -> Level {
+        test_level(1, 1, tile)
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::default();
+        let mut levels = [level(MapTile::Floor), level(MapTile::Floor)];
+
+        levels[1] = level(MapTile::Kreuz);
+        history.record(1, level(MapTile::Floor), &levels[1]);
+        history.record(1, levels[1].clone(), &levels[1]);
+
+        assert_eq!(history.undo(&mut levels), Some(1));
+        assert_eq!(levels[1], level(MapTile::Floor));
+        assert_eq!(history.undo(&mut levels), None);
+
+        assert_eq!(history.redo(&mut levels), Some(1));
+        assert_eq!(levels[1], level(MapTile::Kreuz));
+        assert_eq!(history.redo(&mut levels), None);
+    }
+
+    #[test]
+    fn only_changed_levels_are_recorded() {
+        let mut history = History::default();
+        let before = [level(MapTile::Floor), level(MapTile::Floor)];
+        let mut levels = before.clone();
+        levels[0].levelname = c"Renamed".into();
+
+        history.record_levels(before, &levels);
+        assert_eq!(history.undo(&mut levels), Some(0));
+        assert_eq!(levels[0], level(MapTile::Floor));
+        assert_eq!(history.undo(&mut levels), None);
+    }
+
+    #[test]
+    fn new_edit_drops_redo() {
+        let mut history = History::default();
+        let mut levels = [level(MapTile::Kreuz)];
+        history.record(0, level(MapTile::Floor), &levels[0]);
+        history.undo(&mut levels);
+
+        levels[0] = level(MapTile::Void);
+        history.record(0, level(MapTile::Floor), &levels[0]);
+        assert_eq!(history.redo(&mut levels), None);
+    }
+}
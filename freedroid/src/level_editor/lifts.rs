@@ -0,0 +1,207 @@
+//! The lifts of the ship: every lift tile needs an entry in the lift list,
+//! which tells the elevator where it leads up and down and in which lift row
+//! it is shown.
+
+use crate::{
+    lifts::{LiftProblem, check_lift_list},
+    structs::{Lift, Ship},
+};
+
+use log::warn;
+use std::ops::Not;
+
+/// The index of the lift at the given position.
+pub fn find_lift(ship: &Ship, level: u8, x: u8, y: u8) -> Option<usize> {
+    ship.lifts
+        .iter()
+        .position(|lift| lift.level == level && lift.x == i32::from(x) && lift.y == i32::from(y))
+}
+
+/// Adds a lift leading nowhere at the given position, returning its index.
+/// The lift is put in the first lift row, so the ship needs one.
+pub fn add_lift(ship: &mut Ship, level: u8, x: u8, y: u8) -> Option<usize> {
+    if ship.lift_row_rects.is_empty() {
+        warn!("The ship has no lift row to show the lift in. Add one first.");
+        return None;
+    }
+    let lift = Lift {
+        level,
+        x: x.into(),
+        y: y.into(),
+        up: -1,
+        down: -1,
+        row: 0,
+    };
+    if ship.lifts.try_push(lift).is_err() {
+        warn!(
+            "Maximal number of lifts ({}) reached. Cannot insert any more.",
+            ship.lifts.capacity(),
+        );
+        return None;
+    }
+    Some(ship.lifts.len() - 1)
+}
+
+/// Removes the lift `index`, the lifts leading to it lead nowhere afterwards.
+pub fn remove_lift(ship: &mut Ship, index: usize) {
+    ship.lifts.remove(index);
+
+    let index = i32::try_from(index).unwrap();
+    for lift in &mut ship.lifts {
+        for link in [&mut lift.up, &mut lift.down] {
+            if *link == index {
+                *link = -1;
+            } else if *link > index {
+                *link -= 1;
+            }
+        }
+    }
+}
+
+/// Puts the lift `upper` above the lift `lower`.  The lifts they were linked
+/// to before lead nowhere afterwards.
+pub fn link_lifts(ship: &mut Ship, lower: usize, upper: usize) {
+    let lifts = &mut ship.lifts;
+    let [lower_link, upper_link] = [lower, upper].map(|index| i32::try_from(index).unwrap());
+
+    let old_upper = usize::try_from(lifts[lower].up).ok();
+    if let Some(old_upper) = old_upper
+        .and_then(|i| lifts.get_mut(i))
+        .filter(|lift| lift.down == lower_link)
+    {
+        old_upper.down = -1;
+    }
+    let old_lower = usize::try_from(lifts[upper].down).ok();
+    if let Some(old_lower) = old_lower
+        .and_then(|i| lifts.get_mut(i))
+        .filter(|lift| lift.up == upper_link)
+    {
+        old_lower.up = -1;
+    }
+
+    lifts[lower].up = upper_link;
+    lifts[upper].down = lower_link;
+}
+
+/// Removes the links of the lift `index` in both directions.
+pub fn unlink_lift(ship: &mut Ship, index: usize) {
+    let link = i32::try_from(index).unwrap();
+    for lift in &mut ship.lifts {
+        for target in [&mut lift.up, &mut lift.down] {
+            if *target == link {
+                *target = -1;
+            }
+        }
+    }
+    ship.lifts[index].up = -1;
+    ship.lifts[index].down = -1;
+}
+
+/// Moves the lift `index` to the next lift row, or the previous one if
+/// `back` is set.
+pub fn step_lift_row(ship: &mut Ship, index: usize, back: bool) {
+    let rows = i32::try_from(ship.lift_row_rects.len()).unwrap();
+    if rows == 0 {
+        return;
+    }
+
+    let row = &mut ship.lifts[index].row;
+    *row = if back { *row - 1 } else { *row + 1 }.rem_euclid(rows);
+}
+
+/// Removes the lift row `row`, the lifts in the following rows move along.
+/// The lifts in the removed row are left in the row taking its place, or in
+/// the one before it if it was the last row.  The only lift row of a ship
+/// with lifts is not removed, returning `false`.
+pub fn remove_lift_row(ship: &mut Ship, row: usize) -> bool {
+    if ship.lift_row_rects.len() == 1 && ship.lifts.is_empty().not() {
+        warn!("The lifts need a lift row to be shown in. Cannot remove the last one.");
+        return false;
+    }
+    ship.lift_row_rects.remove(row);
+
+    let last = i32::try_from(ship.lift_row_rects.len()).unwrap() - 1;
+    let row = i32::try_from(row).unwrap();
+    for lift in &mut ship.lifts {
+        if lift.row > row {
+            lift.row -= 1;
+        }
+        lift.row = lift.row.min(last);
+    }
+    true
+}
+
+/// Checks the lifts of the ship against each other and against the lift
+/// tiles of its decks.
+pub fn check_lifts(ship: &Ship) -> Vec<LiftProblem> {
+    let decks = ship
+        .levels
+        .iter()
+        .map(|level| (level.levelnum, &level.map[..usize::from(level.ylen)]));
+    check_lift_list(&ship.lifts, decks, ship.lift_row_rects.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defs::MapTile, test_utils::test_level};
+
+    use sdl::Rect;
+
+    fn ship() -> Ship {
+        let mut ship = Ship::default();
+        for levelnum in 0..2 {
+            let mut level = test_level(2, 1, MapTile::Floor);
+            level.levelnum = levelnum;
+            level.map[0][1] = MapTile::Lift;
+            ship.levels.push(level);
+        }
+        ship.lift_row_rects.push(Rect::new(0, 0, 1, 1));
+        ship
+    }
+
+    #[test]
+    fn link_and_remove() {
+        let mut ship = ship();
+        let lower = add_lift(&mut ship, 0, 1, 0).unwrap();
+        let upper = add_lift(&mut ship, 1, 1, 0).unwrap();
+        assert_eq!(find_lift(&ship, 1, 1, 0), Some(upper));
+        assert_eq!(check_lifts(&ship), []);
+
+        link_lifts(&mut ship, lower, upper);
+        assert_eq!((ship.lifts[0].up, ship.lifts[1].down), (1, 0));
+        assert_eq!(check_lifts(&ship), []);
+
+        remove_lift(&mut ship, lower);
+        assert_eq!((ship.lifts[0].up, ship.lifts[0].down), (-1, -1));
+        assert_eq!(
+            check_lifts(&ship),
+            [LiftProblem::Unregistered {
+                level: 0,
+                x: 1,
+                y: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn remove_last_lift_row() {
+        let mut ship = ship();
+        ship.lift_row_rects.push(Rect::new(0, 0, 1, 1));
+        let lower = add_lift(&mut ship, 0, 1, 0).unwrap();
+        let upper = add_lift(&mut ship, 1, 1, 0).unwrap();
+        ship.lifts[upper].row = 1;
+
+        assert!(remove_lift_row(&mut ship, 1));
+        assert_eq!((ship.lifts[lower].row, ship.lifts[upper].row), (0, 0));
+        assert_eq!(check_lifts(&ship), []);
+
+        assert!(remove_lift_row(&mut ship, 0).not());
+        assert_eq!(ship.lift_row_rects.len(), 1);
+
+        remove_lift(&mut ship, upper);
+        remove_lift(&mut ship, lower);
+        assert!(remove_lift_row(&mut ship, 0));
+        assert_eq!(add_lift(&mut ship, 0, 1, 0), None);
+    }
+}
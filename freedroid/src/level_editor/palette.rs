@@ -0,0 +1,72 @@
+//! The tile palette at the bottom of the level editor: it shows every map
+//! tile, the left and the right mouse button each paint the one chosen for
+//! them.
+
+use crate::defs::MapTile;
+
+use std::iter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// The tile painted with the left mouse button.
+    pub left: MapTile,
+    /// The tile painted with the right mouse button.
+    pub right: MapTile,
+    /// The index of the first tile shown.
+    first: usize,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            left: MapTile::Floor,
+            right: MapTile::Void,
+            first: 0,
+        }
+    }
+}
+
+/// All the map tiles, in the order of their numbers.
+fn tiles() -> impl Iterator<Item = MapTile> {
+    iter::successors(Some(MapTile::Floor), |tile| tile.next())
+}
+
+impl Palette {
+    /// The tiles shown in `slots` places.
+    pub fn visible(&self, slots: usize) -> impl Iterator<Item = MapTile> {
+        tiles().skip(self.first).take(slots)
+    }
+
+    /// The tile shown in the place `slot`.
+    pub fn tile(&self, slot: usize) -> Option<MapTile> {
+        tiles().nth(self.first + slot)
+    }
+
+    /// Scrolls the palette by `by` tiles, as far as there are tiles to fill
+    /// the `slots` places.
+    pub fn scroll(&mut self, by: isize, slots: usize) {
+        let last_first = tiles().count().saturating_sub(slots);
+        self.first = self.first.saturating_add_signed(by).min(last_first);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_stays_in_range() {
+        let mut palette = Palette::default();
+        palette.scroll(-1, 10);
+        assert_eq!(palette.tile(0), Some(MapTile::Floor));
+
+        palette.scroll(2, 10);
+        assert_eq!(palette.tile(0), Some(MapTile::Tu));
+
+        palette.scroll(100, 10);
+        let shown: Vec<_> = palette.visible(10).collect();
+        assert_eq!(shown.len(), 10);
+        assert_eq!(shown.last(), Some(&MapTile::FineGrid));
+        assert_eq!(palette.tile(10), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! Playing the edited deck from within the level editor, with its real crew
+//! and starting from the block under the cursor.
+
+use crate::{
+    defs::{Cmds, DisplayBannerFlags, Droid, MAXBULLETS, Status},
+    structs::CoarsePoint,
+};
+
+use log::warn;
+use sdl::Rect;
+use std::ops::Not;
+
+impl crate::Data<'_> {
+    /// Plays the current deck until the menu key is pressed or the
+    /// influencer is defeated.  The ship, the droids, the influencer and the
+    /// score are restored afterwards, so nothing that happens during the test
+    /// ends up in the edited ship.
+    pub(super) fn play_test(&mut self, cursor: CoarsePoint<u8>, game_rect: Rect) {
+        let ship = self.main.cur_ship.clone();
+        let enemys = self.main.enemys.clone();
+        let me = self.vars.me.clone();
+        let cur_level_index = self.main.cur_level_index;
+        let (real_score, show_score) = (self.main.real_score, self.main.show_score);
+        let death_count = self.main.death_count;
+        let editor_rect = self.vars.user_rect;
+        let combat_scale_factor = self.global.current_combat_scale_factor;
+
+        // the droids of this deck are rolled again from its crew
+        let levelnum = self.main.cur_level().levelnum;
+        self.main.enemys.retain(|enemy| enemy.levelnum != levelnum);
+        if let Err(err) = self.spawn_crew(levelnum) {
+            warn!("Not all droids of the crew fit on board: {err}");
+        }
+        self.shuffle_enemys();
+
+        self.vars.me.ty = Droid::Droid001;
+        self.vars.me.pos.x = cursor.x.into();
+        self.vars.me.pos.y = cursor.y.into();
+        self.vars.me.speed.x = 0.;
+        self.vars.me.speed.y = 0.;
+        self.vars.me.energy = self.vars.droidmap[Droid::Droid001 as usize].maxenergy;
+        self.vars.me.health = self.vars.me.energy;
+        self.vars.me.status = Status::Mobile;
+        self.vars.me.phase = 0.;
+        self.init_influ_position_history();
+
+        self.vars.user_rect = game_rect;
+        self.set_combat_scale_to(1.);
+        self.clear_graph_mem();
+        self.display_banner(None, None, DisplayBannerFlags::FORCE_UPDATE);
+        self.activate_conservative_frame_computation();
+        self.main.play_test = true;
+        self.game_over = false;
+
+        while self.quit.get().not() && self.game_over.not() {
+            if self.cmd_is_active_r(Cmds::Menu) {
+                break;
+            }
+
+            self.start_taking_time_for_fps_calculation();
+            self.game_frame(false);
+            if self.global.game_config.hog_cpu.not() {
+                self.sdl.delay_ms(1);
+            }
+            self.compute_fps_for_this_frame();
+        }
+
+        self.main.play_test = false;
+        self.game_over = false;
+        for bullet in 0..MAXBULLETS {
+            self.delete_bullet(bullet);
+        }
+        for blast in &mut self.main.all_blasts {
+            blast.phase = (Status::Out as u8).into();
+            blast.ty = None;
+        }
+
+        self.main.cur_ship = ship;
+        self.main.enemys = enemys;
+        self.vars.me = me;
+        self.main.cur_level_index = cur_level_index;
+        self.main.real_score = real_score;
+        self.main.show_score = show_score;
+        self.main.death_count = death_count;
+        self.set_time_factor(1.0);
+
+        self.vars.user_rect = editor_rect;
+        self.global.current_combat_scale_factor = combat_scale_factor;
+        self.set_combat_scale_to(combat_scale_factor);
+        self.clear_graph_mem();
+    }
+}
@@ -0,0 +1,399 @@
+//! The selection of the level editor: a rectangle of tiles, which can be
+//! filled with a tile, copied, pasted elsewhere and mirrored.
+
+use super::delete_waypoint;
+use crate::{
+    defs::MapTile,
+    structs::{CoarsePoint, Level, Waypoint},
+};
+
+use log::{info, warn};
+use std::ops::Not;
+
+/// A rectangle of tiles, the borders included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub left: u8,
+    pub top: u8,
+    pub right: u8,
+    pub bottom: u8,
+}
+
+impl Area {
+    /// The rectangle spanned by two opposite corners.
+    pub fn new(a: CoarsePoint<u8>, b: CoarsePoint<u8>) -> Self {
+        Self {
+            left: a.x.min(b.x),
+            top: a.y.min(b.y),
+            right: a.x.max(b.x),
+            bottom: a.y.max(b.y),
+        }
+    }
+
+    fn contains(self, x: u8, y: u8) -> bool {
+        (self.left..=self.right).contains(&x) && (self.top..=self.bottom).contains(&y)
+    }
+
+    fn width(self) -> u8 {
+        self.right - self.left + 1
+    }
+
+    fn height(self) -> u8 {
+        self.bottom - self.top + 1
+    }
+}
+
+/// The direction in which a selection is mirrored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirror {
+    LeftRight,
+    TopBottom,
+}
+
+/// Copied tiles, with the waypoints among them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clipboard {
+    width: u8,
+    height: u8,
+    /// The tiles, row after row.
+    tiles: Vec<MapTile>,
+    /// The waypoints, relative to the upper left corner.  Only the
+    /// connections between copied waypoints are kept.
+    waypoints: Vec<Waypoint>,
+}
+
+#[derive(Debug, Default)]
+pub struct Selection {
+    /// The corner where the selection was started, the opposite one is the
+    /// cursor.
+    anchor: Option<CoarsePoint<u8>>,
+    clipboard: Option<Clipboard>,
+}
+
+impl Selection {
+    /// Starts a selection at the cursor, or ends the current one.
+    pub fn toggle(&mut self, cursor: CoarsePoint<u8>) {
+        self.anchor = match self.anchor {
+            Some(_) => None,
+            None => Some(cursor),
+        };
+    }
+
+    /// Ends the current selection.
+    pub fn clear(&mut self) {
+        self.anchor = None;
+    }
+
+    /// The selected rectangle, if a selection has been started.
+    pub fn area(&self, cursor: CoarsePoint<u8>) -> Option<Area> {
+        self.anchor.map(|anchor| Area::new(anchor, cursor))
+    }
+
+    /// The selected rectangle, or only the tile under the cursor.
+    pub fn area_or_cursor(&self, cursor: CoarsePoint<u8>) -> Area {
+        self.area(cursor)
+            .unwrap_or_else(|| Area::new(cursor, cursor))
+    }
+
+    /// Copies the selected rectangle, or the tile under the cursor.
+    pub fn copy(&mut self, level: &Level, cursor: CoarsePoint<u8>) {
+        let area = self.area_or_cursor(cursor);
+        info!(
+            "Copied {}x{} tiles at {}/{}.",
+            area.width(),
+            area.height(),
+            area.left,
+            area.top
+        );
+        self.clipboard = Some(copy(level, area));
+    }
+
+    /// Pastes the copied tiles with their upper left corner at the cursor.
+    ///
+    /// Returns `false` if nothing has been copied yet.
+    pub fn paste(&self, level: &mut Level, cursor: CoarsePoint<u8>, with_waypoints: bool) -> bool {
+        let Some(clipboard) = &self.clipboard else {
+            warn!("Nothing has been copied yet, there is nothing to paste.");
+            return false;
+        };
+        paste(level, clipboard, cursor, with_waypoints);
+        true
+    }
+}
+
+/// Fills all the tiles of `area` with `tile`.
+pub fn fill(level: &mut Level, area: Area, tile: MapTile) {
+    for row in &mut level.map[usize::from(area.top)..=usize::from(area.bottom)] {
+        row[usize::from(area.left)..=usize::from(area.right)].fill(tile);
+    }
+}
+
+fn copy(level: &Level, area: Area) -> Clipboard {
+    let tiles = level.map[usize::from(area.top)..=usize::from(area.bottom)]
+        .iter()
+        .flat_map(|row| &row[usize::from(area.left)..=usize::from(area.right)])
+        .copied()
+        .collect();
+
+    let copied: Vec<usize> = level
+        .waypoints
+        .iter()
+        .enumerate()
+        .filter(|(_, waypoint)| area.contains(waypoint.x, waypoint.y))
+        .map(|(index, _)| index)
+        .collect();
+    let waypoints = copied
+        .iter()
+        .map(|&index| {
+            let waypoint = &level.waypoints[index];
+            Waypoint {
+                x: waypoint.x - area.left,
+                y: waypoint.y - area.top,
+                connections: waypoint
+                    .connections
+                    .iter()
+                    .filter_map(|&connection| {
+                        copied
+                            .iter()
+                            .position(|&index| index == usize::from(connection))
+                    })
+                    .map(|index| u8::try_from(index).unwrap())
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Clipboard {
+        width: area.width(),
+        height: area.height(),
+        tiles,
+        waypoints,
+    }
+}
+
+/// Pastes `clipboard` with its upper left corner at `corner`.  What does not
+/// fit into the level is left out.  With `with_waypoints`, the waypoints of
+/// the covered tiles are replaced by the copied ones.
+fn paste(level: &mut Level, clipboard: &Clipboard, corner: CoarsePoint<u8>, with_waypoints: bool) {
+    let right =
+        (u16::from(corner.x) + u16::from(clipboard.width) - 1).min(u16::from(level.xlen) - 1);
+    let bottom =
+        (u16::from(corner.y) + u16::from(clipboard.height) - 1).min(u16::from(level.ylen) - 1);
+    let area = Area {
+        left: corner.x,
+        top: corner.y,
+        right: u8::try_from(right).unwrap(),
+        bottom: u8::try_from(bottom).unwrap(),
+    };
+
+    for (row, tiles) in clipboard
+        .tiles
+        .chunks(usize::from(clipboard.width))
+        .take(usize::from(area.height()))
+        .enumerate()
+    {
+        let y = usize::from(area.top) + row;
+        let x = usize::from(area.left);
+        level.map[y][x..=usize::from(area.right)]
+            .copy_from_slice(&tiles[..usize::from(area.width())]);
+    }
+
+    if with_waypoints.not() {
+        return;
+    }
+
+    while let Some(index) = level
+        .waypoints
+        .iter()
+        .rposition(|waypoint| area.contains(waypoint.x, waypoint.y))
+    {
+        delete_waypoint(level, index.try_into().unwrap());
+    }
+
+    // the new number of each copied waypoint, if it can be pasted
+    let mut numbers = Vec::with_capacity(clipboard.waypoints.len());
+    let mut next_number = level.waypoints.len();
+    for waypoint in &clipboard.waypoints {
+        let position = corner
+            .x
+            .checked_add(waypoint.x)
+            .zip(corner.y.checked_add(waypoint.y));
+        let fits = position.is_some_and(|(x, y)| area.contains(x, y));
+        if fits && next_number < level.waypoints.capacity() {
+            numbers.push(Some(u8::try_from(next_number).unwrap()));
+            next_number += 1;
+        } else {
+            numbers.push(None);
+        }
+    }
+    if next_number - level.waypoints.len() < clipboard.waypoints.len() {
+        warn!("Not all of the copied waypoints could be pasted.");
+    }
+
+    for (waypoint, number) in clipboard.waypoints.iter().zip(&numbers) {
+        if number.is_none() {
+            continue;
+        }
+        level.waypoints.push(Waypoint {
+            x: corner.x + waypoint.x,
+            y: corner.y + waypoint.y,
+            connections: waypoint
+                .connections
+                .iter()
+                .filter_map(|&connection| numbers[usize::from(connection)])
+                .collect(),
+        });
+    }
+}
+
+/// Mirrors the tiles and the waypoints of `area`.
+pub fn mirror(level: &mut Level, area: Area, mirror: Mirror) {
+    let columns = usize::from(area.left)..=usize::from(area.right);
+    let (top, bottom) = (usize::from(area.top), usize::from(area.bottom));
+    match mirror {
+        Mirror::LeftRight => {
+            for row in &mut level.map[top..=bottom] {
+                row[columns.clone()].reverse();
+            }
+        }
+        Mirror::TopBottom => {
+            let height = bottom - top + 1;
+            for offset in 0..height / 2 {
+                let (upper, lower) = level.map.split_at_mut(bottom - offset);
+                upper[top + offset][columns.clone()]
+                    .swap_with_slice(&mut lower[0][columns.clone()]);
+            }
+        }
+    }
+    for row in &mut level.map[top..=bottom] {
+        for tile in &mut row[columns.clone()] {
+            *tile = mirror_tile(*tile, mirror);
+        }
+    }
+
+    for waypoint in &mut level.waypoints {
+        if area.contains(waypoint.x, waypoint.y) {
+            match mirror {
+                Mirror::LeftRight => waypoint.x = area.left + area.right - waypoint.x,
+                Mirror::TopBottom => waypoint.y = area.top + area.bottom - waypoint.y,
+            }
+        }
+    }
+}
+
+/// The tile which looks like `tile` in a mirror.
+fn mirror_tile(tile: MapTile, mirror: Mirror) -> MapTile {
+    use MapTile::{
+        EckLo, EckLu, EckRo, EckRu, KonsoleL, KonsoleO, KonsoleR, KonsoleU, Tl, To, Tr, Tu,
+    };
+
+    match mirror {
+        Mirror::LeftRight => match tile {
+            EckLu => EckRu,
+            EckRu => EckLu,
+            EckLo => EckRo,
+            EckRo => EckLo,
+            Tl => Tr,
+            Tr => Tl,
+            KonsoleL => KonsoleR,
+            KonsoleR => KonsoleL,
+            _ => tile,
+        },
+        Mirror::TopBottom => match tile {
+            EckLu => EckLo,
+            EckLo => EckLu,
+            EckRu => EckRo,
+            EckRo => EckRu,
+            Tu => To,
+            To => Tu,
+            KonsoleU => KonsoleO,
+            KonsoleO => KonsoleU,
+            _ => tile,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_level, waypoint};
+
+    use MapTile::{EckLu, EckRu, Floor, Kreuz, Tl, Tu, Void};
+
+    fn point(x: u8, y: u8) -> CoarsePoint<u8> {
+        CoarsePoint { x, y }
+    }
+
+    #[test]
+    fn fill_area() {
+        let mut level = test_level(4, 3, Floor);
+        fill(&mut level, Area::new(point(2, 1), point(1, 2)), Kreuz);
+        assert_eq!(level.map[0], [Floor; 4]);
+        assert_eq!(level.map[1], [Floor, Kreuz, Kreuz, Floor]);
+        assert_eq!(level.map[2], [Floor, Kreuz, Kreuz, Floor]);
+    }
+
+    #[test]
+    fn copy_and_paste() {
+        let mut level = test_level(4, 4, Floor);
+        level.map[0][0] = EckLu;
+        level.map[0][1] = Tu;
+        level.waypoints.push(waypoint(0, 0, &[1, 2]));
+        level.waypoints.push(waypoint(3, 3, &[0]));
+        level.waypoints.push(waypoint(1, 1, &[0]));
+
+        let mut selection = Selection::default();
+        selection.toggle(point(0, 0));
+        selection.copy(&level, point(1, 1));
+        assert!(selection.paste(&mut level, point(3, 2), true));
+
+        assert_eq!(level.map[2][3..], [EckLu]);
+        assert_eq!(level.map[3][3..], [Floor]);
+        // the waypoint at 3/3 is replaced, the copied one at 1/1 does not fit
+        assert_eq!(
+            level.waypoints[..],
+            [
+                waypoint(0, 0, &[1]),
+                waypoint(1, 1, &[0]),
+                waypoint(3, 2, &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn paste_without_waypoints() {
+        let mut level = test_level(3, 1, Void);
+        level.waypoints.push(waypoint(2, 0, &[]));
+
+        let mut selection = Selection::default();
+        assert!(selection.paste(&mut level, point(0, 0), false).not());
+        selection.copy(&test_level(1, 1, Kreuz), point(0, 0));
+        assert!(selection.paste(&mut level, point(2, 0), false));
+        assert_eq!(level.map[0], [Void, Void, Kreuz]);
+        assert_eq!(level.waypoints[..], [waypoint(2, 0, &[])]);
+    }
+
+    #[test]
+    fn mirror_area() {
+        let mut level = test_level(3, 2, Floor);
+        level.map[0][..2].copy_from_slice(&[EckLu, Tl]);
+        level.waypoints.push(waypoint(0, 1, &[]));
+
+        mirror(
+            &mut level,
+            Area::new(point(0, 0), point(1, 1)),
+            Mirror::LeftRight,
+        );
+        assert_eq!(level.map[0], [MapTile::Tr, EckRu, Floor]);
+        assert_eq!(level.waypoints[0], waypoint(1, 1, &[]));
+
+        mirror(
+            &mut level,
+            Area::new(point(0, 0), point(2, 1)),
+            Mirror::TopBottom,
+        );
+        assert_eq!(level.map[0], [Floor; 3]);
+        assert_eq!(level.map[1], [MapTile::Tr, MapTile::EckRo, Floor]);
+        assert_eq!(level.waypoints[0], waypoint(1, 0, &[]));
+    }
+}
@@ -0,0 +1,635 @@
+//! Freedroid Classic, a clone of the C64 game Paradroid.
+//!
+//! The `freedroid` program only calls [`run`].  The game simulation can also be
+//! run without a window with [`run_headless`], so gameplay can be checked on
+//! machines without a display.
+
+mod array_c_string;
+mod b_font;
+mod bullet;
+mod convert;
+mod defs;
+mod enemy;
+mod error;
+mod global;
+mod graphics;
+mod headless;
+mod highscore;
+mod influencer;
+mod init;
+mod input;
+#[cfg(not(target_os = "android"))]
+mod level_editor;
+mod lifts;
+mod map;
+mod menu;
+mod misc;
+mod pathfinding;
+mod replay;
+mod savegame;
+mod ship;
+mod sound;
+mod structs;
+mod takeover;
+#[cfg(test)]
+mod test_utils;
+mod text;
+mod user_dirs;
+mod validate;
+mod vars;
+mod view;
+mod waypoints;
+
+pub use error::LoadError;
+pub use headless::{HeadlessReport, run_headless, use_dummy_video_driver};
+pub use init::Opt;
+
+use array_c_string::ArrayCString;
+use arrayvec::ArrayVec;
+use b_font::BFont;
+use clap::Parser;
+use defs::{
+    AlertLevel, AssembleCombatWindowFlags, BYCOLOR, DROID_ROTATION_TIME, DisplayBannerFlags,
+    MAX_ENEMYS_ON_SHIP, MAXBLASTS, MAXBULLETS, SHOW_WAIT, STANDARD_MISSION, Status,
+};
+use error::LoadError;
+use global::Global;
+use graphics::Graphics;
+use highscore::Highscore;
+use influencer::Influencer;
+use init::{Command, Init, Opt};
+use input::Input;
+use log::info;
+use map::{Color, Map};
+use menu::Menu;
+use misc::Misc;
+use once_cell::unsync::OnceCell;
+use pathfinding::Paths;
+use qcell::{TCell, TCellOwner};
+use rand::{SeedableRng, rngs::StdRng};
+use sdl::{Rect, convert::u8_to_usize};
+use sound::Sound;
+use structs::{Blast, Bullet, Enemy, Level, Ship};
+use takeover::Takeover;
+use text::Text;
+use vars::Vars;
+
+use std::{
+    array,
+    cell::Cell,
+    fs::File,
+    ops::{ControlFlow, Not},
+    path::{Path, PathBuf},
+    process,
+};
+
+#[allow(clippy::struct_excessive_bools)]
+struct Main<'sdl> {
+    last_got_into_blast_sound: f32,
+    last_refresh_sound: f32,
+    // Toggle TRUE/FALSE for turning sounds on/off
+    sound_on: bool,
+    // the current level data
+    cur_level_index: Option<usize>,
+    // the current ship-data
+    cur_ship: Ship,
+    show_score: u32,
+    real_score: f32,
+    // a cumulative/draining counter of kills->determines Alert!
+    death_count: f32,
+    // drain per second
+    death_count_drain_speed: f32,
+    alert_level: AlertLevel,
+    // threshold for FIRST Alert-color (yellow), the others are 2*, 3*..
+    alert_threshold: u16,
+    // bonus/sec for FIRST Alert-color, the others are 2*, 3*,...
+    alert_bonus_per_sec: f32,
+    enemys: ArrayVec<Enemy, MAX_ENEMYS_ON_SHIP>,
+    // the shortest ways between the waypoints of the levels
+    paths: Paths,
+    // where the config file is kept
+    config_dir: Option<PathBuf>,
+    // where the highscores and the saved games are kept
+    data_dir: Option<PathBuf>,
+    // the mission started by the next game
+    next_mission: String,
+    invincible_mode: bool,
+    /* display enemys regardless of IsVisible() */
+    show_all_droids: bool,
+    /* for bullet debugging: stop where u are */
+    stop_influencer: bool,
+    number_of_droid_types: u8,
+    all_bullets: [Option<Bullet<'sdl>>; u8_to_usize(MAXBULLETS)],
+    all_blasts: [Blast; u8_to_usize(MAXBLASTS)],
+    first_digit_rect: Rect,
+    second_digit_rect: Rect,
+    third_digit_rect: Rect,
+    f_p_sover1: f32,
+    // running without a window: no pictures, no sound, no interactive screens
+    headless: bool,
+    // playing a deck from the level editor: a defeat ends the test instead of the game
+    play_test: bool,
+}
+
+impl Default for Main<'_> {
+    fn default() -> Self {
+        Self {
+            last_got_into_blast_sound: 2.,
+            last_refresh_sound: 2.,
+            sound_on: true,
+            cur_level_index: None,
+            cur_ship: Ship::default(),
+            show_score: 0,
+            real_score: 0.,
+            death_count: 0.,
+            death_count_drain_speed: 0.,
+            alert_level: AlertLevel::default(),
+            alert_threshold: 0,
+            alert_bonus_per_sec: 0.,
+            enemys: ArrayVec::new(),
+            paths: Paths::default(),
+            config_dir: None,
+            data_dir: None,
+            next_mission: STANDARD_MISSION.to_owned(),
+            invincible_mode: false,
+            show_all_droids: false,
+            stop_influencer: false,
+            number_of_droid_types: 0,
+            all_bullets: array::from_fn(|_| None),
+            all_blasts: array::from_fn(|_| Blast::default()),
+            first_digit_rect: Rect::default(),
+            second_digit_rect: Rect::default(),
+            third_digit_rect: Rect::default(),
+            f_p_sover1: 0.,
+            headless: false,
+            play_test: false,
+        }
+    }
+}
+
+type Sdl = sdl::Sdl<sdl::Video, sdl::Timer, OnceCell<sdl::joystick::System>, OnceCell<sdl::Mixer>>;
+
+pub struct FontCellMarker;
+type FontCell<'sdl> = TCell<FontCellMarker, b_font::Info<'sdl>>;
+type FontCellOwner = TCellOwner<FontCellMarker>;
+
+struct Data<'sdl> {
+    game_over: bool,
+    sdl: &'sdl Sdl,
+    map: Map,
+    b_font: BFont<'sdl>,
+    highscore: Highscore,
+    influencer: Influencer,
+    init: Init,
+    text: Text,
+    sound: Option<Sound<'sdl>>,
+    misc: Misc,
+    ship: ship::Data<'sdl>,
+    input: Input,
+    menu: Menu<'sdl>,
+    global: Global<'sdl>,
+    vars: Vars<'sdl>,
+    takeover: Takeover<'sdl>,
+    graphics: Graphics<'sdl>,
+    main: Main<'sdl>,
+    quit: Cell<bool>,
+    font_owner: FontCellOwner,
+    // the one source of randomness for everything that affects the game
+    rng: StdRng,
+}
+
+impl<'sdl> Data<'sdl> {
+    fn new(sdl: &'sdl Sdl) -> Self {
+        Self {
+            game_over: false,
+            sdl,
+            map: Map::default(),
+            b_font: BFont::default(),
+            highscore: Highscore::default(),
+            influencer: Influencer::default(),
+            init: Init::default(),
+            text: Text::default(),
+            sound: Option::default(),
+            misc: Misc::default(),
+            ship: ship::Data::default(),
+            input: Input::default(),
+            menu: Menu::default(),
+            global: Global::default(),
+            vars: Vars::default(),
+            takeover: Takeover::default(),
+            graphics: Graphics::default(),
+            main: Main::default(),
+            quit: Cell::new(false),
+            font_owner: FontCellOwner::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+fn init_sdl() -> Sdl {
+    let sdl = sdl::init().video().timer().build().unwrap_or_else(|| {
+        // Safety: no other SDL function will be used -- we are panicking.
+        unsafe {
+            sdl::get_error(|err| {
+                panic!("Couldn't initialize SDL: {}", err.to_string_lossy());
+            })
+        }
+    });
+    info!("SDL initialisation successful.");
+    sdl
+}
+
+/// Runs the game as the `freedroid` program, as told by the command line.
+pub fn run() {
+    env_logger::init();
+
+    let opt = Opt::parse();
+    if let Some(Command::Validate { mission }) = &opt.command {
+        let problems = validate::validate_mission(mission, opt.data_search_path());
+        for problem in &problems {
+            println!("{problem}");
+        }
+        if problems.is_empty() {
+            println!("No problems found in mission {mission}.");
+            return;
+        }
+        println!("{} problems found in mission {mission}.", problems.len());
+        process::exit(1);
+    }
+
+    if let Some(Command::Convert { input, output }) = &opt.command {
+        if let Err(err) = convert::convert(input, output) {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+        return;
+    }
+
+    if opt.headless {
+        // SAFETY: nothing but the main thread has been started yet
+        unsafe {
+            headless::use_dummy_video_driver();
+        }
+        match headless::run_headless(&opt) {
+            Ok(report) => println!("{report}"),
+            Err(err) => {
+                eprintln!("{err}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let sdl = init_sdl();
+    let mut data = Data::new(&sdl);
+
+    data.input.joy_sensitivity = 1;
+    data.init_freedroid(&opt); // Initialisation of global variables and arrays
+    if data.quit.get() {
+        // nothing has been loaded that could be written back
+        info!("Freedroid could not be started.");
+        return;
+    }
+    sdl.cursor().hide();
+
+    #[cfg(target_os = "windows")]
+    {
+        // spread the word :)
+        win32_disclaimer();
+    }
+
+    while data.quit.get().not() {
+        if matches!(game_single_loop(&mut data, &sdl), ControlFlow::Break(())) {
+            break;
+        }
+    }
+
+    info!("Termination of Freedroid initiated.");
+    info!("Writing config file");
+    data.save_game_config();
+
+    info!("Writing highscores to disk");
+    data.save_highscores();
+
+    info!("Thank you for playing Freedroid.");
+}
+
+fn game_single_loop<'sdl>(data: &mut Data<'sdl>, sdl: &'sdl Sdl) -> ControlFlow<()> {
+    let mission = data.main.next_mission.clone();
+    data.init_new_mission(&mission);
+    if data.quit.get() {
+        return ControlFlow::Break(());
+    }
+
+    data.scale_ship_rects();
+
+    // release all keys
+    data.wait_for_all_keys_released();
+
+    data.show_droid_info(data.vars.me.ty, Page::TitleScreen, false); // show unit-intro page
+    data.show_droid_portrait(
+        data.vars.cons_droid_rect,
+        data.vars.me.ty,
+        DROID_ROTATION_TIME,
+        ShowDroidPortraitFlags::RESET,
+    );
+    let now = sdl.ticks_ms();
+    while data.quit.get().not() && sdl.ticks_ms() - now < SHOW_WAIT && !data.fire_pressed_r() {
+        data.show_droid_portrait(
+            data.vars.cons_droid_rect,
+            data.vars.me.ty,
+            DROID_ROTATION_TIME,
+            ShowDroidPortraitFlags::empty(),
+        );
+        sdl.delay_ms(1);
+    }
+
+    data.clear_graph_mem();
+    data.display_banner(
+        None,
+        None,
+        DisplayBannerFlags::FORCE_UPDATE | DisplayBannerFlags::NO_SDL_UPDATE,
+    );
+    assert!(data.graphics.ne_screen.as_mut().unwrap().flip());
+
+    data.game_over = false;
+    data.start_input_session();
+
+    data.graphics
+        .crosshair_cursor
+        .as_ref()
+        .unwrap()
+        .set_active(); // default cursor is a crosshair
+    sdl.cursor().show();
+
+    while data.quit.get().not() && data.game_over.not() {
+        data.start_taking_time_for_fps_calculation();
+        data.game_frame(true);
+
+        if data.global.game_config.hog_cpu.not() {
+            // don't use up 100% CPU unless requested
+            sdl.delay_ms(1);
+        }
+
+        data.compute_fps_for_this_frame();
+    }
+
+    data.finish_input_session();
+    ControlFlow::Continue(())
+}
+
+impl Data<'_> {
+    /// One frame of the game: the input, the movements, the collisions and
+    /// the combat picture.  The mission is only completed if
+    /// `check_mission` is set, which a play-test in the level editor does
+    /// not.  In the headless mode there is neither input nor a picture.
+    fn game_frame(&mut self, check_mission: bool) {
+        let headless = self.main.headless;
+        self.update_counters_for_this_frame();
+        if headless.not() {
+            self.react_to_special_keys();
+
+            if self.input.show_cursor {
+                self.sdl.cursor().show();
+            } else {
+                self.sdl.cursor().hide();
+            }
+        }
+
+        self.move_level_doors();
+        self.animate_refresh();
+        self.explode_blasts(); // move blasts to the right current "phase" of the blast
+        self.alert_level_warning(); // tout tout, blink blink... Alert!!
+        if headless.not() {
+            self.display_banner(None, None, DisplayBannerFlags::empty());
+        }
+        self.move_bullets(); // leave this in front of graphics output: time_in_frames should start with 1
+        if headless.not() {
+            self.assemble_combat_picture(AssembleCombatWindowFlags::DO_SCREEN_UPDATE);
+        }
+
+        for bullet in 0..MAXBULLETS {
+            self.check_bullet_collisions(bullet);
+        }
+
+        // change Influ-speed depending on keys pressed, but
+        // also change his status and position and "phase" of rotation
+        self.move_influence();
+        self.move_enemys(); // move all the enemys:
+        // also do attacks on influ and also move "phase" or their rotation
+        self.check_influence_wall_collisions(); /* Testen ob der Weg nicht durch Mauern verstellt ist */
+        self.check_influence_enemy_collision();
+
+        self.control_time_flow();
+        if check_mission {
+            self.check_if_mission_is_complete();
+        }
+    }
+
+    /// Controls the speed of time-flow: dark-levels=emptyLevelSpeedup, normal-levels=1.0
+    fn control_time_flow(&mut self) {
+        let cur_level = self.main.cur_level_mut();
+        if cur_level.empty.not() {
+            self.set_time_factor(1.0);
+        } else if cur_level.color == Color::Dark {
+            // if level is already dark
+            self.set_time_factor(self.global.game_config.empty_level_speedup);
+        } else if cur_level.timer <= 0. {
+            // time to switch off the lights ...
+            cur_level.color = Color::Dark;
+            self.switch_background_music_to(Some(BYCOLOR)); // start new background music
+        }
+    }
+
+    /// This function updates counters and is called ONCE every frame.
+    /// The counters include timers, but framerate-independence of game speed
+    /// is preserved because everything is weighted with the [`frame_time`]
+    /// function.
+    ///
+    /// [`frame_time`]: Data::frame_time
+    fn update_counters_for_this_frame(&mut self) {
+        // Here are some things, that were previously done by some periodic */
+        // interrupt function
+        self.main.last_got_into_blast_sound += self.frame_time();
+        self.main.last_refresh_sound += self.frame_time();
+        self.vars.me.last_crysound_time += self.frame_time();
+        self.vars.me.timer += self.frame_time();
+
+        let mut timer = self.main.cur_level().timer;
+        if timer >= 0.0 {
+            timer -= self.frame_time();
+            self.main.cur_level_mut().timer = timer;
+        }
+
+        self.vars.me.last_transfer_sound_time += self.frame_time();
+        self.vars.me.text_visible_time += self.frame_time();
+        self.global.level_doors_not_moved_time += self.frame_time();
+        if self.global.skip_a_few_frames {
+            self.global.skip_a_few_frames = false;
+        }
+
+        if self.vars.me.firewait > 0. {
+            self.vars.me.firewait -= self.frame_time();
+            if self.vars.me.firewait < 0. {
+                self.vars.me.firewait = 0.;
+            }
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+        {
+            if self.main.real_score > self.main.show_score as f32 {
+                self.main.show_score = self.main.show_score.saturating_add(1);
+            }
+            if self.main.real_score < self.main.show_score as f32 {
+                self.main.show_score = self.main.show_score.saturating_sub(1);
+            }
+        }
+
+        // drain Death-count, responsible for Alert-state
+        if self.main.death_count > 0. {
+            self.main.death_count -= self.main.death_count_drain_speed * self.frame_time();
+        }
+        if self.main.death_count < 0. {
+            self.main.death_count = 0.;
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+        {
+            // and switch Alert-level according to DeathCount
+            self.main.alert_level =
+                AlertLevel::from_death_count(self.main.death_count, self.main.alert_threshold);
+            // player gets a bonus/second in AlertLevel
+            self.main.real_score += f32::from(self.main.alert_level)
+                * self.main.alert_bonus_per_sec
+                * self.frame_time();
+        }
+
+        let Self {
+            main, misc, global, ..
+        } = self;
+        for enemy in &mut main.enemys {
+            if enemy.status == Status::Out {
+                continue;
+            }
+
+            if enemy.warten > 0. {
+                enemy.warten -= misc.frame_time(global, main.f_p_sover1);
+                if enemy.warten < 0. {
+                    enemy.warten = 0.;
+                }
+            }
+
+            if enemy.firewait > 0. {
+                enemy.firewait -= misc.frame_time(global, main.f_p_sover1);
+                if enemy.firewait <= 0. {
+                    enemy.firewait = 0.;
+                }
+            }
+
+            enemy.text_visible_time += misc.frame_time(global, main.f_p_sover1);
+        }
+    }
+}
+
+#[inline]
+fn find_subslice(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).position(|s| s == needle)
+}
+
+#[inline]
+fn split_at_subslice<'a>(data: &'a [u8], needle: &[u8]) -> Option<(&'a [u8], &'a [u8])> {
+    let pos = find_subslice(data, needle)?;
+    let (before, after) = data.split_at(pos);
+    Some((before, &after[needle.len()..]))
+}
+
+/// This function read in a file with the specified name, allocated
+/// memory for it of course, looks for the file end string and then
+/// terminates the whole read in file with a 0 character, so that it
+/// can easily be treated like a common string.
+pub(crate) fn read_and_malloc_and_terminate_file(
+    filename: &Path,
+    file_end_string: &[u8],
+) -> Result<Box<[u8]>, LoadError> {
+    use bstr::ByteSlice;
+    use std::io::Read;
+
+    info!(
+        "ReadAndMallocAndTerminateFile: The filename is: {}",
+        filename.display()
+    );
+
+    let io_error = |error| LoadError::Io {
+        file: filename.to_owned(),
+        error,
+    };
+
+    // Read the whole theme data to memory
+    let mut file = File::open(filename).map_err(io_error)?;
+    info!("ReadAndMallocAndTerminateFile: Opening file succeeded...");
+
+    let file_len = file.metadata().map_err(io_error)?.len();
+    let file_len = usize::try_from(file_len)
+        .map_err(|_| LoadError::invalid_data(filename, "the file is too big"))?;
+    info!("ReadAndMallocAndTerminateFile: fstating file succeeded...");
+
+    let mut all_data: Box<[u8]> = vec![0; file_len + 64 * 2 + 10000].into_boxed_slice();
+
+    file.read_exact(&mut all_data[..file_len])
+        .map_err(io_error)?;
+    info!("ReadAndMallocAndTerminateFile: Reading file succeeded...");
+    all_data[file_len..].fill(0);
+
+    drop(file);
+
+    info!("ReadAndMallocAndTerminateFile: Adding a 0 at the end of read data....");
+
+    let Some(pos) = all_data.find(file_end_string) else {
+        return Err(LoadError::Unterminated {
+            file: filename.to_owned(),
+            end_string: String::from_utf8_lossy(file_end_string).into_owned(),
+        });
+    };
+    all_data[pos] = 0;
+
+    info!(
+        "ReadAndMallocAndTerminateFile: The content of the read file: \n{}",
+        String::from_utf8_lossy(all_data.split(|&c| c == b'\0').next().unwrap_or(b""))
+    );
+
+    Ok(all_data)
+}
+
+impl Main<'_> {
+    pub fn cur_level_mut(&mut self) -> &mut Level {
+        cur_level!(mut self)
+    }
+
+    pub fn cur_level(&self) -> &Level {
+        cur_level!(self)
+    }
+
+    fn get_config_dir(&self) -> Option<&Path> {
+        self.config_dir.as_deref()
+    }
+
+    fn get_data_dir(&self) -> Option<&Path> {
+        self.data_dir.as_deref()
+    }
+}
+
+macro_rules! cur_level {
+    (mut $main:expr) => {
+        &mut $main.cur_ship.levels[$main
+            .cur_level_index
+            .expect("no current level index available")]
+    };
+
+    ($main:expr) => {
+        &$main.cur_ship.levels[$main
+            .cur_level_index
+            .expect("no current level index available")]
+    };
+}
+pub(crate) use cur_level;
+
+use crate::ship::{Page, ShowDroidPortraitFlags};
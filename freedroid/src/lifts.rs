@@ -0,0 +1,200 @@
+//! Checks of the lift list of a ship.
+//!
+//! Every lift tile needs an entry in the lift list, and every lift needs a
+//! lift tile, partners which lead back to it and a lift row to be shown in.
+
+use crate::{
+    defs::{LIFT_OUT_OF_ORDER_X, MapTile},
+    structs::Lift,
+};
+
+use std::{fmt, ops::Not};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiftProblem {
+    /// A lift tile without a lift.
+    Unregistered { level: u8, x: usize, y: usize },
+    /// The lift is on a deck which does not exist.
+    NoDeck { lift: usize },
+    /// The lift does not lie on a lift tile.
+    NoLiftTile { lift: usize },
+    /// The lift leads to a lift which does not exist.
+    Dangling {
+        lift: usize,
+        direction: &'static str,
+        target: i32,
+    },
+    /// The lift leads to a lift which does not lead back to it.
+    OneWay {
+        lift: usize,
+        direction: &'static str,
+        target: usize,
+    },
+    /// The lift is in a lift row which does not exist.
+    NoRow { lift: usize, row: i32 },
+}
+
+impl fmt::Display for LiftProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unregistered { level, x, y } => {
+                write!(f, "the lift tile at ({x}, {y}) on deck {level} has no lift")
+            }
+            Self::NoDeck { lift } => write!(f, "lift {lift} is on a deck which does not exist"),
+            Self::NoLiftTile { lift } => write!(f, "lift {lift} is not on a lift tile"),
+            Self::Dangling {
+                lift,
+                direction,
+                target,
+            } => write!(
+                f,
+                "lift {lift} leads {direction} to lift {target}, which does not exist"
+            ),
+            Self::OneWay {
+                lift,
+                direction,
+                target,
+            } => write!(
+                f,
+                "lift {lift} leads {direction} to lift {target}, which does not lead back"
+            ),
+            Self::NoRow { lift, row } => {
+                write!(f, "lift {lift} is in lift row {row}, which does not exist")
+            }
+        }
+    }
+}
+
+/// Checks `lifts` against each other and against the lift tiles of `decks`,
+/// which are given by their number and their rows.  `rows` is the number of
+/// lift rows.
+pub fn check_lift_list<'a>(
+    lifts: &[Lift],
+    decks: impl IntoIterator<Item = (u8, &'a [Vec<MapTile>])>,
+    rows: usize,
+) -> Vec<LiftProblem> {
+    let decks: Vec<_> = decks.into_iter().collect();
+    let mut problems = Vec::new();
+
+    for &(levelnum, map) in &decks {
+        for (y, row) in map.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                let registered = lifts.iter().any(|lift| {
+                    lift.level == levelnum
+                        && usize::try_from(lift.x) == Ok(x)
+                        && usize::try_from(lift.y) == Ok(y)
+                });
+                if tile == MapTile::Lift && registered.not() {
+                    problems.push(LiftProblem::Unregistered {
+                        level: levelnum,
+                        x,
+                        y,
+                    });
+                }
+            }
+        }
+    }
+
+    for (index, lift) in lifts.iter().enumerate() {
+        match decks.iter().find(|&&(levelnum, _)| levelnum == lift.level) {
+            None => problems.push(LiftProblem::NoDeck { lift: index }),
+            Some(_) if lift.x == LIFT_OUT_OF_ORDER_X => {}
+            Some((_, map)) => {
+                let tile = usize::try_from(lift.y)
+                    .ok()
+                    .and_then(|y| map.get(y)?.get(usize::try_from(lift.x).ok()?));
+                if tile != Some(&MapTile::Lift) {
+                    problems.push(LiftProblem::NoLiftTile { lift: index });
+                }
+            }
+        }
+
+        let link = i32::try_from(index).unwrap();
+        for (direction, target) in [("up", lift.up), ("down", lift.down)] {
+            if target == -1 {
+                continue;
+            }
+            let Some(target_lift) = usize::try_from(target).ok().and_then(|i| lifts.get(i)) else {
+                problems.push(LiftProblem::Dangling {
+                    lift: index,
+                    direction,
+                    target,
+                });
+                continue;
+            };
+            let back = if direction == "up" {
+                target_lift.down
+            } else {
+                target_lift.up
+            };
+            if back != link {
+                problems.push(LiftProblem::OneWay {
+                    lift: index,
+                    direction,
+                    target: usize::try_from(target).unwrap(),
+                });
+            }
+        }
+
+        if usize::try_from(lift.row).is_ok_and(|row| row < rows).not() {
+            problems.push(LiftProblem::NoRow {
+                lift: index,
+                row: lift.row,
+            });
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_level;
+
+    fn lift(level: u8, x: i32, up: i32, row: i32) -> Lift {
+        Lift {
+            level,
+            x,
+            y: 0,
+            up,
+            down: -1,
+            row,
+        }
+    }
+
+    #[test]
+    fn broken_lifts() {
+        let mut decks = [0, 1].map(|_| test_level(2, 1, MapTile::Floor));
+        for level in &mut decks {
+            level.map[0][1] = MapTile::Lift;
+        }
+        let decks = decks
+            .iter()
+            .zip(0..)
+            .map(|(level, levelnum)| (levelnum, &level.map[..]));
+        let lifts = [lift(0, 0, 1, 0), lift(1, 1, 7, 1)];
+        assert_eq!(
+            check_lift_list(&lifts, decks, 1),
+            [
+                LiftProblem::Unregistered {
+                    level: 0,
+                    x: 1,
+                    y: 0
+                },
+                LiftProblem::NoLiftTile { lift: 0 },
+                LiftProblem::OneWay {
+                    lift: 0,
+                    direction: "up",
+                    target: 1
+                },
+                LiftProblem::Dangling {
+                    lift: 1,
+                    direction: "up",
+                    target: 7
+                },
+                LiftProblem::NoRow { lift: 1, row: 1 },
+            ]
+        );
+    }
+}
@@ -1,30 +1,28 @@
 #[cfg(not(target_os = "android"))]
-use crate::menu::SHIP_EXT;
+use crate::menu::{CREW_EXT, LIFT_EXT, SHIP_EXT};
 use crate::{
     defs::{
-        self, DIRECTIONS, Direction, Droid, MAP_DIR_C, MAX_ALERTS_ON_LEVEL, MAX_LEVELS,
-        MAX_REFRESHES_ON_LEVEL, MAXWAYPOINTS, MapTile, Status, Themed,
+        self, DIRECTIONS, Direction, Droid, MAP_DIR_C, MAX_ALERTS_ON_LEVEL, MAX_ENEMYS_ON_SHIP,
+        MAX_LEVEL_RECTS, MAX_LEVELS, MAX_LIFT_ROWS, MAX_LIFTS, MAX_REFRESHES_ON_LEVEL,
+        MAXWAYPOINTS, MapTile, Status, Themed,
     },
+    error::{DataProblem, LoadError, expect_entry},
     find_subslice, map,
     misc::{
-        locate_string_in_data, read_and_malloc_string_from_data, read_i32_from_string,
-        read_u8_from_string,
+        try_read_and_malloc_string_from_data, try_read_i32_from_string, try_read_u8_from_string,
     },
-    read_and_malloc_and_terminate_file, split_at_subslice, split_at_subslice_mut,
-    structs::{CoarsePoint, Enemy, Finepoint, Level, Lift, Waypoint},
+    read_and_malloc_and_terminate_file,
+    structs::{CoarsePoint, Crew, Enemy, Finepoint, Level, Lift, Ship, Waypoint},
 };
 
-use arrayvec::ArrayVec;
+use arrayvec::{ArrayVec, CapacityError};
 use bstr::ByteSlice;
-use defs::MAX_DOORS_ON_LEVEL;
-#[cfg(not(target_os = "android"))]
-use defs::MAX_WP_CONNECTIONS;
-#[cfg(not(target_os = "android"))]
-use log::trace;
-use log::{error, info, warn};
+use defs::{MAX_DOORS_ON_LEVEL, MAX_WP_CONNECTIONS};
+use log::{info, trace, warn};
 use nom::{Finish, IResult, Parser};
-use rand::{Rng, seq::SliceRandom, thread_rng};
+use rand::{Rng, seq::SliceRandom};
 use sdl::Rect;
+use serde::{Deserialize, Serialize};
 #[cfg(not(target_os = "android"))]
 use std::ffi::CStr;
 use std::{
@@ -32,8 +30,9 @@ use std::{
     convert::identity,
     ffi::CString,
     fmt::{self, Display},
-    ops::Not,
-    path::Path,
+    io::{self, Write},
+    ops::{Not, Range},
+    path::{Path, PathBuf},
 };
 
 const WALLPASS: f32 = 4_f32 / 64.;
@@ -48,14 +47,25 @@ const V_RANDBREITE: f32 = 5_f32 / 64.;
 const H_RANDSPACE: f32 = WALLPASS;
 const H_RANDBREITE: f32 = 5_f32 / 64.;
 
-const AREA_NAME_STRING: &[u8] = b"Area name=\"";
-const LEVEL_NAME_STRING: &str = "Name of this level=";
-const LEVEL_ENTER_COMMENT_STRING: &str = "Comment of the Influencer on entering this level=\"";
-const BACKGROUND_SONG_NAME_STRING: &str = "Name of background song for this level=";
-const MAP_BEGIN_STRING: &str = "begin_map";
-const WP_BEGIN_STRING: &str = "begin_waypoints";
-const LEVEL_END_STRING: &str = "end_level";
-const CONNECTION_STRING: &str = "connections: ";
+pub const AREA_NAME_STRING: &[u8] = b"Area name=\"";
+pub const LEVEL_NAME_STRING: &str = "Name of this level=";
+pub const LEVEL_ENTER_COMMENT_STRING: &str = "Comment of the Influencer on entering this level=\"";
+pub const BACKGROUND_SONG_NAME_STRING: &str = "Name of background song for this level=";
+pub const MAP_BEGIN_STRING: &str = "begin_map";
+pub const WP_BEGIN_STRING: &str = "begin_waypoints";
+pub const LEVEL_END_STRING: &str = "end_level";
+pub const CONNECTION_STRING: &str = "connections: ";
+pub const END_OF_SHIP_DATA_STRING: &str = "*** End of Ship Data ***";
+pub const START_OF_LIFT_RECTANGLE_DATA_STRING: &str = "*** Beginning of elevator rectangles ***";
+pub const START_OF_LIFT_DATA_STRING: &str = "*** Beginning of Lift Data ***";
+pub const END_OF_LIFT_DATA_STRING: &str = "*** End of elevator specification file ***";
+pub const END_OF_DROID_DATA_STRING: &[u8] = b"*** End of Droid Data ***";
+pub const DROIDS_LEVEL_DESCRIPTION_START_STRING: &[u8] = b"** Beginning of new Level **";
+pub const DROIDS_LEVEL_DESCRIPTION_END_STRING: &[u8] = b"** End of this levels droid data **";
+pub const DROIDS_LEVEL_INDICATION_STRING: &[u8] = b"Level=";
+pub const DROIDS_MAXRAND_INDICATION_STRING: &[u8] = b"Maximum number of Random Droids=";
+pub const DROIDS_MINRAND_INDICATION_STRING: &[u8] = b"Minimum number of Random Droids=";
+pub const ALLOWED_TYPE_INDICATION_STRING: &[u8] = b"Allowed Type of Random Droid for this level: ";
 
 #[derive(Debug, Default)]
 pub struct Map {
@@ -74,6 +84,78 @@ pub fn get_map_brick(deck: &Level, x: f32, y: f32) -> u8 {
         })
 }
 
+/// How droids pass a tile.  The collision checks and the waypoint graph both
+/// follow it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Passage {
+    /// Droids walk over the whole tile.
+    Open,
+    /// The space around the deck, also what lies beyond its borders.  Droids
+    /// can cross it, but it is no part of the deck.
+    Void,
+    /// A door, which opens in front of the droids.
+    Door,
+    /// A console, droids only pass along its front.
+    Console,
+    /// A wall piece, droids only pass along its edges.
+    Wall,
+    /// An alert tile, only light passes.
+    Alert,
+    /// Nothing passes.
+    Blocked,
+}
+
+/// How droids pass `tile`.
+pub fn tile_passage(tile: MapTile) -> Passage {
+    use MapTile as M;
+
+    match tile {
+        M::Floor
+        | M::Lift
+        | M::Block4
+        | M::Block5
+        | M::Refresh1
+        | M::Refresh2
+        | M::Refresh3
+        | M::Refresh4
+        | M::FineGrid => Passage::Open,
+        M::Void => Passage::Void,
+        M::HZutuere
+        | M::HHalbtuere1
+        | M::HHalbtuere2
+        | M::HHalbtuere3
+        | M::HGanztuere
+        | M::VZutuere
+        | M::VHalbtuere1
+        | M::VHalbtuere2
+        | M::VHalbtuere3
+        | M::VGanztuere => Passage::Door,
+        M::KonsoleL | M::KonsoleR | M::KonsoleO | M::KonsoleU => Passage::Console,
+        M::EckLu
+        | M::Tu
+        | M::EckRu
+        | M::Tl
+        | M::Kreuz
+        | M::Tr
+        | M::EckLo
+        | M::To
+        | M::EckRo
+        | M::HWall
+        | M::VWall => Passage::Wall,
+        M::AlertGreen | M::AlertYellow | M::AlertAmber | M::AlertRed => Passage::Alert,
+        M::Invisible | M::Block1 | M::Block2 | M::Block3 | M::Unused2 | M::NumMapTiles => {
+            Passage::Blocked
+        }
+    }
+}
+
+/// Whether droids can stand in the middle of `tile`, so a waypoint may lie
+/// on it.  The doors count, as they open in front of the droids.  The void
+/// does not, droids have no business outside of the deck.
+pub fn is_walkable(tile: MapTile) -> bool {
+    matches!(tile_passage(tile), Passage::Open | Passage::Door)
+}
+
 pub fn free_level_memory(level: &mut Level) {
     level.levelname = CString::default();
     level.background_song_name = CString::default();
@@ -86,7 +168,7 @@ pub fn free_level_memory(level: &mut Level) {
         .for_each(Vec::clear);
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Color {
     #[default]
     Red,
@@ -184,7 +266,6 @@ impl Display for InvalidColor {
 
 impl std::error::Error for InvalidColor {}
 
-#[cfg(not(target_os = "android"))]
 fn reset_level_map(level: &mut Level) {
     // Now in the game and in the level editor, it might have happend that some open
     // doors occur.  The make life easier for the saving routine, these doors should
@@ -361,136 +442,202 @@ where
     map(opt(is_a(" \t")), Option::unwrap_or_default)(input)
 }
 
-/// This function is for LOADING map data!
-/// This function extracts the data from *data and writes them
-/// into a Level-struct:
-///
-/// Doors and Waypoints Arrays are initialized too
-pub fn level_to_struct(data: &[u8]) -> Option<Level> {
-    use nom::{character::complete::u8, sequence::tuple};
-
-    /* Get the memory for one level */
-    let mut loadlevel = Level {
+/// A level of `xlen` x `ylen` tiles, all of them `tile`.
+pub fn new_level(levelnum: u8, xlen: u8, ylen: u8, tile: MapTile) -> Level {
+    Level {
         empty: false,
         timer: 0.,
-        levelnum: 0,
+        levelnum,
         levelname: CString::default(),
         background_song_name: CString::default(),
         enter_comment: CString::default(),
-        xlen: 0,
-        ylen: 0,
+        xlen,
+        ylen,
         color: Color::default(),
-        map: array::from_fn(|_| Vec::default()),
+        map: array::from_fn(|row| {
+            if row < usize::from(ylen) {
+                vec![tile; usize::from(xlen)]
+            } else {
+                Vec::new()
+            }
+        }),
         refreshes: [None; MAX_REFRESHES_ON_LEVEL],
         doors: [None; MAX_DOORS_ON_LEVEL],
         alerts: [None; MAX_ALERTS_ON_LEVEL],
-        waypoints: ArrayVec::default(),
-    };
+        waypoints: ArrayVec::new(),
+    }
+}
+
+/// This function is for LOADING map data!
+/// This function extracts the data of the level in `data[range]` and writes
+/// them into a Level-struct, the problems found are added to `problems`.
+///
+/// Doors and Waypoints Arrays are initialized too.  The positions of the
+/// waypoints in `data` are returned with the level.
+#[allow(clippy::too_many_lines)]
+fn read_level(
+    data: &[u8],
+    range: Range<usize>,
+    expected_levelnum: usize,
+    problems: &mut Vec<DataProblem>,
+) -> (Level, Vec<usize>) {
+    /* Get the memory for one level */
+    let mut loadlevel = new_level(0, 0, 0, MapTile::Void);
+    let mut waypoint_offsets = Vec::new();
+    let start = range.start;
+    let level_data = &data[range.clone()];
 
     info!("Starting to process information for another level:");
 
     /* Read Header Data: levelnum and x/ylen */
-    let data_pointer = find_subslice(data, b"Levelnumber:")
-        .map(|pos| &data[pos..])
-        .expect("No Levelnumber entry found! Terminating! ");
-
-    (
-        loadlevel.levelnum,
-        loadlevel.xlen,
-        loadlevel.ylen,
-        loadlevel.color,
-    ) = parse_levelnum_xlen_ylen_color(data_pointer);
+    let header = find_subslice(level_data, b"Levelnumber:")
+        .and_then(|pos| parse_levelnum_xlen_ylen_color(&level_data[pos..]));
+    let Some((levelnum, xlen, ylen, color)) = expect_entry(header, "Levelnumber:", start, problems)
+    else {
+        return (loadlevel, waypoint_offsets);
+    };
+    if usize::from(levelnum) != expected_levelnum {
+        problems.push(DataProblem::at(
+            start,
+            format!("level number {levelnum} found, {expected_levelnum} was expected"),
+        ));
+    }
+    match Color::try_from(color) {
+        Ok(color) => loadlevel.color = color,
+        Err(_) => problems.push(DataProblem::at(
+            start,
+            format!("unknown level color {color}"),
+        )),
+    }
+    loadlevel.levelnum = levelnum;
+    loadlevel.xlen = xlen;
+    loadlevel.ylen = ylen;
 
     info!("Levelnumber : {} ", loadlevel.levelnum);
     info!("xlen of this level: {} ", loadlevel.xlen);
     info!("ylen of this level: {} ", loadlevel.ylen);
-    info!("color of this level: {} ", loadlevel.ylen);
-
-    loadlevel.levelname =
-        read_and_malloc_string_from_data(data, LEVEL_NAME_STRING.as_bytes(), b"\n");
-    loadlevel.background_song_name =
-        read_and_malloc_string_from_data(data, BACKGROUND_SONG_NAME_STRING.as_bytes(), b"\n");
-    loadlevel.enter_comment =
-        read_and_malloc_string_from_data(data, LEVEL_ENTER_COMMENT_STRING.as_bytes(), b"\n");
-
-    // find the map data
-    let map_begin = data.find(MAP_BEGIN_STRING.as_bytes())?;
-
-    /* set position to Waypoint-Data */
-    let wp_begin = data.find(WP_BEGIN_STRING.as_bytes())?;
+    info!("color of this level: {} ", color);
 
-    // find end of level-data
-    let level_end = data.find(LEVEL_END_STRING.as_bytes())?;
+    let mut read_string = |label: &str| {
+        let string = try_read_and_malloc_string_from_data(level_data, label.as_bytes(), b"\n");
+        expect_entry(string, label, start, problems).unwrap_or_default()
+    };
+    loadlevel.levelname = read_string(LEVEL_NAME_STRING);
+    loadlevel.background_song_name = read_string(BACKGROUND_SONG_NAME_STRING);
+    loadlevel.enter_comment = read_string(LEVEL_ENTER_COMMENT_STRING);
+
+    // find the map data and the waypoints following it
+    let map_begin = level_data.find(MAP_BEGIN_STRING);
+    let map_begin = expect_entry(map_begin, MAP_BEGIN_STRING, start, problems);
+    let wp_begin = level_data.find(WP_BEGIN_STRING);
+    let wp_begin = expect_entry(wp_begin, WP_BEGIN_STRING, start, problems);
+    let (Some(map_begin), Some(wp_begin)) = (map_begin, wp_begin) else {
+        return (loadlevel, waypoint_offsets);
+    };
+    let (map_begin, wp_begin) = (start + map_begin, start + wp_begin);
+    if wp_begin < map_begin {
+        problems.push(DataProblem::at(
+            wp_begin,
+            format!("`{WP_BEGIN_STRING}` must follow the map, not precede it"),
+        ));
+        return (loadlevel, waypoint_offsets);
+    }
 
     /* now scan the map */
-    let mut lines = data[map_begin..].lines().skip(1);
-
-    /* read MapData */
-    for i in 0..usize::from(loadlevel.ylen) {
-        let this_line = lines.next()?;
-        loadlevel.map[i].resize(usize::from(loadlevel.xlen), MapTile::Void);
-        let mut pos = this_line.trim_start();
+    let mut rows = lines_with_offsets(data, map_begin..wp_begin).skip(1);
+    for y in 0..usize::from(ylen) {
+        let Some((offset, line)) = rows.next() else {
+            problems.push(DataProblem::at(
+                map_begin,
+                format!("the map has {y} rows, {ylen} are needed"),
+            ));
+            break;
+        };
 
-        for k in 0..usize::from(loadlevel.xlen) {
-            if pos.is_empty() {
-                return None;
-            }
-            let raw_number;
-            (raw_number, pos) = pos
-                .iter()
-                .position(|&c| c.is_ascii_digit().not())
-                .map_or((pos, b"".as_slice()), |end_of_digits| {
-                    pos.split_at(end_of_digits)
-                });
-            let tmp = std::str::from_utf8(raw_number)
+        let row = &mut loadlevel.map[y];
+        row.resize(usize::from(xlen), MapTile::Void);
+        let mut words = line.fields();
+        for (x, tile) in row.iter_mut().enumerate() {
+            let Some(word) = words.next() else {
+                problems.push(DataProblem::at(
+                    offset,
+                    format!("map row {y} has {x} tiles, {xlen} are needed"),
+                ));
+                break;
+            };
+            match std::str::from_utf8(word)
                 .ok()
-                .and_then(|s| s.trim_start().parse::<u8>().ok())?;
-            loadlevel.map[i][k] = tmp.try_into().unwrap();
-            pos = pos.trim_start();
+                .and_then(|word| word.parse::<u8>().ok())
+                .and_then(|word| MapTile::try_from(word).ok())
+            {
+                Some(read_tile) => *tile = read_tile,
+                None => problems.push(DataProblem::at(
+                    offset,
+                    format!("invalid map tile `{}` at ({x}, {y})", word.to_str_lossy()),
+                )),
+            }
         }
     }
 
     /* Get Waypoints */
-    let mut lines = data[wp_begin..level_end].lines().skip(1);
-
-    for _ in 0..MAXWAYPOINTS {
-        let Some(this_line) = lines.next() else {
-            break;
+    let lines = lines_with_offsets(data, wp_begin..range.end).skip(1);
+    for (index, (offset, line)) in lines.take(usize::from(MAXWAYPOINTS)).enumerate() {
+        let Some([x, y]) = parse_waypoint_x_y(line) else {
+            problems.push(DataProblem::at(
+                offset,
+                format!("waypoint {index} has no valid position"),
+            ));
+            continue;
+        };
+        let (Ok(x), Ok(y)) = (u8::try_from(x), u8::try_from(y)) else {
+            problems.push(DataProblem::at(
+                offset,
+                format!("waypoint {index} lies outside of the level"),
+            ));
+            continue;
         };
-
-        let [x, y] = parse_waypoint_x_y(this_line);
         let mut waypoint = Waypoint {
-            x: x.try_into().unwrap(),
-            y: y.try_into().unwrap(),
+            x,
+            y,
             connections: ArrayVec::new(),
         };
 
-        let mut pos = this_line
-            [this_line.find(CONNECTION_STRING).unwrap() + CONNECTION_STRING.len()..]
-            .trim_start();
-
-        loop {
-            if pos.is_empty() {
-                break;
-            }
-
-            match tuple((whitespace, u8))(pos).finish() {
-                Ok((rest, (_, connection))) => {
+        let connections = line.find(CONNECTION_STRING);
+        let Some(pos) = expect_entry(connections, CONNECTION_STRING, offset, problems) else {
+            continue;
+        };
+        for connection in line[pos + CONNECTION_STRING.len()..].fields() {
+            match connection
+                .to_str()
+                .ok()
+                .and_then(|connection| connection.parse::<u8>().ok())
+            {
+                Some(connection) => {
                     if waypoint.connections.try_push(connection).is_err() {
                         break;
                     }
-                    pos = rest.trim_start();
                 }
-                Err(()) => break,
+                None => problems.push(DataProblem::at(
+                    offset,
+                    format!(
+                        "invalid connection `{}` of waypoint {index}",
+                        connection.to_str_lossy()
+                    ),
+                )),
             }
         }
         loadlevel.waypoints.push(waypoint);
+        waypoint_offsets.push(offset);
     }
 
-    Some(loadlevel)
+    if let Err(reason) = init_level(&mut loadlevel) {
+        problems.push(DataProblem::at(start, reason));
+    }
+
+    (loadlevel, waypoint_offsets)
 }
 
-fn parse_levelnum_xlen_ylen_color(data: &[u8]) -> (u8, u8, u8, map::Color) {
+fn parse_levelnum_xlen_ylen_color(data: &[u8]) -> Option<(u8, u8, u8, u8)> {
     use nom::{bytes::complete::tag, character::complete::u8, sequence::tuple};
 
     let (_, (_, _, levelnum, _, _, x_len, _, _, y_len, _, _, color)) = tuple::<_, _, (), _>((
@@ -508,12 +655,12 @@ fn parse_levelnum_xlen_ylen_color(data: &[u8]) -> (u8, u8, u8, map::Color) {
         u8,
     ))(data)
     .finish()
-    .unwrap();
+    .ok()?;
 
-    (levelnum, x_len, y_len, color.try_into().unwrap())
+    Some((levelnum, x_len, y_len, color))
 }
 
-fn parse_waypoint_x_y(data: &[u8]) -> [i32; 2] {
+fn parse_waypoint_x_y(data: &[u8]) -> Option<[i32; 2]> {
     use nom::{bytes::complete::tag, character::complete::i32, sequence::tuple};
 
     let (_, (_, _, _, _, _, _, x, _, _, _, y)) = tuple::<_, _, (), _>((
@@ -530,9 +677,96 @@ fn parse_waypoint_x_y(data: &[u8]) -> [i32; 2] {
         i32,
     ))(data)
     .finish()
-    .unwrap();
+    .ok()?;
+
+    Some([x, y])
+}
+
+/// The lines of `data[range]` without their line ends, together with their
+/// positions in `data`.
+fn lines_with_offsets(data: &[u8], range: Range<usize>) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = range.start;
+    data[range].lines_with_terminator().map(move |line| {
+        let start = offset;
+        offset += line.len();
+        (start, line.trim_end_with(|c| c == '\n' || c == '\r'))
+    })
+}
+
+/// Reads all the levels contained in the given ship data, the problems found
+/// are added to `problems`.
+///
+/// The levels are counted by searching for the `LEVEL_END_STRING` again and
+/// again until it is no longer found in the data. Doors, refreshes and alerts
+/// of every level are initialized as well.  The positions of the waypoints of
+/// every level in the data are returned with the levels.
+pub fn read_levels(
+    ship_data: &[u8],
+    problems: &mut Vec<DataProblem>,
+) -> (ArrayVec<Level, MAX_LEVELS>, Vec<Vec<usize>>) {
+    let mut levels = ArrayVec::new();
+    let mut waypoint_offsets = Vec::new();
+    let mut start = 0;
+    for level_end in ship_data.find_iter(LEVEL_END_STRING) {
+        let (level, offsets) = read_level(ship_data, start..level_end, levels.len(), problems);
+        if levels.try_push(level).is_err() {
+            problems.push(DataProblem::at(
+                start,
+                format!("too many levels in ship data, at most {MAX_LEVELS} are allowed"),
+            ));
+            break;
+        }
+        waypoint_offsets.push(offsets);
+
+        start = level_end + LEVEL_END_STRING.len();
+    }
 
-    [x, y]
+    (levels, waypoint_offsets)
+}
+
+/// Reads all the levels contained in the given ship data, see
+/// [`read_levels`].
+///
+/// On failure the reason of the first problem is returned.
+pub fn levels_from_data(ship_data: &[u8]) -> Result<ArrayVec<Level, MAX_LEVELS>, String> {
+    let mut problems = Vec::new();
+    let (levels, _) = read_levels(ship_data, &mut problems);
+    match problems.into_iter().next() {
+        Some(problem) => Err(problem.reason),
+        None => Ok(levels),
+    }
+}
+
+/// Initializes doors, refreshes and alerts of a level whose map has just been
+/// read.
+///
+/// On failure the reason is returned.
+pub fn init_level(level: &mut Level) -> Result<(), String> {
+    let doors = count_tiles(level, &[MapTile::VZutuere, MapTile::HZutuere]);
+    if doors > MAX_DOORS_ON_LEVEL {
+        return Err(format!(
+            "level {} has {} doors, at most {} are allowed",
+            level.levelnum, doors, MAX_DOORS_ON_LEVEL
+        ));
+    }
+    let refreshes = count_tiles(level, &[MapTile::Refresh1]);
+    if refreshes > MAX_REFRESHES_ON_LEVEL {
+        return Err(format!(
+            "level {} has {} refreshes, at most {} are allowed",
+            level.levelnum, refreshes, MAX_REFRESHES_ON_LEVEL
+        ));
+    }
+
+    map::interpret(level);
+    Ok(())
+}
+
+fn count_tiles(level: &Level, tiles: &[MapTile]) -> usize {
+    level.map[..usize::from(level.ylen)]
+        .iter()
+        .flatten()
+        .filter(|&tile| tiles.contains(tile))
+        .count()
 }
 
 impl crate::Data<'_> {
@@ -615,24 +849,14 @@ impl crate::Data<'_> {
 
         let map_tile = MapTile::try_from(map_brick).ok()?;
 
-        match map_tile {
-            M::Floor
-            | M::Lift
-            | M::Void
-            | M::Block4
-            | M::Block5
-            | M::Refresh1
-            | M::Refresh2
-            | M::Refresh3
-            | M::Refresh4
-            | M::FineGrid => {
-                Some(D::Center) /* these are passable */
-            }
-
-            M::AlertGreen | M::AlertYellow | M::AlertAmber | M::AlertRed => {
-                (check_pos.try_into() == Ok(D::Light)).then_some(D::Center)
-            }
+        match tile_passage(map_tile) {
+            Passage::Open | Passage::Void => return Some(D::Center),
+            Passage::Alert => return (check_pos.try_into() == Ok(D::Light)).then_some(D::Center),
+            Passage::Blocked => return None,
+            Passage::Door | Passage::Console | Passage::Wall => {}
+        }
 
+        match map_tile {
             M::KonsoleL => (check_pos.try_into() == Ok(D::Light) || fx > 1.0 - KONSOLEPASS_X)
                 .then_some(D::Center),
 
@@ -770,107 +994,72 @@ impl crate::Data<'_> {
         }
     }
 
-    /// Saves ship-data to disk
+    /// Saves ship-data, the crews of its levels and its lifts into `dir`.
+    /// The files are only replaced once all of them have been written
+    /// completely, so that a failed save does not leave a ship behind whose
+    /// files do not fit together.
     #[cfg(not(target_os = "android"))]
-    pub fn save_ship(&mut self, shipname: &str) -> i32 {
-        use std::{fs::File, io::Write, path::PathBuf};
-
+    pub fn save_ship(&mut self, dir: &Path, shipname: &str) -> io::Result<()> {
         trace!("SaveShip(): real function call confirmed.");
 
-        let filename = PathBuf::from(format!("{shipname}{SHIP_EXT}"));
-
-        /* count the levels */
-        let level_anz: u8 = self.main.cur_ship.levels.iter().count().try_into().unwrap();
-
-        trace!("SaveShip(): now opening the ship file...");
-
-        let mut ship_file = match File::create(filename) {
-            Ok(file) => file,
-            Err(err) => {
-                panic!("Error opening ship file: {err}. Terminating");
-            }
-        };
-
-        let result = (|| -> Result<(), std::io::Error> {
-            //--------------------
-            // Now that the file is opend for writing, we can start writing.  And the first thing
-            // we will write to the file will be a fine header, indicating what this file is about
-            // and things like that...
-            //
-            const MAP_HEADER_STRING: &str = "\n\
-----------------------------------------------------------------------\n\
-This file was generated using the Freedroid level editor.\n\
-Please feel free to make any modifications you like, but in order for you\n\
-to have an easier time, it is recommended that you use the Freedroid level\n\
-editor for this purpose.  If you have created some good new maps, please \n\
-send a short notice (not too large files attached) to the freedroid project.\n\
-\n\
-freedroid-discussion@lists.sourceforge.net\n\
-----------------------------------------------------------------------\n\
-\n";
-            const AREA_NAME_STRING: &str = "Area name=\"";
-            const END_OF_SHIP_DATA_STRING: &str = "*** End of Ship Data ***";
-
-            ship_file.write_all(MAP_HEADER_STRING.as_bytes())?;
-            ship_file.write_all(AREA_NAME_STRING.as_bytes())?;
-            ship_file.write_all(self.main.cur_ship.area_name.to_bytes())?;
-            ship_file.write_all(b"\"\n\n  ")?;
-
-            /* Save all Levels */
-
-            trace!("SaveShip(): now saving levels...");
-
-            for i in 0..level_anz {
-                let mut level_iter = self
-                    .main
-                    .cur_ship
-                    .levels
-                    .iter_mut()
-                    .filter(|level| level.levelnum == i);
-
-                let level = level_iter
-                    .next()
-                    .expect("Missing Levelnumber error in SaveShip.");
-
-                assert!(
-                    level_iter.next().is_none(),
-                    "Identical Levelnumber Error in SaveShip."
-                );
+        let mut temp_files = Vec::with_capacity(3);
+        let result = self
+            .write_ship_temp_files(dir, shipname, &mut temp_files)
+            .and_then(|()| replace_with_temp_files(&temp_files));
+        if result.is_err() {
+            remove_temp_files(&temp_files);
+        }
 
-                //--------------------
-                // Now comes the real saving part FOR ONE LEVEL.  First THE LEVEL is packed into a string and
-                // then this string is wirtten to the file.  easy. simple.
-                let level_mem = struct_to_mem(level);
-                let end = level_mem
-                    .iter()
-                    .copied()
-                    .position(|c| c == b'\0')
-                    .unwrap_or(level_mem.len());
-                ship_file.write_all(&level_mem[..end])?;
-            }
+        trace!("SaveShip(): end of function reached.");
+        result
+    }
 
-            //--------------------
-            // Now we are almost done writing.  Everything that is missing is
-            // the termination string for the ship file.  This termination string
-            // is needed later for the ship loading functions to find the end of
-            // the data and to be able to terminate the long file-string with a
-            // null character at the right position.
-            //
-            writeln!(ship_file, "{END_OF_SHIP_DATA_STRING}\n")?;
-
-            trace!("SaveShip(): now flushing ship file...");
-            ship_file.flush()?;
-
-            trace!("SaveShip(): end of function reached.");
-            Ok(())
-        })();
-
-        match result {
-            Ok(()) => defs::OK.into(),
-            Err(err) => {
-                panic!("Error writing to ship file: {err}. Terminating");
-            }
+    /// Writes the files of the ship into temporary files next to them, adding
+    /// them to `temp_files` together with the file they are going to replace.
+    #[cfg(not(target_os = "android"))]
+    fn write_ship_temp_files(
+        &mut self,
+        dir: &Path,
+        shipname: &str,
+        temp_files: &mut Vec<(PathBuf, PathBuf)>,
+    ) -> io::Result<()> {
+        let path = dir.join(format!("{shipname}{SHIP_EXT}"));
+        let tmp_path = write_temp_file(&path, |writer| {
+            write_ship_file(&mut self.main.cur_ship, writer)
+        })?;
+        temp_files.push((tmp_path, path));
+
+        trace!("SaveShip(): now saving the crew file...");
+
+        let path = dir.join(format!("{shipname}{CREW_EXT}"));
+        let droid_names: Vec<_> = self
+            .vars
+            .droidmap
+            .iter()
+            .map(|droid| droid.druidname.to_bytes())
+            .collect();
+        let tmp_path = write_temp_file(&path, |writer| {
+            write_crew_file(&self.main.cur_ship, &droid_names, writer)
+        })?;
+        temp_files.push((tmp_path, path));
+
+        trace!("SaveShip(): now saving the lift file...");
+
+        // the rectangles have been scaled to the screen on loading
+        let mut ship = self.main.cur_ship.clone();
+        let scale = self.global.game_config.scale;
+        #[allow(clippy::float_cmp)]
+        if scale != 1.0 {
+            ship.level_rects
+                .iter_mut()
+                .flatten()
+                .chain(&mut ship.lift_row_rects)
+                .for_each(|rect| rect.scale(1. / scale));
         }
+        let path = dir.join(format!("{shipname}{LIFT_EXT}"));
+        let tmp_path = write_temp_file(&path, |writer| write_lift_file(&ship, writer))?;
+        temp_files.push((tmp_path, path));
+        Ok(())
     }
 
     /// This funtion moves the level doors in the sense that they are opened
@@ -995,355 +1184,73 @@ freedroid-discussion@lists.sourceforge.net\n\
             .unwrap_or(Some(Direction::Center))
     }
 
-    /// This function receives a pointer to the already read in crew section in a already read in
-    /// droids file and decodes all the contents of that droid section to fill the `AllEnemys`
-    /// array with droid types according to the specifications made in the file.
-    pub fn get_this_levels_droids(&mut self, section_data: &[u8]) {
-        const DROIDS_LEVEL_INDICATION_STRING: &[u8] = b"Level=";
-        const DROIDS_LEVEL_END_INDICATION_STRING: &[u8] = b"** End of this levels droid data **";
-        const DROIDS_MAXRAND_INDICATION_STRING: &[u8] = b"Maximum number of Random Droids=";
-        const DROIDS_MINRAND_INDICATION_STRING: &[u8] = b"Minimum number of Random Droids=";
-        const ALLOWED_TYPE_INDICATION_STRING: &[u8] =
-            b"Allowed Type of Random Droid for this level: ";
-
-        let section_data = &section_data
-            [..locate_string_in_data(section_data, DROIDS_LEVEL_END_INDICATION_STRING)];
-
-        // Now we read in the level number for this level
-        let our_level_number = read_u8_from_string(section_data, DROIDS_LEVEL_INDICATION_STRING);
-
-        // Now we read in the maximal number of random droids for this level
-        let max_rand = read_i32_from_string(section_data, DROIDS_MAXRAND_INDICATION_STRING);
-
-        // Now we read in the minimal number of random droids for this level
-        let min_rand = read_i32_from_string(section_data, DROIDS_MINRAND_INDICATION_STRING);
-
-        let mut different_random_types = 0;
-        let mut search_pos_opt = section_data.find(ALLOWED_TYPE_INDICATION_STRING);
-        let mut list_of_types_allowed: [Droid; 1000] = [Droid::Droid001; 1000];
-        while let Some(mut search_pos) = search_pos_opt {
-            search_pos += ALLOWED_TYPE_INDICATION_STRING.len();
-            let remaining_data = &section_data[search_pos..];
-            let type_indication_string = &remaining_data[..3];
-            // Now that we have got a type indication string, we only need to translate it
-            // into a number corresponding to that droid in the droid list
-            let mut list_index = 0;
-            while list_index < self.main.number_of_droid_types {
-                if self.vars.droidmap[usize::from(list_index)].druidname
-                    == std::str::from_utf8(type_indication_string).unwrap()
-                {
-                    break;
-                }
-                list_index += 1;
-            }
-            if list_index >= self.main.number_of_droid_types {
-                panic!(
-                    "unknown droid type: {} found in data file for level {}",
-                    String::from_utf8_lossy(type_indication_string),
-                    our_level_number,
-                );
-            } else {
-                info!(
-                    "Type indication string {} translated to type Nr.{}.",
-                    String::from_utf8_lossy(type_indication_string),
-                    list_index,
-                );
-            }
-            list_of_types_allowed[different_random_types] = list_index.try_into().unwrap();
-            different_random_types += 1;
-
-            search_pos_opt = remaining_data
-                .find(ALLOWED_TYPE_INDICATION_STRING)
-                .map(|pos| pos + search_pos);
-        }
-        info!(
-            "Found {} different allowed random types for this level. ",
-            different_random_types,
+    /// Puts the random droids of the crew of level `levelnum` on board, with
+    /// their batteries full.
+    pub fn spawn_crew(&mut self, levelnum: u8) -> Result<(), CapacityError<Enemy>> {
+        let droids = roll_crew(
+            &self.main.cur_ship.crews[usize::from(levelnum)],
+            &mut self.rng,
         );
+        let droidmap = &self.vars.droidmap;
+        put_on_board(&mut self.main.enemys, droids, levelnum, |droid| {
+            droidmap[droid.to_usize()].maxenergy
+        })
+    }
 
-        //--------------------
-        // At this point, the List "ListOfTypesAllowed" has been filled with the NUMBERS of
-        // the allowed types.  The number of different allowed types found is also available.
-        // That means that now we can add the apropriate droid types into the list of existing
-        // droids in that mission.
-
-        let mut rng = thread_rng();
-        let mut real_number_of_random_droids = rng.gen_range(min_rand..=max_rand);
-
-        while real_number_of_random_droids > 0 {
-            real_number_of_random_droids -= 1;
-
-            let enemy_slot = self
-                .main
-                .enemys
-                .iter_mut()
-                .find(|enemy| enemy.status == Status::Out);
-            let random_droid_type = list_of_types_allowed[0..different_random_types]
-                .choose(&mut rng)
-                .copied()
-                .unwrap();
+    /// Reads the crews of all levels from a crew file, without putting any
+    /// droids on board.
+    pub fn read_crew(&mut self, filename: &[u8]) -> Result<(), LoadError> {
+        let fpath = self.find_data_file(filename, Some(MAP_DIR_C), Themed::NoTheme)?;
+        self.read_crew_file(&fpath)
+    }
 
-            let new_enemy = Enemy::new(random_droid_type, our_level_number);
-            if let Some(enemy_slot) = enemy_slot {
-                *enemy_slot = new_enemy;
-            } else {
-                self.main
-                    .enemys
-                    .try_push(new_enemy)
-                    .expect("No more free position to fill random droids into in GetCrew");
-            }
-        }
+    fn read_crew_file(&mut self, fpath: &Path) -> Result<(), LoadError> {
+        let data = read_and_malloc_and_terminate_file(fpath, END_OF_DROID_DATA_STRING)?;
+        let droid_names: Vec<_> = self
+            .vars
+            .droidmap
+            .iter()
+            .map(|droid| droid.druidname.to_bytes())
+            .collect();
+        self.main.cur_ship.crews = crews_from_data(&data, fpath, &droid_names)?;
+        Ok(())
     }
 
     /// This function initializes all enemys
-    pub fn get_crew(&mut self, filename: &[u8]) -> i32 {
-        const END_OF_DROID_DATA_STRING: &[u8] = b"*** End of Droid Data ***";
-        const DROIDS_LEVEL_DESCRIPTION_START_STRING: &[u8] = b"** Beginning of new Level **";
-        const DROIDS_LEVEL_DESCRIPTION_END_STRING: &[u8] = b"** End of this levels droid data **";
-
+    pub fn get_crew(&mut self, filename: &[u8]) -> Result<(), LoadError> {
         self.main.enemys.clear();
 
-        //Now its time to start decoding the droids file.
-        //For that, we must get it into memory first.
-        //The procedure is the same as with LoadShip
-        let fpath = self.find_file(filename, Some(MAP_DIR_C), Themed::NoTheme);
-        let fpath = Path::new(
-            fpath
-                .to_str()
-                .expect("unable to convert C string to UTF-8 string"),
-        );
+        let fpath = self.find_data_file(filename, Some(MAP_DIR_C), Themed::NoTheme)?;
+        self.read_crew_file(&fpath)?;
 
-        let mut main_droids_file =
-            read_and_malloc_and_terminate_file(fpath, END_OF_DROID_DATA_STRING);
-
-        // The Droid crew file for this map is now completely read into memory
-        // It's now time to decode the file and to fill the array of enemys with
-        // new droids of the given types.
-        let mut droid_section_slice_opt =
-            split_at_subslice_mut(&mut main_droids_file, DROIDS_LEVEL_DESCRIPTION_START_STRING)
-                .map(|(_, s)| s);
-        while let Some(droid_section_slice) = droid_section_slice_opt {
-            info!("Found another levels droids description starting point entry!");
-            let end_of_this_droid_section_index =
-                find_subslice(droid_section_slice, DROIDS_LEVEL_DESCRIPTION_END_STRING)
-                    .expect("GetCrew: Unterminated droid section encountered.");
-            self.get_this_levels_droids(droid_section_slice);
-
-            droid_section_slice_opt = split_at_subslice_mut(
-                &mut droid_section_slice[(end_of_this_droid_section_index + 2)..],
-                DROIDS_LEVEL_DESCRIPTION_START_STRING,
-            )
-            .map(|(_, s)| s);
-        }
-
-        // Now that the correct crew types have been filled into the
-        // right structure, it's time to set the energy of the corresponding
-        // droids to "full" which means to the maximum of each type.
-        for enemy in &mut self.main.enemys {
-            enemy.energy = self.vars.droidmap[enemy.ty.to_usize()].maxenergy;
-            enemy.status = Status::Mobile;
+        // Fill the array of enemys with new droids of the given types.
+        for levelnum in 0..u8::try_from(MAX_LEVELS).unwrap() {
+            if self.spawn_crew(levelnum).is_err() {
+                return Err(LoadError::invalid_data(
+                    &fpath,
+                    format!("more than {MAX_ENEMYS_ON_SHIP} droids on the ship"),
+                ));
+            }
         }
 
-        defs::OK.into()
+        Ok(())
     }
 
     /// loads lift-connctions to cur-ship struct
-    pub fn get_lift_connections(&mut self, filename: &[u8]) -> i32 {
-        const END_OF_LIFT_DATA_STRING: &[u8] = b"*** End of elevator specification file ***";
-        const START_OF_LIFT_RECTANGLE_DATA_STRING: &[u8] =
-            b"*** Beginning of elevator rectangles ***";
-
+    pub fn get_lift_connections(&mut self, filename: &[u8]) -> Result<(), LoadError> {
         /* Now get the lift-connection data from "FILE.elv" file */
-        let fpath = self.find_file(filename, Some(MAP_DIR_C), Themed::NoTheme);
-        let fpath = Path::new(
-            fpath
-                .to_str()
-                .expect("unable to convert C string to UTF-8 string"),
-        );
-
-        let data = read_and_malloc_and_terminate_file(fpath, END_OF_LIFT_DATA_STRING);
-
-        // At first we read in the rectangles that define where the colums of the
-        // lift are, so that we can highlight them later.
-        self.main.cur_ship.lift_row_rects.clear();
-        let mut entry_slice =
-            &data[find_subslice(&data, START_OF_LIFT_RECTANGLE_DATA_STRING).unwrap()..];
-        loop {
-            let next_entry_slice =
-                split_at_subslice(entry_slice, b"Elevator Number=").map(|(_, s)| s);
-            entry_slice = match next_entry_slice {
-                Some(x) => x,
-                None => break,
-            };
-
-            let elevator_index = nom::character::complete::u16::<_, ()>(entry_slice)
-                .finish()
-                .unwrap()
-                .1;
-            assert_eq!(
-                usize::from(elevator_index),
-                self.main.cur_ship.lift_row_rects.len()
-            );
-            entry_slice = &entry_slice[1..];
-
-            let x = read_tagged_i16(entry_slice, "ElRowX=");
-            let y = read_tagged_i16(entry_slice, "ElRowY=");
-            let w = read_tagged_u16(entry_slice, "ElRowW=");
-            let h = read_tagged_u16(entry_slice, "ElRowH=");
-
-            self.main
-                .cur_ship
-                .lift_row_rects
-                .push(Rect::new(x, y, w, h));
-        }
-
-        //--------------------
-        // Now we read in the rectangles that define where the decks of the
-        // current area system are, so that we can highlight them later in the
-        // elevator and console functions.
-        //
-        self.main
-            .cur_ship
-            .level_rects
-            .iter_mut()
-            .for_each(ArrayVec::clear);
-        let mut entry_slice = &*data;
-
-        loop {
-            let next_entry_slice = split_at_subslice(entry_slice, b"DeckNr=").map(|(_, s)| s);
-
-            entry_slice = match next_entry_slice {
-                Some(x) => x,
-                None => break,
-            };
-
-            let deck_index = nom::character::complete::u8::<_, ()>(entry_slice)
-                .finish()
-                .unwrap()
-                .1;
-
-            let deck = &mut self.main.cur_ship.level_rects[usize::from(deck_index)];
-            let rect_index = read_tagged_u16(entry_slice, "RectNumber=");
-            assert_eq!(usize::from(rect_index), deck.len());
-            entry_slice = &entry_slice[1..];
-
-            let x = read_tagged_i16(&entry_slice[1..], "DeckX=");
-            let y = read_tagged_i16(entry_slice, "DeckY=");
-            let w = read_tagged_u16(entry_slice, "DeckW=");
-            let h = read_tagged_u16(entry_slice, "DeckH=");
-
-            deck.push(Rect::new(x, y, w, h));
-        }
-
-        self.load_lifts_from_data(entry_slice);
+        let fpath = self.find_data_file(filename, Some(MAP_DIR_C), Themed::NoTheme)?;
 
-        defs::OK.into()
+        read_lift_file(&fpath, &mut self.main.cur_ship)
     }
 
-    fn load_lifts_from_data(&mut self, data: &[u8]) {
-        const START_OF_LIFT_DATA_STRING: &[u8] = b"*** Beginning of Lift Data ***";
-
-        let mut entry_slice = &data[find_subslice(data, START_OF_LIFT_DATA_STRING)
-            .expect("START OF LIFT DATA STRING NOT FOUND!  Terminating...")..];
-
-        self.main.cur_ship.lifts.clear();
-        loop {
-            let next_entry_slice = split_at_subslice(entry_slice, b"Label=").map(|(_, s)| s);
-
-            entry_slice = match next_entry_slice {
-                Some(x) => x,
-                None => break,
-            };
-
-            let label = nom::character::complete::u16::<_, ()>(entry_slice)
-                .finish()
-                .unwrap()
-                .1;
-            entry_slice = &entry_slice[1..];
-
-            assert_eq!(usize::from(label), self.main.cur_ship.lifts.len());
-            let level = read_tagged_u8(entry_slice, "Deck=");
-            let x = read_tagged_i32(entry_slice, "PosX=");
-            let y = read_tagged_i32(entry_slice, "PosY=");
-            let up = read_tagged_i32(entry_slice, "LevelUp=");
-            let down = read_tagged_i32(entry_slice, "LevelDown=");
-            let row = read_tagged_i32(entry_slice, "LiftRow=");
-            self.main.cur_ship.lifts.push(Lift {
-                level,
-                x,
-                y,
-                up,
-                down,
-                row,
-            });
-        }
-    }
-
-    pub fn load_ship(&mut self, filename: &[u8]) -> i32 {
-        const END_OF_SHIP_DATA_STRING: &[u8] = b"*** End of Ship Data ***";
-
-        let mut level_start: [Option<&[u8]>; MAX_LEVELS] = [None; MAX_LEVELS];
+    pub fn load_ship(&mut self, filename: &[u8]) -> Result<(), LoadError> {
         self.free_ship_memory(); // clear vestiges of previous ship data, if any
 
         /* Read the whole ship-data to memory */
-        let fpath = self.find_file(filename, Some(MAP_DIR_C), Themed::NoTheme);
-        let fpath = Path::new(
-            fpath
-                .to_str()
-                .expect("unable to convert C string to UTF-8 string"),
-        );
-
-        let ship_data = read_and_malloc_and_terminate_file(fpath, END_OF_SHIP_DATA_STRING);
-
-        // Now we read the Area-name from the loaded data
-        let buffer = read_and_malloc_string_from_data(&ship_data, AREA_NAME_STRING, b"\"");
-        self.main.cur_ship.area_name.set_slice(buffer.to_bytes());
-        drop(buffer);
-
-        // Now we count the number of levels and remember their start-addresses.
-        // This is done by searching for the LEVEL_END_STRING again and again
-        // until it is no longer found in the ship file.  good.
-
-        let mut levels_count = 0u8;
-        let mut ship_rest = &*ship_data;
-        level_start[0] = Some(ship_rest);
-
-        loop {
-            let next_ship_rest =
-                split_at_subslice(ship_rest, LEVEL_END_STRING.as_bytes()).map(|(_, s)| s);
-            ship_rest = match next_ship_rest {
-                Some(x) => x,
-                None => break,
-            };
-
-            levels_count += 1;
-            level_start[usize::from(levels_count)] = Some(&ship_rest[1..]);
-        }
+        let fpath = self.find_data_file(filename, Some(MAP_DIR_C), Themed::NoTheme)?;
 
-        self.main.cur_ship.levels.clear();
-        let result = level_start
-            .iter()
-            .copied()
-            .enumerate()
-            .take(levels_count.into())
-            .try_for_each(|(index, start)| {
-                if let Some(new_level) = level_to_struct(start.unwrap()) {
-                    self.main.cur_ship.levels.push(new_level);
-
-                    // initialize doors, refreshes and lifts
-                    map::interpret(self.main.cur_ship.levels.last_mut().unwrap());
-                    Some(())
-                } else {
-                    error!("reading of level {} failed", index);
-                    None
-                }
-            });
-
-        if result.is_none() {
-            return defs::ERR.into();
-        }
-
-        defs::OK.into()
+        read_ship_file(&fpath, &mut self.main.cur_ship)
     }
 
     /// Checks Influencer on `SpecialFields` like Lifts and Konsoles and acts on it
@@ -1415,49 +1322,683 @@ freedroid-discussion@lists.sourceforge.net\n\
     }
 }
 
-fn read_tagged_generic<'a, F, T>(s: &'a [u8], tag: &str, f: F) -> T
+/// Reads the area name and the levels of a ship from a ship file.
+pub fn read_ship_file(fpath: &Path, ship: &mut Ship) -> Result<(), LoadError> {
+    let ship_data = read_and_malloc_and_terminate_file(fpath, END_OF_SHIP_DATA_STRING.as_bytes())?;
+
+    let mut problems = Vec::new();
+    read_ship(&ship_data, ship, &mut problems);
+    LoadError::first_problem(fpath, problems)
+}
+
+/// Reads the area name and the levels of a ship from the data of a ship
+/// file, the problems found are added to `problems`.
+///
+/// The positions of the waypoints of every level in the data are returned,
+/// see [`read_levels`].
+pub fn read_ship(
+    ship_data: &[u8],
+    ship: &mut Ship,
+    problems: &mut Vec<DataProblem>,
+) -> Vec<Vec<usize>> {
+    // Now we read the Area-name from the loaded data
+    let buffer = try_read_and_malloc_string_from_data(ship_data, AREA_NAME_STRING, b"\"");
+    if let Some(buffer) = expect_entry(buffer, AREA_NAME_STRING, 0, problems) {
+        if ship.area_name.try_set_slice(buffer.to_bytes()).is_err() {
+            problems.push(DataProblem::at(0, "the area name is too long"));
+        }
+    }
+
+    let (levels, waypoint_offsets) = read_levels(ship_data, problems);
+    if levels.is_empty() {
+        problems.push(DataProblem::in_file("the ship has no levels"));
+    }
+    ship.levels = levels;
+
+    waypoint_offsets
+}
+
+/// Writes the area name and the levels of a ship in the format of the ship
+/// files.
+///
+/// The levels must be numbered from zero on without gaps, nothing is written
+/// otherwise and an error of the kind [`io::ErrorKind::InvalidInput`] is
+/// returned.
+pub fn write_ship_file(ship: &mut Ship, mut writer: impl Write) -> io::Result<()> {
+    //--------------------
+    // The first thing we will write to the file will be a fine header,
+    // indicating what this file is about and things like that...
+    //
+    const MAP_HEADER_STRING: &str = "\n\
+----------------------------------------------------------------------\n\
+This file was generated using the Freedroid level editor.\n\
+Please feel free to make any modifications you like, but in order for you\n\
+to have an easier time, it is recommended that you use the Freedroid level\n\
+editor for this purpose.  If you have created some good new maps, please \n\
+send a short notice (not too large files attached) to the freedroid project.\n\
+\n\
+freedroid-discussion@lists.sourceforge.net\n\
+----------------------------------------------------------------------\n\
+\n";
+
+    // the levels are found by their numbers when the ship is read again
+    let mut levels: Vec<&mut Level> = ship.levels.iter_mut().collect();
+    levels.sort_by_key(|level| level.levelnum);
+    if let Some((expected, level)) = (0..)
+        .zip(&levels)
+        .find(|(expected, level)| level.levelnum != *expected)
+    {
+        let reason = if level.levelnum < expected {
+            format!("level number {} is used more than once", level.levelnum)
+        } else {
+            format!("level number {expected} is missing")
+        };
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{reason}, the levels must be numbered from 0 on without gaps"),
+        ));
+    }
+
+    writer.write_all(MAP_HEADER_STRING.as_bytes())?;
+    writer.write_all(AREA_NAME_STRING)?;
+    writer.write_all(ship.area_name.to_bytes())?;
+    writer.write_all(b"\"\n\n  ")?;
+
+    /* Save all Levels */
+
+    trace!("SaveShip(): now saving levels...");
+
+    for level in levels {
+        //--------------------
+        // Now comes the real saving part FOR ONE LEVEL.  First THE LEVEL is packed into a string and
+        // then this string is wirtten to the file.  easy. simple.
+        let level_mem = struct_to_mem(level);
+        let end = level_mem
+            .iter()
+            .copied()
+            .position(|c| c == b'\0')
+            .unwrap_or(level_mem.len());
+        writer.write_all(&level_mem[..end])?;
+    }
+
+    //--------------------
+    // Now we are almost done writing.  Everything that is missing is
+    // the termination string for the ship file.  This termination string
+    // is needed later for the ship loading functions to find the end of
+    // the data and to be able to terminate the long file-string with a
+    // null character at the right position.
+    //
+    writeln!(writer, "{END_OF_SHIP_DATA_STRING}\n")?;
+
+    trace!("SaveShip(): now flushing ship file...");
+    writer.flush()
+}
+
+/// Writes the file at `path` with `write`, first into a temporary file next to
+/// it which then takes its place, so that a failed save does not leave a
+/// broken file behind.  The errors name the file.
+pub fn write_file_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut io::BufWriter<std::fs::File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let temp_files = [(write_temp_file(path, write)?, path.to_path_buf())];
+    let result = replace_with_temp_files(&temp_files);
+    if result.is_err() {
+        remove_temp_files(&temp_files);
+    }
+    result
+}
+
+/// Writes the file at `path` with `write` into a temporary file next to it,
+/// returning the path of the temporary file.  Nothing is left behind on
+/// errors, which name the file.
+fn write_temp_file(
+    path: &Path,
+    write: impl FnOnce(&mut io::BufWriter<std::fs::File>) -> io::Result<()>,
+) -> io::Result<PathBuf> {
+    use std::fs::{self, File};
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let result = File::create(&tmp_path).and_then(|file| {
+        let mut writer = io::BufWriter::new(file);
+        write(&mut writer)?;
+        writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?
+            .sync_all()
+    });
+    if result.is_err() {
+        // a partly written file is of no use
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+        .map(|()| tmp_path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))
+}
+
+/// Moves the temporary files written by [`write_temp_file`] into the place of
+/// the files they have been written for.
+fn replace_with_temp_files(temp_files: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+    for (tmp_path, path) in temp_files {
+        std::fs::rename(tmp_path, path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+    }
+    Ok(())
+}
+
+/// Removes the temporary files which have not taken the place of their files.
+fn remove_temp_files(temp_files: &[(PathBuf, PathBuf)]) {
+    for (tmp_path, _) in temp_files {
+        let _ = std::fs::remove_file(tmp_path);
+    }
+}
+
+/// Writes the lift rows, the deck rectangles and the lifts of a ship in the
+/// format of the lift files.
+pub fn write_lift_file(ship: &Ship, mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "{START_OF_LIFT_RECTANGLE_DATA_STRING}")?;
+    for (index, rect) in ship.lift_row_rects.iter().enumerate() {
+        writeln!(
+            writer,
+            "Elevator Number={} ElRowX={} ElRowY={} ElRowW={} ElRowH={}",
+            index,
+            rect.x(),
+            rect.y(),
+            rect.width(),
+            rect.height()
+        )?;
+    }
+    writeln!(writer)?;
+
+    for (deck_index, deck) in ship.level_rects.iter().enumerate() {
+        for (index, rect) in deck.iter().enumerate() {
+            writeln!(
+                writer,
+                "DeckNr={} RectNumber={} DeckX={} DeckY={} DeckW={} DeckH={}",
+                deck_index,
+                index,
+                rect.x(),
+                rect.y(),
+                rect.width(),
+                rect.height()
+            )?;
+        }
+    }
+    writeln!(writer)?;
+
+    writeln!(writer, "{START_OF_LIFT_DATA_STRING}")?;
+    for (index, lift) in ship.lifts.iter().enumerate() {
+        writeln!(
+            writer,
+            "Label={} Deck={} PosX={} PosY={} LevelUp={} LevelDown={} LiftRow={}",
+            index, lift.level, lift.x, lift.y, lift.up, lift.down, lift.row
+        )?;
+    }
+    writeln!(writer)?;
+
+    writeln!(writer, "{END_OF_LIFT_DATA_STRING}")?;
+    writer.flush()
+}
+
+/// Reads the crew of a level from the section of a crew file starting at
+/// `start`, the problems found are added to `problems`.  The level number is
+/// returned with the crew, unless the section is unusable.
+///
+/// The droid types are given by their names, `droid_names` lists them in
+/// the order of [`Droid`].
+fn crew_from_section(
+    data: &[u8],
+    start: usize,
+    droid_names: &[&[u8]],
+    problems: &mut Vec<DataProblem>,
+) -> Option<(u8, Crew)> {
+    let Some(section_end) = find_subslice(&data[start..], DROIDS_LEVEL_DESCRIPTION_END_STRING)
+    else {
+        problems.push(DataProblem::at(
+            start,
+            "unterminated droid section encountered",
+        ));
+        return None;
+    };
+    let section_data = &data[start..start + section_end];
+
+    // Now we read in the level number for this level
+    let our_level_number = expect_entry(
+        try_read_u8_from_string(section_data, DROIDS_LEVEL_INDICATION_STRING),
+        DROIDS_LEVEL_INDICATION_STRING,
+        start,
+        problems,
+    );
+    if let Some(level_number) = our_level_number {
+        if usize::from(level_number) >= MAX_LEVELS {
+            problems.push(DataProblem::at(
+                start,
+                format!(
+                    "crew for level {level_number}, but a ship has at most {MAX_LEVELS} levels"
+                ),
+            ));
+        }
+    }
+
+    // Now we read in the maximal number of random droids for this level
+    let max_rand = expect_entry(
+        try_read_i32_from_string(section_data, DROIDS_MAXRAND_INDICATION_STRING),
+        DROIDS_MAXRAND_INDICATION_STRING,
+        start,
+        problems,
+    );
+
+    // Now we read in the minimal number of random droids for this level
+    let min_rand = expect_entry(
+        try_read_i32_from_string(section_data, DROIDS_MINRAND_INDICATION_STRING),
+        DROIDS_MINRAND_INDICATION_STRING,
+        start,
+        problems,
+    );
+
+    if let (Some(min_rand), Some(max_rand)) = (min_rand, max_rand) {
+        if min_rand > max_rand {
+            problems.push(DataProblem::at(
+                start,
+                format!(
+                    "minimum number of random droids {min_rand} is bigger than the maximum \
+                     {max_rand}"
+                ),
+            ));
+        }
+    }
+
+    let mut types = Vec::new();
+    for type_pos in section_data.find_iter(ALLOWED_TYPE_INDICATION_STRING) {
+        let remaining_data = &section_data[type_pos + ALLOWED_TYPE_INDICATION_STRING.len()..];
+        let type_indication_string = remaining_data.get(..3).unwrap_or(remaining_data);
+        // Now that we have got a type indication string, we only need to translate it
+        // into a number corresponding to that droid in the droid list
+        let droid = droid_names
+            .iter()
+            .position(|&name| name == type_indication_string)
+            .and_then(|list_index| u8::try_from(list_index).ok())
+            .and_then(|list_index| Droid::try_from(list_index).ok());
+        let Some(droid) = droid else {
+            problems.push(DataProblem::at(
+                start + type_pos,
+                format!(
+                    "unknown droid type `{}`",
+                    type_indication_string.to_str_lossy()
+                ),
+            ));
+            continue;
+        };
+        info!(
+            "Type indication string {} translated to type Nr.{}.",
+            String::from_utf8_lossy(type_indication_string),
+            droid.to_usize(),
+        );
+        types.push(droid);
+    }
+    info!(
+        "Found {} different allowed random types for this level. ",
+        types.len(),
+    );
+
+    if section_data.find(ALLOWED_TYPE_INDICATION_STRING).is_none()
+        && max_rand.is_some_and(|max_rand| max_rand > 0)
+    {
+        let level_number = our_level_number.map_or_else(String::new, |n| format!(" for level {n}"));
+        problems.push(DataProblem::at(
+            start,
+            format!("no droid type is allowed{level_number}"),
+        ));
+    }
+
+    let our_level_number =
+        our_level_number.filter(|&level_number| usize::from(level_number) < MAX_LEVELS)?;
+    Some((
+        our_level_number,
+        Crew {
+            min_rand: min_rand?,
+            max_rand: max_rand?,
+            types,
+        },
+    ))
+}
+
+/// Reads the crews of all levels from the data of a crew file, the problems
+/// found are added to `problems`.  Levels without a section in the file get
+/// no random droids.
+///
+/// The droid types are given by their names, `droid_names` lists them in
+/// the order of [`Droid`].
+pub fn read_crews(
+    data: &[u8],
+    droid_names: &[&[u8]],
+    problems: &mut Vec<DataProblem>,
+) -> [Crew; MAX_LEVELS] {
+    let mut crews: [Crew; MAX_LEVELS] = array::from_fn(|_| Crew::default());
+
+    for start in data.find_iter(DROIDS_LEVEL_DESCRIPTION_START_STRING) {
+        info!("Found another levels droids description starting point entry!");
+        if let Some((levelnum, crew)) = crew_from_section(data, start, droid_names, problems) {
+            crews[usize::from(levelnum)] = crew;
+        }
+    }
+
+    crews
+}
+
+/// Reads the crews of all levels from the data of a crew file, see
+/// [`read_crews`].
+pub fn crews_from_data(
+    data: &[u8],
+    fpath: &Path,
+    droid_names: &[&[u8]],
+) -> Result<[Crew; MAX_LEVELS], LoadError> {
+    let mut problems = Vec::new();
+    let crews = read_crews(data, droid_names, &mut problems);
+    LoadError::first_problem(fpath, problems)?;
+    Ok(crews)
+}
+
+/// Writes the crews of the levels of a ship in the format of the crew files.
+///
+/// The droid types are given by their names, `droid_names` lists them in
+/// the order of [`Droid`].
+pub fn write_crew_file(
+    ship: &Ship,
+    droid_names: &[&[u8]],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "This file was generated using the Freedroid level editor.\n"
+    )?;
+
+    for (levelnum, crew) in ship.crews.iter().enumerate().take(ship.levels.len()) {
+        writer.write_all(DROIDS_LEVEL_DESCRIPTION_START_STRING)?;
+        writeln!(writer)?;
+        writer.write_all(DROIDS_LEVEL_INDICATION_STRING)?;
+        writeln!(writer, "{levelnum}")?;
+        writer.write_all(DROIDS_MAXRAND_INDICATION_STRING)?;
+        writeln!(writer, "{}", crew.max_rand)?;
+        writer.write_all(DROIDS_MINRAND_INDICATION_STRING)?;
+        writeln!(writer, "{}", crew.min_rand)?;
+        for droid in &crew.types {
+            writer.write_all(ALLOWED_TYPE_INDICATION_STRING)?;
+            writer.write_all(droid_names[droid.to_usize()])?;
+            writeln!(writer)?;
+        }
+        writer.write_all(DROIDS_LEVEL_DESCRIPTION_END_STRING)?;
+        writeln!(writer, "\n")?;
+    }
+
+    writer.write_all(END_OF_DROID_DATA_STRING)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Chooses the random droids of a level with the given `crew`.
+pub fn roll_crew(crew: &Crew, rng: &mut impl Rng) -> Vec<Droid> {
+    if crew.types.is_empty() || crew.min_rand > crew.max_rand {
+        return Vec::new();
+    }
+
+    let count = rng.gen_range(crew.min_rand..=crew.max_rand);
+    (0..count)
+        .map(|_| *crew.types.choose(rng).unwrap())
+        .collect()
+}
+
+/// Puts `droids` on board of level `levelnum`, taking the places of the droids
+/// which are out first.  Their energy is the `max_energy` of their type.
+fn put_on_board(
+    enemys: &mut ArrayVec<Enemy, MAX_ENEMYS_ON_SHIP>,
+    droids: Vec<Droid>,
+    levelnum: u8,
+    max_energy: impl Fn(Droid) -> f32,
+) -> Result<(), CapacityError<Enemy>> {
+    for droid in droids {
+        let new_enemy = Enemy {
+            energy: max_energy(droid),
+            ..Enemy::new(droid, levelnum)
+        };
+        let enemy_slot = enemys.iter_mut().find(|enemy| enemy.status == Status::Out);
+        if let Some(enemy_slot) = enemy_slot {
+            *enemy_slot = new_enemy;
+        } else {
+            enemys.try_push(new_enemy)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the lift connections of a ship from a lift file.
+///
+/// The lift rows, the deck rectangles and the lifts of `ship` are replaced.
+pub fn read_lift_file(fpath: &Path, ship: &mut Ship) -> Result<(), LoadError> {
+    let data = read_and_malloc_and_terminate_file(fpath, END_OF_LIFT_DATA_STRING.as_bytes())?;
+
+    let mut problems = Vec::new();
+    read_lifts(&data, ship, &mut problems);
+    LoadError::first_problem(fpath, problems)
+}
+
+/// Reads the lift connections of a ship from the data of a lift file, the
+/// problems found are added to `problems`.
+///
+/// The lift rows, the deck rectangles and the lifts of `ship` are replaced.
+/// The positions of the lifts in the data are returned.
+#[allow(clippy::too_many_lines)]
+pub fn read_lifts(data: &[u8], ship: &mut Ship, problems: &mut Vec<DataProblem>) -> Vec<usize> {
+    use nom::character::complete::{i16, u8, u16};
+
+    // At first we read in the rectangles that define where the colums of the
+    // lift are, so that we can highlight them later.
+    ship.lift_row_rects.clear();
+    let rects_start = data.find(START_OF_LIFT_RECTANGLE_DATA_STRING);
+    if let Some(rects_start) = expect_entry(
+        rects_start,
+        START_OF_LIFT_RECTANGLE_DATA_STRING,
+        0,
+        problems,
+    ) {
+        for offset in data[rects_start..].find_iter(b"Elevator Number=") {
+            let offset = rects_start + offset;
+            let entry = &data[offset..];
+            let elevator_index = read_entry(entry, "Elevator Number=", u16, offset, problems);
+            let x = read_entry(entry, "ElRowX=", i16, offset, problems);
+            let y = read_entry(entry, "ElRowY=", i16, offset, problems);
+            let w = read_entry(entry, "ElRowW=", u16, offset, problems);
+            let h = read_entry(entry, "ElRowH=", u16, offset, problems);
+
+            let expected = ship.lift_row_rects.len();
+            if let Some(index) = elevator_index.filter(|&index| usize::from(index) != expected) {
+                problems.push(DataProblem::at(
+                    offset,
+                    format!("elevator number {index} found, {expected} was expected"),
+                ));
+            }
+
+            // a row is kept even if it is broken, so that the others keep their numbers
+            let rect = Rect::new(
+                x.unwrap_or_default(),
+                y.unwrap_or_default(),
+                w.unwrap_or_default(),
+                h.unwrap_or_default(),
+            );
+            if ship.lift_row_rects.try_push(rect).is_err() {
+                problems.push(DataProblem::at(
+                    offset,
+                    format!("more than {MAX_LIFT_ROWS} elevator rows"),
+                ));
+                break;
+            }
+        }
+    }
+
+    //--------------------
+    // Now we read in the rectangles that define where the decks of the
+    // current area system are, so that we can highlight them later in the
+    // elevator and console functions.
+    //
+    ship.level_rects.iter_mut().for_each(ArrayVec::clear);
+    // the lifts follow the last deck rectangle
+    let mut lifts_from = 0;
+    for offset in data.find_iter(b"DeckNr=") {
+        lifts_from = offset;
+        let entry = &data[offset..];
+        let deck_index = read_entry(entry, "DeckNr=", u8, offset, problems);
+        let rect_index = read_entry(entry, "RectNumber=", u16, offset, problems);
+        let x = read_entry(entry, "DeckX=", i16, offset, problems);
+        let y = read_entry(entry, "DeckY=", i16, offset, problems);
+        let w = read_entry(entry, "DeckW=", u16, offset, problems);
+        let h = read_entry(entry, "DeckH=", u16, offset, problems);
+
+        let Some(deck_index) = deck_index else {
+            continue;
+        };
+        let Some(deck) = ship.level_rects.get_mut(usize::from(deck_index)) else {
+            problems.push(DataProblem::at(
+                offset,
+                format!("invalid deck number {deck_index}"),
+            ));
+            continue;
+        };
+        if let Some(index) = rect_index.filter(|&index| usize::from(index) != deck.len()) {
+            problems.push(DataProblem::at(
+                offset,
+                format!(
+                    "rectangle number {index} found for deck {deck_index}, {} was expected",
+                    deck.len()
+                ),
+            ));
+        }
+
+        let rect = Rect::new(
+            x.unwrap_or_default(),
+            y.unwrap_or_default(),
+            w.unwrap_or_default(),
+            h.unwrap_or_default(),
+        );
+        if deck.try_push(rect).is_err() {
+            problems.push(DataProblem::at(
+                offset,
+                format!("more than {MAX_LEVEL_RECTS} rectangles for deck {deck_index}"),
+            ));
+        }
+    }
+
+    read_lift_entries(data, lifts_from, ship, problems)
+}
+
+/// Reads the lifts following `from` in the data of a lift file, and returns
+/// their positions.
+fn read_lift_entries(
+    data: &[u8],
+    from: usize,
+    ship: &mut Ship,
+    problems: &mut Vec<DataProblem>,
+) -> Vec<usize> {
+    use nom::character::complete::{i32, u8, u16};
+
+    ship.lifts.clear();
+    let mut offsets = Vec::new();
+
+    let lifts_start = data[from..]
+        .find(START_OF_LIFT_DATA_STRING)
+        .map(|pos| from + pos);
+    let Some(lifts_start) = expect_entry(lifts_start, START_OF_LIFT_DATA_STRING, from, problems)
+    else {
+        return offsets;
+    };
+
+    for offset in data[lifts_start..].find_iter(b"Label=") {
+        let offset = lifts_start + offset;
+        let entry = &data[offset..];
+        let label = read_entry(entry, "Label=", u16, offset, problems);
+        let level = read_entry(entry, "Deck=", u8, offset, problems);
+        let x = read_entry(entry, "PosX=", i32, offset, problems);
+        let y = read_entry(entry, "PosY=", i32, offset, problems);
+        let up = read_entry(entry, "LevelUp=", i32, offset, problems);
+        let down = read_entry(entry, "LevelDown=", i32, offset, problems);
+        let row = read_entry(entry, "LiftRow=", i32, offset, problems);
+
+        let expected = ship.lifts.len();
+        if let Some(label) = label.filter(|&label| usize::from(label) != expected) {
+            problems.push(DataProblem::at(
+                offset,
+                format!("lift label {label} found, {expected} was expected"),
+            ));
+        }
+
+        // a lift is kept even if it is broken, so that the others keep their numbers
+        let lift = Lift {
+            level: level.unwrap_or_default(),
+            x: x.unwrap_or_default(),
+            y: y.unwrap_or_default(),
+            up: up.unwrap_or(-1),
+            down: down.unwrap_or(-1),
+            row: row.unwrap_or_default(),
+        };
+        if ship.lifts.try_push(lift).is_err() {
+            problems.push(DataProblem::at(
+                offset,
+                format!("more than {MAX_LIFTS} lifts"),
+            ));
+            break;
+        }
+        offsets.push(offset);
+    }
+
+    // the elevator follows these links blindly, so they have to be valid
+    let lifts = &ship.lifts;
+    let is_valid_link =
+        |link: i32| link == -1 || usize::try_from(link).is_ok_and(|link| link < lifts.len());
+    for (index, lift) in lifts.iter().enumerate() {
+        if is_valid_link(lift.up).not() || is_valid_link(lift.down).not() {
+            problems.push(DataProblem::at(
+                offsets[index],
+                format!("lift {index} leads to a lift that does not exist"),
+            ));
+        }
+    }
+
+    offsets
+}
+
+fn read_tagged_generic<'a, F, T>(s: &'a [u8], tag: &str, f: F) -> Option<T>
 where
     F: Fn(&'a [u8]) -> IResult<&'a [u8], T, ()>,
 {
-    let pos = s
-        .windows(tag.len())
-        .enumerate()
-        .find(|&(_, s)| s == tag.as_bytes())
-        .unwrap()
-        .0;
+    let pos = find_subslice(s, tag.as_bytes())?;
 
     whitespace::<_, ()>
         .and(f)
         .parse(&s[(pos + tag.len())..])
         .map(|(_, (_, n))| n)
-        .unwrap()
-}
-
-#[inline]
-fn read_tagged_i32(s: &[u8], tag: &str) -> i32 {
-    read_tagged_generic(s, tag, nom::character::complete::i32)
-}
-
-#[inline]
-fn read_tagged_u8(s: &[u8], tag: &str) -> u8 {
-    read_tagged_generic(s, tag, nom::character::complete::u8)
-}
-
-#[inline]
-fn read_tagged_u16(s: &[u8], tag: &str) -> u16 {
-    read_tagged_generic(s, tag, nom::character::complete::u16)
+        .ok()
 }
 
-#[inline]
-fn read_tagged_i16(s: &[u8], tag: &str) -> i16 {
-    read_tagged_generic(s, tag, nom::character::complete::i16)
+/// Reads the number following `tag` in `entry` with `f`, a missing or
+/// invalid number is recorded at `offset`.
+fn read_entry<'a, F, T>(
+    entry: &'a [u8],
+    tag: &str,
+    f: F,
+    offset: usize,
+    problems: &mut Vec<DataProblem>,
+) -> Option<T>
+where
+    F: Fn(&'a [u8]) -> IResult<&'a [u8], T, ()>,
+{
+    expect_entry(read_tagged_generic(entry, tag, f), tag, offset, problems)
 }
 
 /// Returns a pointer to Map in a memory field
-#[cfg(not(target_os = "android"))]
 pub fn struct_to_mem(level: &mut Level) -> Box<[u8]> {
-    use std::io::Write;
-
     let x_len = level.xlen;
     let y_len = level.ylen;
 
@@ -1544,12 +2085,169 @@ pub fn struct_to_mem(level: &mut Level) -> Box<[u8]> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{ScratchDir, test_level};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn waypoints_lie_where_droids_pass() {
+        use MapTile as M;
+
+        for tile in [M::Floor, M::Lift, M::Refresh2, M::HZutuere, M::VGanztuere] {
+            assert!(is_walkable(tile), "{tile:?}");
+        }
+        for tile in [M::Void, M::KonsoleL, M::AlertRed, M::Tu, M::Block1] {
+            assert!(is_walkable(tile).not(), "{tile:?}");
+        }
+        assert_eq!(tile_passage(M::Void), Passage::Void);
+    }
 
     #[test]
     fn read_tagged_i32_simple() {
         assert_eq!(
-            read_tagged_i32(b"assd Hello=       5 World".as_slice(), "Hello="),
-            5,
+            read_tagged_generic(
+                b"assd Hello=       5 World".as_slice(),
+                "Hello=",
+                nom::character::complete::i32
+            ),
+            Some(5),
+        );
+    }
+
+    #[test]
+    fn crew_file_round_trip() {
+        const DROID_NAMES: [&[u8]; 3] = [b"001", b"123", b"139"];
+
+        let mut ship = Ship::default();
+        ship.levels.push(test_level(1, 1, MapTile::Floor));
+        ship.crews[0] = Crew {
+            min_rand: 2,
+            max_rand: 5,
+            types: vec![Droid::Droid123, Droid::Droid139],
+        };
+
+        let mut data = Vec::new();
+        write_crew_file(&ship, &DROID_NAMES, &mut data).unwrap();
+        let crews = crews_from_data(&data, Path::new("test.droids"), &DROID_NAMES).unwrap();
+        assert_eq!(crews, ship.crews);
+
+        let droids = roll_crew(&crews[0], &mut StdRng::seed_from_u64(17));
+        assert!((2..=5).contains(&droids.len()));
+        assert!(droids.iter().all(|droid| crews[0].types.contains(droid)));
+    }
+
+    #[test]
+    fn misnumbered_levels_are_not_written() {
+        let mut ship = Ship::default();
+        for levelnum in [0, 2] {
+            let mut level = test_level(1, 1, MapTile::Floor);
+            level.levelnum = levelnum;
+            ship.levels.push(level);
+        }
+
+        let mut data = Vec::new();
+        let err = write_ship_file(&mut ship, &mut data).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("level number 1 is missing"));
+        assert!(data.is_empty());
+
+        ship.levels[1].levelnum = 0;
+        let err = write_ship_file(&mut ship, &mut data).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("level number 0 is used more than once")
+        );
+    }
+
+    #[test]
+    fn same_seed_same_crews() {
+        let crews = [
+            Crew {
+                min_rand: 0,
+                max_rand: 8,
+                types: vec![Droid::Droid123, Droid::Droid139, Droid::Droid302],
+            },
+            Crew {
+                min_rand: 3,
+                max_rand: 12,
+                types: vec![Droid::Droid001, Droid::Droid999],
+            },
+        ];
+        let roll_all = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            crews
+                .iter()
+                .map(|crew| roll_crew(crew, &mut rng))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(roll_all(42), roll_all(42));
+        assert_eq!(roll_all(7), roll_all(7));
+    }
+
+    #[test]
+    fn crew_comes_on_board_with_full_batteries() {
+        let mut enemys = ArrayVec::new();
+        enemys.push(Enemy::new(Droid::Droid001, 0));
+        enemys.push(Enemy {
+            status: Status::Out,
+            ..Enemy::new(Droid::Droid001, 0)
+        });
+        let max_energy = |droid: Droid| if droid == Droid::Droid123 { 40. } else { 80. };
+
+        put_on_board(
+            &mut enemys,
+            vec![Droid::Droid123, Droid::Droid302],
+            1,
+            max_energy,
+        )
+        .unwrap();
+        let on_board: Vec<_> = enemys
+            .iter()
+            .map(|enemy| (enemy.ty, enemy.levelnum, enemy.energy, enemy.status))
+            .collect();
+        assert_eq!(
+            on_board,
+            [
+                (Droid::Droid001, 0, 0., Status::Mobile),
+                (Droid::Droid123, 1, 40., Status::Mobile),
+                (Droid::Droid302, 1, 80., Status::Mobile),
+            ]
         );
     }
+
+    #[test]
+    fn failed_write_keeps_the_old_file() {
+        let dir = ScratchDir::new("map");
+        let path = dir.join("test.shp");
+
+        write_file_atomically(&path, |writer| writer.write_all(b"old")).unwrap();
+        let err = write_file_atomically(&path, |writer| {
+            writer.write_all(b"new")?;
+            Err(io::Error::other("disk full"))
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("test.shp"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        assert!(dir.join("test.shp.tmp").exists().not());
+    }
+
+    #[test]
+    fn failed_write_keeps_the_other_files() {
+        let dir = ScratchDir::new("map-files");
+        let [ship, lifts] = ["test.shp", "test.elv"].map(|name| dir.join(name));
+        for path in [&ship, &lifts] {
+            write_file_atomically(path, |writer| writer.write_all(b"old")).unwrap();
+        }
+
+        let mut temp_files = Vec::new();
+        let tmp_path = write_temp_file(&ship, |writer| writer.write_all(b"new")).unwrap();
+        temp_files.push((tmp_path, ship.clone()));
+        write_temp_file(&lifts, |_| Err(io::Error::other("disk full"))).unwrap_err();
+        remove_temp_files(&temp_files);
+
+        for path in [&ship, &lifts] {
+            assert_eq!(std::fs::read(path).unwrap(), b"old");
+        }
+        assert_eq!(std::fs::read_dir(&*dir).unwrap().count(), 2);
+    }
 }
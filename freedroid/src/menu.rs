@@ -5,18 +5,24 @@ use crate::{
     array_c_string::ArrayCString,
     b_font::{char_width, font_height},
     defs::{
-        AssembleCombatWindowFlags, CREDITS_PIC_FILE, Cmds, DisplayBannerFlags, GRAPHICS_DIR_C,
-        MenuAction, Status, Themed,
+        self, AssembleCombatWindowFlags, CREDITS_PIC_FILE, Cmds, DisplayBannerFlags,
+        GRAPHICS_DIR_C, MenuAction, Status, Themed,
     },
+    init::{MissionInfo, find_missions},
     sound::Sound,
 };
 #[cfg(not(target_os = "android"))]
 use crate::{
     b_font::print_string_font,
     cur_level,
-    defs::{BYCOLOR, MAX_MAP_COLS, MAX_MAP_ROWS, MapTile},
+    defs::{BYCOLOR, Droid, MAP_DIR_C, MAX_MAP_COLS, MAX_MAP_ROWS, MapTile},
     input::{CMD_STRINGS, KEY_STRINGS},
+    level_editor::decks::{add_deck, delete_deck, max_random_droids, new_ship, swap_decks},
+    map::roll_crew,
+    user_dirs::user_data_dir,
 };
+#[cfg(not(target_os = "android"))]
+use log::error;
 
 use sdl::{Surface, convert::u32_to_u16};
 use sdl_sys::{
@@ -28,6 +34,8 @@ use std::{
     io::Cursor,
     ops::{AddAssign, Not, RangeInclusive, SubAssign},
 };
+#[cfg(not(target_os = "android"))]
+use std::{ffi::CString, fs};
 
 #[derive(Debug, Default)]
 pub struct Menu<'sdl> {
@@ -36,6 +44,10 @@ pub struct Menu<'sdl> {
     quit: bool,
     #[cfg(not(target_os = "android"))]
     pub quit_level_editor: bool,
+    /// The decks of the ship have been deleted or renumbered in the level
+    /// editor menu.
+    #[cfg(not(target_os = "android"))]
+    pub le_decks_changed: bool,
     last_movekey_time: u32,
     action_directions: MenuActionDirections,
     show_menu_last_move_tick: u32,
@@ -49,9 +61,29 @@ pub struct Menu<'sdl> {
     le_size_x_buf: ArrayCString<256>,
     #[cfg(not(target_os = "android"))]
     le_size_y_buf: ArrayCString<256>,
+    #[cfg(not(target_os = "android"))]
+    le_comment_buf: ArrayCString<256>,
+    #[cfg(not(target_os = "android"))]
+    le_crew_min_buf: ArrayCString<256>,
+    #[cfg(not(target_os = "android"))]
+    le_crew_max_buf: ArrayCString<256>,
+    #[cfg(not(target_os = "android"))]
+    le_crew_type_buf: ArrayCString<256>,
+    #[cfg(not(target_os = "android"))]
+    le_crew_allowed_buf: ArrayCString<256>,
+    #[cfg(not(target_os = "android"))]
+    le_crew_preview_buf: ArrayCString<256>,
+    /// The droid type chosen in the crew menu of the level editor.
+    #[cfg(not(target_os = "android"))]
+    le_crew_type: u8,
+    /// The droids rolled for a level as a preview of its crew.
+    #[cfg(not(target_os = "android"))]
+    le_crew_preview: Option<(u8, Vec<Droid>)>,
     empty_level_speedup_buf: ArrayCString<256>,
     music_volume_buf: ArrayCString<256>,
     sound_volume_buf: ArrayCString<256>,
+    missions: Vec<MissionInfo>,
+    mission_index: usize,
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -68,8 +100,10 @@ struct MenuActionDirections {
 const SHIP_EXT_C: &CStr = c".shp";
 #[cfg(not(target_os = "android"))]
 pub const SHIP_EXT: &str = ".shp";
-// const ELEVEXT: &CStr = c".elv";
-// const CREWEXT: &CStr = c".crw";
+#[cfg(not(target_os = "android"))]
+pub const LIFT_EXT: &str = ".elv";
+#[cfg(not(target_os = "android"))]
+pub const CREW_EXT: &str = ".droids";
 
 macro_rules! menu_entry {
     () => {
@@ -161,20 +195,49 @@ impl<'sdl> crate::Data<'sdl> {
     ];
 
     #[cfg(not(target_os = "android"))]
-    const LEVEL_EDITOR_MENU: [Entry<'sdl>; 8] = [
+    const LEVEL_EDITOR_MENU: [Entry<'sdl>; 12] = [
         menu_entry! { "Exit Level Editor", 	crate::Data::handle_le_exit},
         menu_entry! { "Current Level: ", crate::Data::handle_le_level_number},
         menu_entry! { "Level Color: ", crate::Data::handle_le_color},
         menu_entry! { "Levelsize X: ", crate::Data::handle_le_size_x},
         menu_entry! { "Levelsize Y: ", crate::Data::handle_le_size_y},
         menu_entry! { "Level Name: ", crate::Data::handle_le_name},
+        menu_entry! { "Music: ", crate::Data::handle_le_song},
+        menu_entry! { "Comment: ", crate::Data::handle_le_comment},
+        menu_entry! { "Droid Crew", None, Self::LEVEL_EDITOR_CREW_MENU },
+        menu_entry! { "Decks & Ship", None, Self::LEVEL_EDITOR_DECKS_MENU },
         menu_entry! { "Save ship: ", crate::Data::handle_le_save_ship},
         menu_entry! {},
     ];
 
+    #[cfg(not(target_os = "android"))]
+    const LEVEL_EDITOR_CREW_MENU: [Entry<'sdl>; 7] = [
+        menu_entry! { "Back"},
+        menu_entry! { "Min Random Droids: ", crate::Data::handle_le_crew_min},
+        menu_entry! { "Max Random Droids: ", crate::Data::handle_le_crew_max},
+        menu_entry! { "Droid Type: ", crate::Data::handle_le_crew_type},
+        menu_entry! { "Allowed: ", crate::Data::handle_le_crew_allowed},
+        menu_entry! { "Preview: ", crate::Data::handle_le_crew_preview},
+        menu_entry! {},
+    ];
+
+    #[cfg(not(target_os = "android"))]
+    const LEVEL_EDITOR_DECKS_MENU: [Entry<'sdl>; 7] = [
+        menu_entry! { "Back"},
+        menu_entry! { "Add Deck", crate::Data::handle_le_add_deck},
+        menu_entry! { "Delete Deck", crate::Data::handle_le_delete_deck},
+        menu_entry! { "Deck Number: ", crate::Data::handle_le_deck_number},
+        menu_entry! { "Ship View Rectangles", crate::Data::handle_le_ship_view},
+        menu_entry! { "New Ship", crate::Data::handle_le_new_ship},
+        menu_entry! {},
+    ];
+
     #[cfg(target_os = "android")]
-    const MAIN_MENU: [Entry<'sdl>; 8] = [
+    const MAIN_MENU: [Entry<'sdl>; 11] = [
         menu_entry! { "Back to Game"},
+        menu_entry! { "Start Mission: ", crate::Data::handle_start_mission},
+        menu_entry! { "Save Game", crate::Data::handle_save_game},
+        menu_entry! { "Load Game", crate::Data::handle_load_game},
         menu_entry! { "Graphics & Sound", None, Self::GRAPHICS_SOUND_MENU },
         menu_entry! { "Legacy Options", None, Self::LEGACY_MENU },
         menu_entry! { "HUD Settings", None, Self::HUD_MENU },
@@ -185,8 +248,11 @@ impl<'sdl> crate::Data<'sdl> {
     ];
 
     #[cfg(not(target_os = "android"))]
-    const MAIN_MENU: [Entry<'sdl>; 10] = [
+    const MAIN_MENU: [Entry<'sdl>; 13] = [
         menu_entry! { "Back to Game"},
+        menu_entry! { "Start Mission: ", crate::Data::handle_start_mission},
+        menu_entry! { "Save Game", crate::Data::handle_save_game},
+        menu_entry! { "Load Game", crate::Data::handle_load_game},
         menu_entry! { "Graphics & Sound", None, Self::GRAPHICS_SOUND_MENU },
         menu_entry! { "Legacy Options", None, Self::LEGACY_MENU },
         menu_entry! { "HUD Settings", None, Self::HUD_MENU },
@@ -255,6 +321,13 @@ impl<'sdl> crate::Data<'sdl> {
 
     /// simple wrapper to `ShowMenu`() to provide the external entry point into the main menu
     pub fn show_main_menu(&mut self) {
+        self.menu.missions = find_missions(&self.misc.data_dirs);
+        self.menu.mission_index = self
+            .menu
+            .missions
+            .iter()
+            .position(|mission| mission.file == self.mission_name())
+            .unwrap_or(0);
         self.show_menu(&Self::MAIN_MENU);
     }
 
@@ -333,6 +406,7 @@ impl<'sdl> crate::Data<'sdl> {
                 Some(b'l') => ne_screen = self.level_robots_list(ne_screen),
                 Some(b'g') => ne_screen = self.ship_robots_list(ne_screen),
                 Some(b'd') => self.level_robots_destroy(&mut ne_screen),
+                Some(b'c') => self.level_robots_call(&mut ne_screen),
                 Some(b't') => ne_screen = self.cheating_teleport(ne_screen),
                 Some(b'r') => ne_screen = self.change_robot_type(ne_screen),
                 Some(b'i') => {
@@ -650,7 +724,9 @@ impl<'sdl> crate::Data<'sdl> {
         } else {
             if let Some(handler) = handler {
                 self.wait_for_all_keys_released();
-                (handler)(self, MenuAction::CLICK);
+                self.change_game_config(|data| {
+                    (handler)(data, MenuAction::CLICK);
+                });
             }
 
             if let Some(submenu) = submenu {
@@ -670,7 +746,9 @@ impl<'sdl> crate::Data<'sdl> {
         need_update: &mut bool,
     ) {
         if let Some(handler) = handler {
-            (handler)(self, action);
+            self.change_game_config(|data| {
+                (handler)(data, action);
+            });
         }
         self.menu.show_menu_last_move_tick = self.sdl.ticks_ms();
         *need_update = true;
@@ -1048,6 +1126,36 @@ impl<'sdl> crate::Data<'sdl> {
         None
     }
 
+    pub fn handle_start_mission(&mut self, action: MenuAction) -> Option<&CStr> {
+        let len = self.menu.missions.len();
+        if len == 0 {
+            return (action == MenuAction::INFO).then_some(c"none found");
+        }
+
+        if action == MenuAction::INFO {
+            return Some(&self.menu.missions[self.menu.mission_index].title);
+        }
+
+        if action == MenuAction::LEFT || action == MenuAction::RIGHT {
+            self.move_lift_sound();
+            let index = &mut self.menu.mission_index;
+            if action == MenuAction::RIGHT {
+                *index = (*index + 1) % len;
+            } else {
+                *index = index.checked_sub(1).unwrap_or(len - 1);
+            }
+        } else if action == MenuAction::CLICK {
+            self.menu_item_selected_sound();
+            self.main
+                .next_mission
+                .clone_from(&self.menu.missions[self.menu.mission_index].file);
+            self.game_over = true;
+            self.menu.quit = true;
+        }
+
+        None
+    }
+
     pub fn handle_highscores(&mut self, action: MenuAction) -> Option<&CStr> {
         if action == MenuAction::CLICK {
             self.menu_item_selected_sound();
@@ -1056,6 +1164,49 @@ impl<'sdl> crate::Data<'sdl> {
         None
     }
 
+    pub fn handle_save_game(&mut self, action: MenuAction) -> Option<&CStr> {
+        if action == MenuAction::CLICK {
+            self.menu_item_selected_sound();
+            if self.save_game() == defs::OK.into() {
+                self.show_menu_message(b"Game saved");
+            } else {
+                self.show_menu_message(b"Saving the game failed");
+            }
+        }
+
+        None
+    }
+
+    pub fn handle_load_game(&mut self, action: MenuAction) -> Option<&CStr> {
+        if action == MenuAction::CLICK {
+            self.menu_item_selected_sound();
+            if self.load_game() == defs::OK.into() {
+                // back to the restored game
+                self.menu.quit = true;
+            } else {
+                self.show_menu_message(b"No saved game found");
+            }
+        }
+
+        None
+    }
+
+    /// Shows a short message below the menu and waits for a key press
+    fn show_menu_message(&mut self, text: &[u8]) {
+        let mut ne_screen = self.graphics.ne_screen.take().unwrap();
+        Self::centered_put_string_static(
+            &self.b_font,
+            &mut self.font_owner,
+            &mut ne_screen,
+            3 * i32::from(self.menu.font_height),
+            text,
+        );
+        assert!(ne_screen.flip());
+        self.graphics.ne_screen = Some(ne_screen);
+        self.wait_for_key_pressed();
+        self.initiate_menu(false);
+    }
+
     pub fn handle_credits(&mut self, action: MenuAction) -> Option<&CStr> {
         if action == MenuAction::CLICK {
             self.menu_item_selected_sound();
@@ -1065,51 +1216,111 @@ impl<'sdl> crate::Data<'sdl> {
         None
     }
 
+    /// Asks for a ship name and saves the ship under it into the map
+    /// directory of the user data, where missions find it, after asking
+    /// whether to overwrite a ship saved there before.
     #[cfg(not(target_os = "android"))]
     pub fn handle_le_save_ship(&mut self, action: MenuAction) -> Option<&CStr> {
-        use std::io::Write;
-
-        const SHIPNAME: &str = "Testship";
-        self.menu.fname.clear();
-        self.menu.fname.push_str(SHIPNAME);
-        self.menu.fname.push_cstr(SHIP_EXT_C);
-
         if action == MenuAction::INFO {
-            return Some(self.menu.fname.as_ref());
+            return self
+                .menu
+                .fname
+                .is_empty()
+                .not()
+                .then_some(self.menu.fname.as_ref());
+        }
+        if action != MenuAction::CLICK {
+            return None;
         }
 
-        if action == MenuAction::CLICK {
-            self.save_ship(SHIPNAME);
-            let mut output = [0; 255];
-            let mut cursor = Cursor::new(output.as_mut());
-            write!(
-                cursor,
-                "Ship saved as '{}'",
-                self.menu.fname.to_str().unwrap()
-            )
-            .unwrap();
-            let position = usize::try_from(cursor.position()).unwrap();
-            let mut ne_screen = self.graphics.ne_screen.take().unwrap();
-            self.centered_put_string(
-                &mut ne_screen,
-                3 * i32::from(font_height(
-                    self.global
-                        .menu_b_font
-                        .as_ref()
-                        .unwrap()
-                        .ro(&self.font_owner),
-                )),
-                &output[..position],
-            );
-            assert!(ne_screen.flip());
-            self.graphics.ne_screen = Some(ne_screen);
-            self.wait_for_key_pressed();
+        self.menu_item_selected_sound();
+        let Some(map_dir) = self
+            .main
+            .get_config_dir()
+            .map(|config_dir| user_data_dir(config_dir).join(MAP_DIR_C.to_str().unwrap()))
+        else {
+            self.le_show_message("No config-dir found, cannot save the ship!");
+            return None;
+        };
+        let shipname = self.le_ask_string(b"Ship name: ", 20);
+        let shipname = shipname.to_string_lossy();
+        let valid = shipname.is_empty().not()
+            && shipname.starts_with('.').not()
+            && shipname.contains(['/', '\\']).not();
+        if valid.not() {
+            self.le_show_message("Not saved, this is no valid ship name.");
+            return None;
+        }
+
+        if let Err(err) = fs::create_dir_all(&map_dir) {
+            error!("Failed to create {}: {err}", map_dir.display());
+            self.le_show_message(&format!("Saving failed: {err}"));
+            return None;
+        }
+        let path = map_dir.join(format!("{shipname}{SHIP_EXT}"));
+        if path.exists()
+            && self
+                .le_confirm(b"This ship exists, overwrite it? (y/n) ")
+                .not()
+        {
             self.initiate_menu(false);
+            return None;
+        }
+
+        match self.save_ship(&map_dir, &shipname) {
+            Ok(()) => {
+                self.menu.fname.clear();
+                self.menu.fname.push_str(&shipname);
+                self.menu.fname.push_cstr(SHIP_EXT_C);
+                self.le_show_message(&format!("Ship saved as '{}'", path.display()));
+            }
+            Err(err) => {
+                error!("Failed to save the ship: {err}");
+                self.le_show_message(&format!("Saving failed: {err}"));
+            }
         }
 
         None
     }
 
+    /// Shows `text` over the level editor menu until a key is pressed.
+    #[cfg(not(target_os = "android"))]
+    fn le_show_message(&mut self, text: &str) {
+        let mut ne_screen = self.graphics.ne_screen.take().unwrap();
+        self.centered_put_string(
+            &mut ne_screen,
+            3 * i32::from(font_height(
+                self.global
+                    .menu_b_font
+                    .as_ref()
+                    .unwrap()
+                    .ro(&self.font_owner),
+            )),
+            text.as_bytes(),
+        );
+        assert!(ne_screen.flip());
+        self.graphics.ne_screen = Some(ne_screen);
+        self.wait_for_key_pressed();
+        self.initiate_menu(false);
+    }
+
+    /// Asks for a text of at most `max_len` characters in the level editor
+    /// menu.
+    #[cfg(not(target_os = "android"))]
+    fn le_ask_string(&mut self, prompt: &[u8], max_len: i32) -> CString {
+        self.display_text(
+            prompt,
+            i32::from(self.vars.menu_rect.x()) - 2 * i32::from(self.menu.font_height),
+            i32::from(self.vars.menu_rect.y()) - 3 * i32::from(self.menu.font_height),
+            Some(self.vars.full_user_rect),
+        );
+        assert!(self.graphics.ne_screen.as_mut().unwrap().flip());
+
+        let text = self.get_string(max_len, 2).unwrap();
+        self.initiate_menu(false);
+        text
+    }
+
     #[cfg(not(target_os = "android"))]
     pub fn handle_le_name(&mut self, action: MenuAction) -> Option<&CStr> {
         if action == MenuAction::INFO {
@@ -1117,16 +1328,50 @@ impl<'sdl> crate::Data<'sdl> {
         }
 
         if action == MenuAction::CLICK {
-            self.display_text(
-                b"New level name: ",
-                i32::from(self.vars.menu_rect.x()) - 2 * i32::from(self.menu.font_height),
-                i32::from(self.vars.menu_rect.y()) - 3 * i32::from(self.menu.font_height),
-                Some(self.vars.full_user_rect),
-            );
-            assert!(self.graphics.ne_screen.as_mut().unwrap().flip());
+            cur_level!(mut self.main).levelname = self.le_ask_string(b"New level name: ", 15);
+        }
 
-            cur_level!(mut self.main).levelname = self.get_string(15, 2).unwrap();
-            self.initiate_menu(false);
+        None
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_song(&mut self, action: MenuAction) -> Option<&CStr> {
+        if action == MenuAction::INFO {
+            return Some(&cur_level!(self.main).background_song_name);
+        }
+
+        if action == MenuAction::CLICK {
+            let song = self.le_ask_string(b"New background music: ", 30);
+            // every level needs some music
+            if song.is_empty().not() {
+                cur_level!(mut self.main).background_song_name = song;
+            }
+        }
+
+        None
+    }
+
+    /// The comment shown when entering the level, only its start fits into
+    /// the menu.
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_comment(&mut self, action: MenuAction) -> Option<&CStr> {
+        const SHOWN_LEN: usize = 16;
+
+        if action == MenuAction::INFO {
+            let comment = cur_level!(self.main).enter_comment.to_string_lossy();
+            self.menu.le_comment_buf.clear();
+            if comment.chars().count() > SHOWN_LEN {
+                let start: String = comment.chars().take(SHOWN_LEN).collect();
+                self.menu.le_comment_buf.push_str(start);
+                self.menu.le_comment_buf.push_str("...");
+            } else {
+                self.menu.le_comment_buf.push_str(comment);
+            }
+            return Some(self.menu.le_comment_buf.as_ref());
+        }
+
+        if action == MenuAction::CLICK {
+            cur_level!(mut self.main).enter_comment = self.le_ask_string(b"New comment: ", 60);
         }
 
         None
@@ -1269,6 +1514,327 @@ impl<'sdl> crate::Data<'sdl> {
         None
     }
 
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_crew_min(&mut self, action: MenuAction) -> Option<&CStr> {
+        use std::fmt::Write;
+
+        let levelnum = cur_level!(self.main).levelnum;
+        let crew = &mut self.main.cur_ship.crews[usize::from(levelnum)];
+        if action == MenuAction::INFO {
+            self.menu.le_crew_min_buf.clear();
+            write!(self.menu.le_crew_min_buf, "{}", crew.min_rand).unwrap();
+            return Some(self.menu.le_crew_min_buf.as_ref());
+        }
+
+        let max_rand = crew.max_rand;
+        MenuChange {
+            sound_on: self.main.sound_on,
+            sdl: self.sdl,
+            sound: self.sound.as_ref().unwrap(),
+            action,
+            val: &mut crew.min_rand,
+        }
+        .run(1, 0..=max_rand);
+        self.menu.le_crew_preview = None;
+
+        None
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_crew_max(&mut self, action: MenuAction) -> Option<&CStr> {
+        use std::fmt::Write;
+
+        let levelnum = cur_level!(self.main).levelnum;
+        let crew = &mut self.main.cur_ship.crews[usize::from(levelnum)];
+        if action == MenuAction::INFO {
+            self.menu.le_crew_max_buf.clear();
+            write!(self.menu.le_crew_max_buf, "{}", crew.max_rand).unwrap();
+            return Some(self.menu.le_crew_max_buf.as_ref());
+        }
+
+        // random droids need a type to be chosen from, and room on the ship
+        let max_rand = if crew.types.is_empty() {
+            0
+        } else {
+            max_random_droids(&self.main.cur_ship, levelnum)
+        };
+        let crew = &mut self.main.cur_ship.crews[usize::from(levelnum)];
+        let min_rand = crew.min_rand;
+        MenuChange {
+            sound_on: self.main.sound_on,
+            sdl: self.sdl,
+            sound: self.sound.as_ref().unwrap(),
+            action,
+            val: &mut crew.max_rand,
+        }
+        .run(1, min_rand..=max_rand);
+        self.menu.le_crew_preview = None;
+
+        None
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_crew_type(&mut self, action: MenuAction) -> Option<&CStr> {
+        use std::fmt::Write;
+
+        let levelnum = cur_level!(self.main).levelnum;
+        let droid = Droid::try_from(self.menu.le_crew_type).unwrap();
+        let allowed = self.main.cur_ship.crews[usize::from(levelnum)]
+            .types
+            .contains(&droid);
+        if action == MenuAction::INFO {
+            self.menu.le_crew_type_buf.clear();
+            write!(
+                self.menu.le_crew_type_buf,
+                "{} {}",
+                self.vars.droidmap[droid.to_usize()]
+                    .druidname
+                    .to_str()
+                    .unwrap(),
+                if allowed { "allowed" } else { "-" },
+            )
+            .unwrap();
+            return Some(self.menu.le_crew_type_buf.as_ref());
+        }
+
+        if action == MenuAction::CLICK {
+            self.menu_item_selected_sound();
+            let crew = &mut self.main.cur_ship.crews[usize::from(levelnum)];
+            if allowed {
+                crew.types.retain(|&ty| ty != droid);
+                if crew.types.is_empty() {
+                    crew.min_rand = 0;
+                    crew.max_rand = 0;
+                }
+            } else {
+                crew.types.push(droid);
+                crew.types.sort_unstable();
+            }
+            self.menu.le_crew_preview = None;
+        } else {
+            let mut droid_type = self.menu.le_crew_type;
+            self.menu_change(
+                action,
+                &mut droid_type,
+                1,
+                0,
+                self.main.number_of_droid_types - 1,
+            );
+            self.menu.le_crew_type = droid_type;
+        }
+
+        None
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_crew_allowed(&mut self, action: MenuAction) -> Option<&CStr> {
+        use std::fmt::Write;
+
+        if action != MenuAction::INFO {
+            return None;
+        }
+
+        let levelnum = cur_level!(self.main).levelnum;
+        let crew = &self.main.cur_ship.crews[usize::from(levelnum)];
+        self.menu.le_crew_allowed_buf.clear();
+        if crew.types.is_empty() {
+            self.menu.le_crew_allowed_buf.push_str("none");
+        }
+        for (index, droid) in crew.types.iter().enumerate() {
+            if index > 0 {
+                self.menu.le_crew_allowed_buf.push_str(" ");
+            }
+            write!(
+                self.menu.le_crew_allowed_buf,
+                "{}",
+                self.vars.droidmap[droid.to_usize()]
+                    .druidname
+                    .to_str()
+                    .unwrap()
+            )
+            .unwrap();
+        }
+        Some(self.menu.le_crew_allowed_buf.as_ref())
+    }
+
+    /// Shows which droids the crew of the current level could bring on
+    /// board, counted by type.  Clicking rolls them again.
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_crew_preview(&mut self, action: MenuAction) -> Option<&CStr> {
+        use std::fmt::Write;
+
+        let levelnum = cur_level!(self.main).levelnum;
+        if action == MenuAction::CLICK {
+            self.menu_item_selected_sound();
+            self.menu.le_crew_preview = None;
+        }
+        if action != MenuAction::INFO {
+            return None;
+        }
+
+        let droids = match &mut self.menu.le_crew_preview {
+            Some((preview_level, droids)) if *preview_level == levelnum => droids,
+            preview => {
+                let crew = &self.main.cur_ship.crews[usize::from(levelnum)];
+                // a preview must not change the game that follows from the seed
+                let mut droids = roll_crew(crew, &mut rand::thread_rng());
+                droids.sort_unstable();
+                &mut preview.insert((levelnum, droids)).1
+            }
+        };
+
+        self.menu.le_crew_preview_buf.clear();
+        write!(self.menu.le_crew_preview_buf, "{}", droids.len()).unwrap();
+        for group in droids.chunk_by(|a, b| a == b) {
+            write!(
+                self.menu.le_crew_preview_buf,
+                " {}x{}",
+                group.len(),
+                self.vars.droidmap[group[0].to_usize()]
+                    .druidname
+                    .to_str()
+                    .unwrap()
+            )
+            .unwrap();
+        }
+        Some(self.menu.le_crew_preview_buf.as_ref())
+    }
+
+    /// Asks `question` in the level editor menu, the answer is yes if 'y'
+    /// is pressed.
+    #[cfg(not(target_os = "android"))]
+    fn le_confirm(&mut self, question: &[u8]) -> bool {
+        self.display_text(
+            question,
+            i32::from(self.vars.menu_rect.x()) - 2 * i32::from(self.menu.font_height),
+            i32::from(self.vars.menu_rect.y()) - 3 * i32::from(self.menu.font_height),
+            Some(self.vars.full_user_rect),
+        );
+        assert!(self.graphics.ne_screen.as_mut().unwrap().flip());
+
+        self.wait_for_key_pressed() == u16::from(b'y')
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_add_deck(&mut self, action: MenuAction) -> Option<&CStr> {
+        if action != MenuAction::CLICK {
+            return None;
+        }
+
+        self.menu_item_selected_sound();
+        let song = self.main.cur_level().background_song_name.clone();
+        if let Some(levelnum) = add_deck(&mut self.main.cur_ship, song) {
+            self.teleport(levelnum, 3, 3);
+            self.switch_background_music_to(Some(BYCOLOR));
+        }
+        self.initiate_menu(false);
+
+        None
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_delete_deck(&mut self, action: MenuAction) -> Option<&CStr> {
+        if action != MenuAction::CLICK {
+            return None;
+        }
+
+        self.menu_item_selected_sound();
+        // a ship needs at least one deck
+        if self.main.cur_ship.levels.len() > 1 && self.le_confirm(b"Delete this deck? (y/n) ") {
+            let levelnum = self.main.cur_level().levelnum;
+            delete_deck(&mut self.main.cur_ship, levelnum);
+            self.main.enemys.retain(|enemy| enemy.levelnum != levelnum);
+            for enemy in &mut self.main.enemys {
+                if enemy.levelnum > levelnum {
+                    enemy.levelnum -= 1;
+                }
+            }
+
+            // continue on the deck which took the place of the deleted one
+            let last = u8::try_from(self.main.cur_ship.levels.len()).unwrap() - 1;
+            let levelnum = levelnum.min(last);
+            self.main.cur_level_index = Some(usize::from(levelnum));
+            self.teleport(levelnum, 3, 3);
+            self.switch_background_music_to(Some(BYCOLOR));
+            self.menu.le_decks_changed = true;
+            self.menu.le_crew_preview = None;
+        }
+        self.initiate_menu(false);
+
+        None
+    }
+
+    /// Moves the current deck to another number, the deck which had that
+    /// number takes the old one.
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_deck_number(&mut self, action: MenuAction) -> Option<&CStr> {
+        use std::fmt::Write;
+
+        let levelnum = self.main.cur_level().levelnum;
+        if action == MenuAction::INFO {
+            self.menu.le_level_number_buf.clear();
+            write!(self.menu.le_level_number_buf, "{levelnum}").unwrap();
+            return Some(self.menu.le_level_number_buf.as_ref());
+        }
+
+        let mut new_levelnum = levelnum;
+        self.menu_change(
+            action,
+            &mut new_levelnum,
+            1,
+            0,
+            u8::try_from(self.main.cur_ship.levels.len()).unwrap() - 1,
+        );
+        if new_levelnum != levelnum {
+            swap_decks(&mut self.main.cur_ship, levelnum, new_levelnum);
+            for enemy in &mut self.main.enemys {
+                if enemy.levelnum == levelnum {
+                    enemy.levelnum = new_levelnum;
+                } else if enemy.levelnum == new_levelnum {
+                    enemy.levelnum = levelnum;
+                }
+            }
+            self.main.cur_level_index = Some(usize::from(new_levelnum));
+            self.menu.le_decks_changed = true;
+            self.menu.le_crew_preview = None;
+        }
+
+        None
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_ship_view(&mut self, action: MenuAction) -> Option<&CStr> {
+        if action == MenuAction::CLICK {
+            self.menu_item_selected_sound();
+            self.edit_ship_view();
+            self.initiate_menu(false);
+        }
+
+        None
+    }
+
+    #[cfg(not(target_os = "android"))]
+    pub fn handle_le_new_ship(&mut self, action: MenuAction) -> Option<&CStr> {
+        if action != MenuAction::CLICK {
+            return None;
+        }
+
+        self.menu_item_selected_sound();
+        if self.le_confirm(b"Start a new ship? Unsaved changes are lost. (y/n) ") {
+            let song = self.main.cur_level().background_song_name.clone();
+            self.main.cur_ship = new_ship(song);
+            self.main.enemys.clear();
+            self.main.cur_level_index = Some(0);
+            self.teleport(0, 3, 3);
+            self.switch_background_music_to(Some(BYCOLOR));
+            self.menu.le_decks_changed = true;
+            self.menu.le_crew_preview = None;
+        }
+        self.initiate_menu(false);
+
+        None
+    }
+
     pub fn handle_strictly_classic(&mut self, action: MenuAction) -> Option<&CStr> {
         if action == MenuAction::CLICK {
             self.menu_item_selected_sound();
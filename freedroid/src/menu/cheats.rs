@@ -1,4 +1,4 @@
-use crate::{cur_level, defs::Status};
+use crate::{cur_level, defs::Status, pathfinding::nearest_waypoint};
 
 use nom::Finish;
 use sdl::FrameBuffer;
@@ -37,6 +37,7 @@ impl<'sdl> crate::Data<'sdl> {
         print_sdl!(" l. robot list of current level\n");
         print_sdl!(" g. complete robot list\n");
         print_sdl!(" d. destroy robots on current level\n");
+        print_sdl!(" c. call robots of current level\n");
         print_sdl!(" t. Teleportation\n");
         print_sdl!(" r. change to new robot type\n");
         print_sdl!(
@@ -271,6 +272,33 @@ impl<'sdl> crate::Data<'sdl> {
         self.getchar_raw();
     }
 
+    /// Sends all robots of the current level to the waypoint closest to the
+    /// influencer.
+    pub(super) fn level_robots_call(&mut self, ne_screen: &mut FrameBuffer<'sdl>) {
+        let cur_level = cur_level!(self.main);
+        if let Some(target) = nearest_waypoint(&cur_level.waypoints, self.vars.me.pos) {
+            for enemy in &mut self.main.enemys {
+                if enemy.levelnum == cur_level.levelnum {
+                    enemy.target = Some(target);
+                }
+            }
+            self.printf_sdl(
+                ne_screen,
+                -1,
+                -1,
+                format_args!("All robots on this deck called to waypoint {target}!\n"),
+            );
+        } else {
+            self.printf_sdl(
+                ne_screen,
+                -1,
+                -1,
+                format_args!("No waypoints on this deck!\n"),
+            );
+        }
+        self.getchar_raw();
+    }
+
     #[must_use]
     pub(super) fn cheating_teleport(
         &mut self,
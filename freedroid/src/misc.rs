@@ -1,11 +1,15 @@
 use crate::{
     ArrayCString, Global,
     defs::{
-        self, AssembleCombatWindowFlags, Cmds, DisplayBannerFlags, FD_DATADIR, GRAPHICS_DIR_C,
-        LOCAL_DATADIR, PROGRESS_FILLER_FILE, PROGRESS_METER_FILE, Status, Themed,
+        self, AssembleCombatWindowFlags, Cmds, DATA_DIRS, DisplayBannerFlags, GRAPHICS_DIR_C,
+        PROGRESS_FILLER_FILE, PROGRESS_METER_FILE, Status, Themed,
     },
+    error::LoadError,
+    find_subslice,
     graphics::{Graphics, LoadBlockVidBppPicFlags, scale_pic},
     input::CMD_STRINGS,
+    structs::Config,
+    user_dirs::UserDirs,
 };
 
 use bstr::{BStr, ByteSlice};
@@ -16,10 +20,11 @@ use sdl::Rect;
 use std::{
     any::type_name,
     borrow::Cow,
-    env,
     ffi::{CStr, CString},
     fs::{self, File},
-    path::Path,
+    ops::Not,
+    path::{Path, PathBuf},
+    str::FromStr,
 };
 
 #[derive(Debug)]
@@ -30,6 +35,10 @@ pub struct Misc {
     one_frame_sdl_ticks: u32,
     now_sdl_ticks: u32,
     one_frame_delay: i64,
+    // the config variables which must not be saved, with the values to save instead
+    unsaved_config: Vec<(&'static str, String)>,
+    /// The directories searched for the game data, in order.
+    pub data_dirs: Vec<PathBuf>,
 }
 
 impl Default for Misc {
@@ -41,6 +50,8 @@ impl Default for Misc {
             one_frame_sdl_ticks: 0,
             now_sdl_ticks: 0,
             one_frame_delay: 0,
+            unsaved_config: Vec::new(),
+            data_dirs: DATA_DIRS.map(PathBuf::from).into(),
         }
     }
 }
@@ -66,58 +77,82 @@ const VID_SCALE_FACTOR: &str = "Vid_ScaleFactor";
 const HOG_CPU: &str = "Hog_Cpu";
 const EMPTY_LEVEL_SPEEDUP: &str = "EmptyLevelSpeedup";
 
+/// The variables of the config file, apart from the version and the key
+/// bindings.
+const CONFIG_VARIABLES: [&str; 19] = [
+    DRAW_FRAMERATE,
+    DRAW_ENERGY,
+    DRAW_POSITION,
+    DRAW_DEATHCOUNT,
+    DROID_TALK,
+    WANTED_TEXT_VISIBLE_TIME,
+    CURRENT_BG_MUSIC_VOLUME,
+    CURRENT_SOUND_FX_VOLUME,
+    CURRENT_GAMMA_CORRECTION,
+    THEME_NAME,
+    FULL_USER_RECT,
+    USE_FULLSCREEN,
+    TAKEOVER_ACTIVATES,
+    FIRE_HOLD_TAKEOVER,
+    SHOW_DECALS,
+    ALL_MAP_VISIBLE,
+    VID_SCALE_FACTOR,
+    HOG_CPU,
+    EMPTY_LEVEL_SPEEDUP,
+];
+
 type IntegralParserFn<'a, T> = fn(&'a [u8]) -> IResult<&'a [u8], T, ()>;
 
-pub fn read_integral_from_string<'a, T>(
+/// Reads the value following `label` with the parser `f`.  A missing label or
+/// an unreadable value gives `None`.
+pub fn try_read_integral_from_string<'a, T>(
     data: &'a [u8],
     label: &'a [u8],
     f: IntegralParserFn<'a, T>,
-) -> T {
+) -> Option<T> {
     use nom::character::complete::space0;
 
-    let pos = locate_string_in_data(data, label) + label.len();
-    let data = &data[pos..];
-
-    let Ok((_, (_, out))) = space0::<_, ()>.and(f).parse(data).finish() else {
-        panic!(
-            "could not read {} {} of label {}",
-            type_name::<T>(),
-            <&BStr>::from(data),
-            <&BStr>::from(label),
-        );
-    };
-    out
+    let pos = find_subslice(data, label)? + label.len();
+    space0::<_, ()>
+        .and(f)
+        .parse(&data[pos..])
+        .finish()
+        .ok()
+        .map(|(_, (_, out))| out)
 }
 
 #[inline]
-pub fn read_float_from_string(data: &[u8], label: &[u8]) -> f32 {
-    read_integral_from_string(data, label, nom::number::complete::float)
+pub fn try_read_float_from_string(data: &[u8], label: &[u8]) -> Option<f32> {
+    try_read_integral_from_string(data, label, nom::number::complete::float)
 }
 
 macro_rules! make_read_from_string_fn {
-    ($($fn_name:ident => $ty:ident),+ $(,)?) => {
+    ($($try_fn_name:ident => $ty:ident),+ $(,)?) => {
         $(
             #[inline]
-            pub fn $fn_name(data: &[u8], label: &[u8]) -> $ty {
-                read_integral_from_string(data, label, nom::character::complete::$ty)
+            pub fn $try_fn_name(data: &[u8], label: &[u8]) -> Option<$ty> {
+                try_read_integral_from_string(data, label, nom::character::complete::$ty)
             }
         )+
     };
 }
 
 make_read_from_string_fn! {
-    read_u8_from_string => u8,
-    read_i16_from_string => i16,
-    read_u16_from_string => u16,
-    read_i32_from_string => i32,
+    try_read_u8_from_string => u8,
+    try_read_i16_from_string => i16,
+    try_read_u16_from_string => u16,
+    try_read_i32_from_string => i32,
 }
 
-pub fn read_string_from_string<'a>(data: &'a [u8], label: &[u8]) -> &'a [u8] {
-    let pos = locate_string_in_data(data, label) + label.len();
+/// Reads the word following `label` in `data`, a missing label gives `None`.
+pub fn try_read_string_from_string<'a>(data: &'a [u8], label: &[u8]) -> Option<&'a [u8]> {
+    let pos = find_subslice(data, label)? + label.len();
     let data = &data[pos..];
-    data.iter()
-        .position(u8::is_ascii_whitespace)
-        .map_or(data, |pos| &data[..pos])
+    Some(
+        data.iter()
+            .position(u8::is_ascii_whitespace)
+            .map_or(data, |pos| &data[..pos]),
+    )
 }
 
 /// This function tries to locate a string in some given data string.
@@ -174,85 +209,19 @@ pub fn count_string_occurences(search: &[u8], target: &[u8]) -> usize {
         .count()
 }
 
-/// This function looks for a sting begin indicator and takes the string
-/// from after there up to a sting end indicator and mallocs memory for
-/// it, copys it there and returns it.
-/// The original source string specified should in no way be modified.
-pub fn read_and_malloc_string_from_data(
+/// This function looks for a string begin indicator and takes the string
+/// from after there up to a string end indicator.  A missing start or end
+/// string gives `None`.
+pub fn try_read_and_malloc_string_from_data(
     search_string: &[u8],
     start_indication_string: &[u8],
     end_indication_string: &[u8],
-) -> CString {
-    let mut search_pos = search_string
-        .windows(start_indication_string.len())
-        .position(|s| s == start_indication_string)
-        .unwrap_or_else(|| {
-            panic!(
-                "\n\
-                 \n\
-                 ----------------------------------------------------------------------\n\
-                 Freedroid has encountered a problem:\n\
-                 In function 'char* ReadAndMalocStringFromData ( ... ):\n\
-                 A starter string that was supposed to be in some data, most likely from an external\n\
-                 data file could not be found, which indicates a corrupted data file or \n\
-                 a serious bug in the reading functions.\n\
-                 \n\
-                 The string that couldn't be located was: {}\n\
-                 \n\
-                 Please check that your external text files are properly set up.\n\
-                 \n\
-                 Please also don't forget, that you might have to run 'make install'\n\
-                 again after you've made modifications to the data files in the source tree.\n\
-                 \n\
-                 Freedroid will terminate now to draw attention to the data problem it could\n\
-                 not resolve.... Sorry, if that interrupts a major game of yours.....\n\
-                 ----------------------------------------------------------------------\n\
-                 \n",
-                <&BStr>::from(start_indication_string)
-            )
-        });
-
-    // Now we move to the beginning
-    search_pos += start_indication_string.len();
+) -> Option<CString> {
+    let search_pos =
+        find_subslice(search_string, start_indication_string)? + start_indication_string.len();
     let search_slice = &search_string[search_pos..];
-    let string_length = search_slice
-        .windows(end_indication_string.len())
-        .position(|s| s == end_indication_string)
-        .unwrap_or_else(|| {
-            panic!(
-                "\n\
-                 \n\
-                 ----------------------------------------------------------------------\n\
-                 Freedroid has encountered a problem:\n\
-                 In function 'char* ReadAndMalocStringFromData ( ... ):\n\
-                 A terminating string that was supposed to be in some data, most likely from an \
-                 external\n\
-                 data file could not be found, which indicates a corrupted data file or \n\
-                 a serious bug in the reading functions.\n\
-                 \n\
-                 The string that couldn't be located was: {}\n\
-                 \n\
-                 Please check that your external text files are properly set up.\n\
-                 \n\
-                 Please also don't forget, that you might have to run 'make install'\n\
-                 again after you've made modifications to the data files in the source tree.\n\
-                 \n\
-                 Freedroid will terminate now to draw attention to the data problem it could\n\
-                 not resolve.... Sorry, if that interrupts a major game of yours.....\n\
-                 ----------------------------------------------------------------------\n\
-                 \n",
-                <&BStr>::from(end_indication_string)
-            )
-        });
-
-    let return_string = CString::new(&search_slice[..string_length]).unwrap();
-
-    info!(
-        "ReadAndMalocStringFromData): Successfully identified string: {}.",
-        return_string.to_string_lossy()
-    );
-
-    return_string
+    let string_length = find_subslice(search_slice, end_indication_string)?;
+    CString::new(&search_slice[..string_length]).ok()
 }
 
 impl crate::Data<'_> {
@@ -375,11 +344,11 @@ impl crate::Data<'_> {
 
     pub fn save_game_config(&self) -> i32 {
         use std::io::Write;
-        if self.main.config_dir.is_empty() {
+        let Some(config_dir) = self.main.get_config_dir() else {
             return defs::ERR.into();
-        }
+        };
 
-        let config_path = Path::new(self.main.config_dir.to_str().unwrap()).join("config");
+        let config_path = config_dir.join("config");
         let Ok(mut config) = File::create(&config_path) else {
             warn!(
                 "WARNING: failed to create config-file: {}",
@@ -388,42 +357,20 @@ impl crate::Data<'_> {
             return defs::ERR.into();
         };
 
-        macro_rules! write_key_value {
-            ($($key:ident = $value:expr),+ $(,)?) => {
-                $(
-                    writeln!(
-                        config,
-                        "{} = {}",
-                        $key,
-                        $value,
-                    ).unwrap();
-                )+
-            };
-        }
-
         // Now write the actual data, line by line
-        write_key_value!(
-            VERSION_STRING = env!("CARGO_PKG_VERSION"),
-            DRAW_FRAMERATE = self.global.game_config.draw_framerate,
-            DRAW_ENERGY = self.global.game_config.draw_energy,
-            DRAW_POSITION = self.global.game_config.draw_position,
-            DRAW_DEATHCOUNT = self.global.game_config.draw_death_count,
-            DROID_TALK = self.global.game_config.droid_talk,
-            WANTED_TEXT_VISIBLE_TIME = self.global.game_config.wanted_text_visible_time,
-            CURRENT_BG_MUSIC_VOLUME = self.global.game_config.current_bg_music_volume,
-            CURRENT_SOUND_FX_VOLUME = self.global.game_config.current_sound_fx_volume,
-            CURRENT_GAMMA_CORRECTION = self.global.game_config.current_gamma_correction,
-            THEME_NAME = self.global.game_config.theme_name.to_str().unwrap(),
-            FULL_USER_RECT = self.global.game_config.full_user_rect,
-            USE_FULLSCREEN = self.global.game_config.use_fullscreen,
-            TAKEOVER_ACTIVATES = self.global.game_config.takeover_activates,
-            FIRE_HOLD_TAKEOVER = self.global.game_config.fire_hold_takeover,
-            SHOW_DECALS = self.global.game_config.show_decals,
-            ALL_MAP_VISIBLE = self.global.game_config.all_map_visible,
-            VID_SCALE_FACTOR = self.global.game_config.scale,
-            HOG_CPU = self.global.game_config.hog_cpu,
-            EMPTY_LEVEL_SPEEDUP = self.global.game_config.empty_level_speedup,
-        );
+        writeln!(config, "{VERSION_STRING} = {}", env!("CARGO_PKG_VERSION")).unwrap();
+        for name in CONFIG_VARIABLES {
+            let value = self
+                .misc
+                .unsaved_config
+                .iter()
+                .find(|&&(unsaved_name, _)| unsaved_name == name)
+                .map_or_else(
+                    || config_variable(&self.global.game_config, name),
+                    |(_, value)| value.clone(),
+                );
+            writeln!(config, "{name} = {value}").unwrap();
+        }
 
         // now write the keyboard->cmd mappings
         for (cmd_string, key_cmd) in CMD_STRINGS[0..Cmds::Last as usize]
@@ -464,6 +411,9 @@ impl crate::Data<'_> {
         //
 
         if self.global.skip_a_few_frames {
+            if let Some(session) = &mut self.input.replay {
+                session.end_frame(0);
+            }
             return;
         }
 
@@ -481,6 +431,10 @@ impl crate::Data<'_> {
         } else {
             1
         }; // avoid division by zero
+        if let Some(session) = &mut self.input.replay {
+            // a replay must run with the frame times of the recording
+            *one_frame_delay = session.end_frame(*one_frame_delay);
+        }
         #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
         {
             self.main.f_p_sover1 = (1000. / *one_frame_delay as f64) as f32;
@@ -496,7 +450,7 @@ impl crate::Data<'_> {
         self.graphics.banner_is_destroyed = true;
     }
 
-    /// Find a given filename in subdir relative to `FD_DATADIR`,
+    /// Find a given filename in subdir relative to the data directories,
     ///
     /// if you pass NULL as "subdir", it will be ignored
     ///
@@ -517,6 +471,24 @@ impl crate::Data<'_> {
         Self::find_file_static(global, misc, fname, subdir, use_theme)
     }
 
+    /// Like `find_file`, but a missing file is returned as an error instead of
+    /// terminating the game.
+    pub fn find_data_file(
+        &mut self,
+        fname: &[u8],
+        subdir: Option<&CStr>,
+        use_theme: Themed,
+    ) -> Result<PathBuf, LoadError> {
+        let Self { global, misc, .. } = self;
+        let fpath = Self::try_find_file_static(global, misc, fname, subdir, use_theme, true)
+            .ok_or_else(|| LoadError::not_found(fname))?;
+        Ok(PathBuf::from(
+            fpath
+                .to_str()
+                .expect("unable to convert C string to UTF-8 string"),
+        ))
+    }
+
     pub fn find_file_static<'a>(
         global: &Global,
         misc: &'a mut Misc,
@@ -579,7 +551,12 @@ impl crate::Data<'_> {
         use std::fmt::Write;
 
         let fname: &BStr = fname.into();
-        let mut inner = |datadir| {
+        let Misc {
+            file_path,
+            data_dirs,
+            ..
+        } = misc;
+        let mut inner = |datadir: &PathBuf| {
             let theme_dir = if use_theme == Themed::UseTheme {
                 Cow::Owned(format!(
                     "{}_theme/",
@@ -589,24 +566,20 @@ impl crate::Data<'_> {
                 Cow::Borrowed("")
             };
 
-            misc.file_path.clear();
-            write!(misc.file_path, "{datadir}",).unwrap();
+            file_path.clear();
+            write!(file_path, "{}", datadir.display()).unwrap();
             if let Some(subdir) = subdir {
-                write!(misc.file_path, "/{}", subdir.to_string_lossy()).unwrap();
+                write!(file_path, "/{}", subdir.to_string_lossy()).unwrap();
             }
-            write!(misc.file_path, "/{theme_dir}/{fname}").unwrap();
+            write!(file_path, "/{theme_dir}/{fname}").unwrap();
 
-            misc.file_path
+            file_path
                 .to_str()
                 .map(|file_path| Path::new(file_path).exists())
                 .unwrap_or(false)
         };
 
-        let mut found = inner(LOCAL_DATADIR);
-        if !found {
-            found = inner(FD_DATADIR);
-        }
-
+        let found = data_dirs.iter().any(inner);
         (found, fname)
     }
 
@@ -751,51 +724,34 @@ impl crate::Data<'_> {
     /// `LoadGameConfig()`: load saved options from config-file
     ///
     /// this should be the first of all load/save functions called
-    /// as here we read the $HOME-dir and create the config-subdir if neccessary
-    pub fn load_game_config(&mut self) -> i32 {
-        use std::fmt::Write;
-
+    /// as here we find the user directories and create them if neccessary.
+    /// `config_dir` replaces all of them when it is given.
+    pub fn load_game_config(&mut self, config_dir: Option<&Path>) -> i32 {
         // ----------------------------------------------------------------------
         // Game-config maker-strings for config-file:
 
         const VERSION_STRING: &str = "Freedroid Version";
 
-        // first we need the user's homedir for loading/saving stuff
-        let homedir = match env::var("HOME") {
-            Err(_) => {
-                warn!("Environment does not contain HOME variable...using local dir");
-                Cow::Borrowed(Path::new("."))
-            }
-            Ok(homedir) => {
-                info!("found environment HOME = '{}'", homedir);
-                Cow::Owned(homedir.into())
+        let user_dirs = UserDirs::locate(config_dir);
+        match user_dirs.prepare() {
+            Ok(true) => {}
+            Ok(false) => {
+                self.main.config_dir = Some(user_dirs.config);
+                self.main.data_dir = Some(user_dirs.data);
+                return defs::OK.into();
             }
-        };
-
-        let config_dir = homedir.join(".freedroidClassic");
-        self.main.config_dir.clear();
-        write!(self.main.config_dir, "{}", config_dir.display()).unwrap();
-
-        if !config_dir.exists() {
-            warn!(
-                "Couldn't stat Config-dir {}, I'll try to create it...",
-                config_dir.display()
-            );
-            let retval = if fs::create_dir(&config_dir).is_ok() {
-                info!("Successfully created config-dir '{}'", config_dir.display());
-                defs::OK.into()
-            } else {
+            Err(err) => {
                 error!(
-                    "Failed to create config-dir: {}. Giving up...",
-                    config_dir.display()
+                    "Failed to create config-dir: {}: {err}. Giving up...",
+                    user_dirs.config.display()
                 );
-                defs::ERR.into()
-            };
-
-            return retval;
+                return defs::ERR.into();
+            }
         }
 
-        let config_path = config_dir.join("config");
+        let config_path = user_dirs.config.join("config");
+        self.main.config_dir = Some(user_dirs.config);
+        self.main.data_dir = Some(user_dirs.data);
         let Ok(data) = fs::read(&config_path) else {
             error!("failed to open config-file: {}", config_path.display());
             return defs::ERR.into();
@@ -825,75 +781,190 @@ impl crate::Data<'_> {
     }
 
     fn parse_game_config_variables(&mut self, data: &[u8]) {
-        const DRAW_FRAMERATE: &str = "Draw_Framerate";
-        const DRAW_ENERGY: &str = "Draw_Energy";
-        const DRAW_POSITION: &str = "Draw_Position";
-        const DRAW_DEATHCOUNT: &str = "Draw_DeathCount";
-        const DROID_TALK: &str = "Droid_Talk";
-        const WANTED_TEXT_VISIBLE_TIME: &str = "WantedTextVisibleTime";
-        const CURRENT_BG_MUSIC_VOLUME: &str = "Current_BG_Music_Volume";
-        const CURRENT_SOUND_FX_VOLUME: &str = "Current_Sound_FX_Volume";
-        const CURRENT_GAMMA_CORRECTION: &str = "Current_Gamma_Correction";
-        const THEME_NAME: &str = "Theme_Name";
-        const FULL_USER_RECT: &str = "FullUserRect";
-        const USE_FULLSCREEN: &str = "UseFullscreen";
-        const TAKEOVER_ACTIVATES: &str = "TakeoverActivates";
-        const FIRE_HOLD_TAKEOVER: &str = "FireHoldTakeover";
-        const SHOW_DECALS: &str = "ShowDecals";
-        const ALL_MAP_VISIBLE: &str = "AllMapVisible";
-        const VID_SCALE_FACTOR: &str = "Vid_ScaleFactor";
-        const HOG_CPU: &str = "Hog_Cpu";
-        const EMPTY_LEVEL_SPEEDUP: &str = "EmptyLevelSpeedup";
-
-        macro_rules! parse_variable {
-            ($(
-                $($var:ident).+ = $name:expr;
-            )+) => {
-                $(
-                    {
-                        let value = read_variable(&data, $name)
-                            .and_then(|slice| std::str::from_utf8(slice).ok())
-                            .and_then(|value| value.parse().ok());
-                        if let Some(value) = value {
-                            $($var).+ = value;
-                        }
-                    }
-                )+
+        for name in CONFIG_VARIABLES {
+            let Some(value) = read_variable(data, name) else {
+                continue;
             };
+            if let Err(err) = set_config_variable(&mut self.global.game_config, name, value) {
+                warn!("Ignoring config variable {name}: {err}");
+            }
         }
+    }
 
-        parse_variable! {
-            self.global.game_config.draw_framerate = DRAW_FRAMERATE;
-            self.global.game_config.draw_energy = DRAW_ENERGY;
-            self.global.game_config.draw_position = DRAW_POSITION;
-            self.global.game_config.draw_death_count = DRAW_DEATHCOUNT;
-            self.global.game_config.droid_talk = DROID_TALK;
-            self.global.game_config.wanted_text_visible_time = WANTED_TEXT_VISIBLE_TIME;
-            self.global.game_config.current_bg_music_volume = CURRENT_BG_MUSIC_VOLUME;
-            self.global.game_config.current_sound_fx_volume = CURRENT_SOUND_FX_VOLUME;
-            self.global.game_config.current_gamma_correction = CURRENT_GAMMA_CORRECTION;
-        };
+    /// Sets a variable of the config file from the command line.
+    ///
+    /// The case and the underscores of `key` do not matter.  If `save` is
+    /// false, the value the variable had before is the one written back by
+    /// `save_game_config`, unless the player changes the variable in the menu.
+    pub fn set_game_config_variable(
+        &mut self,
+        key: &str,
+        value: &str,
+        save: bool,
+    ) -> Result<(), String> {
+        let name = find_config_variable(key).ok_or_else(|| {
+            format!(
+                "unknown config variable, the known ones are {}",
+                CONFIG_VARIABLES.join(", ")
+            )
+        })?;
+
+        let previous_value = config_variable(&self.global.game_config, name);
+        set_config_variable(&mut self.global.game_config, name, value.as_bytes())?;
+        if save.not()
+            && self
+                .misc
+                .unsaved_config
+                .iter()
+                .all(|&(unsaved_name, _)| unsaved_name != name)
         {
-            let value = read_variable(data, THEME_NAME);
-            if let Some(value) = value {
-                self.global.game_config.theme_name.set_slice(value);
-            }
+            self.misc.unsaved_config.push((name, previous_value));
+        }
+        info!("Config variable {name} set to {value}");
+        Ok(())
+    }
+
+    /// Lets the player change the config with `change`.  The variables set
+    /// from the command line not to be saved are saved again once the player
+    /// changes them.
+    pub fn change_game_config(&mut self, change: impl FnOnce(&mut Self)) {
+        let before = self.global.game_config.clone();
+        change(self);
+        save_changed_config_variables(
+            &mut self.misc.unsaved_config,
+            &before,
+            &self.global.game_config,
+        );
+    }
+}
+
+/// Removes the variables whose values differ between `before` and `after`
+/// from the `unsaved_config`.
+fn save_changed_config_variables(
+    unsaved_config: &mut Vec<(&'static str, String)>,
+    before: &Config,
+    after: &Config,
+) {
+    unsaved_config
+        .retain(|&(name, _)| config_variable(before, name) == config_variable(after, name));
+}
+
+/// Finds the name of a variable of the config file, ignoring the case and
+/// the underscores.
+fn find_config_variable(key: &str) -> Option<&'static str> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|&c| c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>()
+    };
+
+    let key = normalize(key);
+    CONFIG_VARIABLES
+        .into_iter()
+        .find(|name| normalize(name) == key)
+}
+
+/// Sets the variable `name` of the config file, which must be one of
+/// [`CONFIG_VARIABLES`].
+///
+/// On failure the reason is returned.
+fn set_config_variable(config: &mut Config, name: &str, value: &[u8]) -> Result<(), String> {
+    fn parse<T: FromStr>(value: &[u8]) -> Result<T, String> {
+        std::str::from_utf8(value)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| {
+                format!(
+                    "invalid value \"{}\", a {} is expected",
+                    value.to_str_lossy(),
+                    type_name::<T>()
+                )
+            })
+    }
+
+    fn parse_checked(
+        value: &[u8],
+        is_valid: impl Fn(f32) -> bool,
+        expected: &str,
+    ) -> Result<f32, String> {
+        let value = parse(value)?;
+        if is_valid(value) {
+            Ok(value)
+        } else {
+            Err(format!("invalid value {value}, it must be {expected}"))
         }
-        parse_variable! {
-            self.global.game_config.full_user_rect = FULL_USER_RECT;
-            self.global.game_config.use_fullscreen = USE_FULLSCREEN;
-            self.global.game_config.takeover_activates = TAKEOVER_ACTIVATES;
-            self.global.game_config.fire_hold_takeover = FIRE_HOLD_TAKEOVER;
-            self.global.game_config.show_decals = SHOW_DECALS;
-            self.global.game_config.all_map_visible = ALL_MAP_VISIBLE;
-            self.global.game_config.scale = VID_SCALE_FACTOR;
-            self.global.game_config.hog_cpu = HOG_CPU;
-            self.global.game_config.empty_level_speedup = EMPTY_LEVEL_SPEEDUP;
-        };
+    }
+    let is_positive = |value: f32| value > 0.;
+    let is_volume = |value: f32| (0. ..=1.).contains(&value);
+
+    match name {
+        DRAW_FRAMERATE => config.draw_framerate = parse(value)?,
+        DRAW_ENERGY => config.draw_energy = parse(value)?,
+        DRAW_POSITION => config.draw_position = parse(value)?,
+        DRAW_DEATHCOUNT => config.draw_death_count = parse(value)?,
+        DROID_TALK => config.droid_talk = parse(value)?,
+        WANTED_TEXT_VISIBLE_TIME => {
+            config.wanted_text_visible_time =
+                parse_checked(value, |value| value >= 0., "at least 0")?;
+        }
+        CURRENT_BG_MUSIC_VOLUME => {
+            config.current_bg_music_volume = parse_checked(value, is_volume, "between 0 and 1")?;
+        }
+        CURRENT_SOUND_FX_VOLUME => {
+            config.current_sound_fx_volume = parse_checked(value, is_volume, "between 0 and 1")?;
+        }
+        CURRENT_GAMMA_CORRECTION => {
+            config.current_gamma_correction = parse_checked(value, is_positive, "above 0")?;
+        }
+        THEME_NAME => config
+            .theme_name
+            .try_set_slice(value)
+            .map_err(|_| "the theme name is too long".to_owned())?,
+        FULL_USER_RECT => config.full_user_rect = parse(value)?,
+        USE_FULLSCREEN => config.use_fullscreen = parse(value)?,
+        TAKEOVER_ACTIVATES => config.takeover_activates = parse(value)?,
+        FIRE_HOLD_TAKEOVER => config.fire_hold_takeover = parse(value)?,
+        SHOW_DECALS => config.show_decals = parse(value)?,
+        ALL_MAP_VISIBLE => config.all_map_visible = parse(value)?,
+        VID_SCALE_FACTOR => config.scale = parse_checked(value, is_positive, "above 0")?,
+        HOG_CPU => config.hog_cpu = parse(value)?,
+        EMPTY_LEVEL_SPEEDUP => {
+            config.empty_level_speedup = parse_checked(value, is_positive, "above 0")?;
+        }
+        _ => return Err(format!("unknown config variable {name}")),
+    }
+    Ok(())
+}
+
+/// The value of the variable `name` of the config file, as it is written
+/// there.
+fn config_variable(config: &Config, name: &str) -> String {
+    match name {
+        DRAW_FRAMERATE => config.draw_framerate.to_string(),
+        DRAW_ENERGY => config.draw_energy.to_string(),
+        DRAW_POSITION => config.draw_position.to_string(),
+        DRAW_DEATHCOUNT => config.draw_death_count.to_string(),
+        DROID_TALK => config.droid_talk.to_string(),
+        WANTED_TEXT_VISIBLE_TIME => config.wanted_text_visible_time.to_string(),
+        CURRENT_BG_MUSIC_VOLUME => config.current_bg_music_volume.to_string(),
+        CURRENT_SOUND_FX_VOLUME => config.current_sound_fx_volume.to_string(),
+        CURRENT_GAMMA_CORRECTION => config.current_gamma_correction.to_string(),
+        THEME_NAME => config.theme_name.to_str().unwrap().to_owned(),
+        FULL_USER_RECT => config.full_user_rect.to_string(),
+        USE_FULLSCREEN => config.use_fullscreen.to_string(),
+        TAKEOVER_ACTIVATES => config.takeover_activates.to_string(),
+        FIRE_HOLD_TAKEOVER => config.fire_hold_takeover.to_string(),
+        SHOW_DECALS => config.show_decals.to_string(),
+        ALL_MAP_VISIBLE => config.all_map_visible.to_string(),
+        VID_SCALE_FACTOR => config.scale.to_string(),
+        HOG_CPU => config.hog_cpu.to_string(),
+        EMPTY_LEVEL_SPEEDUP => config.empty_level_speedup.to_string(),
+        _ => unreachable!("unknown config variable {name}"),
     }
 }
 
-fn read_variable<'a>(data: &'a [u8], var_name: &str) -> Option<&'a [u8]> {
+pub fn read_variable<'a>(data: &'a [u8], var_name: &str) -> Option<&'a [u8]> {
     data.lines()
         .filter_map(|line| line.trim_start().strip_prefix(var_name.as_bytes()))
         .filter_map(|line| line.trim_start().strip_prefix(b"="))
@@ -914,3 +985,76 @@ impl Misc {
         self.previous_time * self.current_time_factor
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_variables_ignore_case_and_underscores() {
+        assert_eq!(find_config_variable("Draw_Framerate"), Some(DRAW_FRAMERATE));
+        assert_eq!(find_config_variable("drawframerate"), Some(DRAW_FRAMERATE));
+        assert_eq!(
+            find_config_variable("VID_SCALE_FACTOR"),
+            Some(VID_SCALE_FACTOR)
+        );
+        assert_eq!(
+            find_config_variable("empty_level_speedup"),
+            Some(EMPTY_LEVEL_SPEEDUP)
+        );
+        assert_eq!(find_config_variable("Framerate"), None);
+        assert_eq!(find_config_variable("Draw_Framerate2"), None);
+        assert_eq!(find_config_variable(""), None);
+    }
+
+    #[test]
+    fn changed_config_variables_are_saved() {
+        let before = Global::default().game_config;
+        let mut after = before.clone();
+        after.draw_framerate = after.draw_framerate.not();
+        let mut unsaved_config = vec![
+            (DRAW_FRAMERATE, "true".to_owned()),
+            (VID_SCALE_FACTOR, "2".to_owned()),
+        ];
+
+        save_changed_config_variables(&mut unsaved_config, &before, &after);
+        assert_eq!(unsaved_config, [(VID_SCALE_FACTOR, "2".to_owned())]);
+    }
+
+    #[test]
+    fn config_values_are_checked() {
+        let mut config = Global::default().game_config;
+        set_config_variable(&mut config, CURRENT_BG_MUSIC_VOLUME, b"0.5").unwrap();
+        set_config_variable(&mut config, VID_SCALE_FACTOR, b"2").unwrap();
+        assert_eq!(config_variable(&config, CURRENT_BG_MUSIC_VOLUME), "0.5");
+        assert_eq!(config_variable(&config, VID_SCALE_FACTOR), "2");
+
+        let unchanged = config.clone();
+        for (name, value) in [
+            (CURRENT_BG_MUSIC_VOLUME, "1.5"),
+            (CURRENT_SOUND_FX_VOLUME, "-0.1"),
+            (VID_SCALE_FACTOR, "0"),
+            (VID_SCALE_FACTOR, "-2"),
+            (CURRENT_GAMMA_CORRECTION, "0"),
+            (DRAW_FRAMERATE, "maybe"),
+        ] {
+            assert!(
+                set_config_variable(&mut config, name, value.as_bytes()).is_err(),
+                "{name} = {value}"
+            );
+        }
+        assert_eq!(config, unchanged);
+
+        assert_eq!(
+            set_config_variable(&mut config, "Unknown", b"1"),
+            Err("unknown config variable Unknown".to_owned())
+        );
+
+        assert_eq!(
+            set_config_variable(&mut config, THEME_NAME, "x".repeat(200).as_bytes()),
+            Err("the theme name is too long".to_owned())
+        );
+        set_config_variable(&mut config, THEME_NAME, b"lanzz").unwrap();
+        assert_eq!(config_variable(&config, THEME_NAME), "lanzz");
+    }
+}
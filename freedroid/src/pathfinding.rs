@@ -0,0 +1,167 @@
+//! Path-finding over the waypoint graph of a level.
+//!
+//! The droids walk along the connections of the waypoints.  The shortest
+//! ways between all waypoints of a level are found with Dijkstra's
+//! algorithm when they are first needed, and kept until the waypoints of the
+//! level change.
+
+use crate::structs::{Finepoint, Waypoint};
+
+use std::ops::Not;
+
+/// The shortest ways between all waypoints of a level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceTable {
+    /// `next[from][to]` is the first waypoint on the shortest way from `from`
+    /// to `to`, `None` if there is no such way.
+    next: Vec<Vec<Option<u8>>>,
+}
+
+impl DistanceTable {
+    pub fn new(waypoints: &[Waypoint]) -> Self {
+        let count = waypoints.len();
+        let length = |from: usize, to: usize| {
+            let (from, to) = (&waypoints[from], &waypoints[to]);
+            (f32::from(from.x) - f32::from(to.x)).hypot(f32::from(from.y) - f32::from(to.y))
+        };
+
+        let next = (0..count)
+            .map(|start| {
+                let mut distance: Vec<Option<f32>> = vec![None; count];
+                let mut first: Vec<Option<u8>> = vec![None; count];
+                let mut done = vec![false; count];
+                distance[start] = Some(0.);
+
+                // the closest waypoint whose way is not known for sure yet
+                while let Some((current, current_distance)) = (0..count)
+                    .filter(|&index| done[index].not())
+                    .filter_map(|index| distance[index].map(|distance| (index, distance)))
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                {
+                    done[current] = true;
+                    for &connection in &waypoints[current].connections {
+                        let to = usize::from(connection);
+                        if to >= count || done[to] {
+                            continue;
+                        }
+
+                        let via = current_distance + length(current, to);
+                        if distance[to].is_none_or(|distance| via < distance) {
+                            distance[to] = Some(via);
+                            first[to] = if current == start {
+                                Some(connection)
+                            } else {
+                                first[current]
+                            };
+                        }
+                    }
+                }
+                first
+            })
+            .collect();
+
+        Self { next }
+    }
+
+    /// The waypoint to walk to from `from` on the shortest way to `to`.
+    /// `None` if `from` is `to` or `to` cannot be reached.
+    pub fn next_step(&self, from: u8, to: u8) -> Option<u8> {
+        *self.next.get(usize::from(from))?.get(usize::from(to))?
+    }
+}
+
+/// The distance tables of the levels of the ship.
+#[derive(Debug, Default)]
+pub struct Paths {
+    /// For every level index the waypoints a table was found for, and the
+    /// table.
+    tables: Vec<Option<(Vec<Waypoint>, DistanceTable)>>,
+}
+
+impl Paths {
+    /// The distance table of the level at `level_index`, which has the
+    /// `waypoints`.  It is found again if the waypoints have changed since.
+    pub fn table(&mut self, level_index: usize, waypoints: &[Waypoint]) -> &DistanceTable {
+        if self.tables.len() <= level_index {
+            self.tables.resize_with(level_index + 1, || None);
+        }
+
+        let cached = &mut self.tables[level_index];
+        if cached
+            .as_ref()
+            .is_none_or(|(found_for, _)| found_for != waypoints)
+        {
+            *cached = Some((waypoints.to_vec(), DistanceTable::new(waypoints)));
+        }
+        &cached.as_ref().unwrap().1
+    }
+}
+
+/// The waypoint closest to `pos`, as the crow flies.
+pub fn nearest_waypoint(waypoints: &[Waypoint], pos: Finepoint) -> Option<u8> {
+    (0..=u8::MAX)
+        .zip(waypoints)
+        .map(|(index, waypoint)| {
+            let distance =
+                (f32::from(waypoint.x) - pos.x).powi(2) + (f32::from(waypoint.y) - pos.y).powi(2);
+            (index, distance)
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::waypoint;
+
+    fn way(table: &DistanceTable, mut from: u8, to: u8) -> Vec<u8> {
+        let mut way = vec![from];
+        while let Some(next) = table.next_step(from, to) {
+            way.push(next);
+            from = next;
+        }
+        way
+    }
+
+    #[test]
+    fn shortest_way() {
+        // a ring with a detour over 4, and 5 only reachable one way
+        let waypoints = [
+            waypoint(0, 0, &[1, 3, 4]),
+            waypoint(2, 0, &[0, 2]),
+            waypoint(2, 2, &[1, 3, 5]),
+            waypoint(0, 3, &[0, 2]),
+            waypoint(9, 9, &[0, 2]),
+            waypoint(3, 2, &[]),
+        ];
+        let table = DistanceTable::new(&waypoints);
+        assert_eq!(way(&table, 0, 2), [0, 1, 2]);
+        assert_eq!(way(&table, 3, 1), [3, 2, 1]);
+        assert_eq!(way(&table, 1, 5), [1, 2, 5]);
+        assert_eq!(table.next_step(5, 0), None);
+        assert_eq!(table.next_step(2, 2), None);
+        assert_eq!(table.next_step(0, 9), None);
+    }
+
+    #[test]
+    fn tables_follow_changes() {
+        let mut waypoints = vec![waypoint(0, 0, &[1]), waypoint(1, 0, &[0])];
+        let mut paths = Paths::default();
+        assert_eq!(paths.table(2, &waypoints).next_step(0, 1), Some(1));
+
+        waypoints[0].connections.clear();
+        assert_eq!(paths.table(2, &waypoints).next_step(0, 1), None);
+        assert_eq!(paths.table(0, &[]).next_step(0, 1), None);
+    }
+
+    #[test]
+    fn nearest() {
+        let waypoints = [waypoint(0, 0, &[]), waypoint(4, 1, &[])];
+        assert_eq!(
+            nearest_waypoint(&waypoints, Finepoint { x: 3., y: 0.2 }),
+            Some(1)
+        );
+        assert_eq!(nearest_waypoint(&[], Finepoint { x: 3., y: 0.2 }), None);
+    }
+}
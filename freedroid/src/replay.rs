@@ -0,0 +1,529 @@
+use crate::{
+    defs::{Cmds, PointerStates},
+    find_subslice,
+    input::InputEvent,
+    misc::read_variable,
+    savegame::{parse_field, parse_value},
+    structs::Config,
+};
+
+use bstr::ByteSlice;
+use log::{error, info, warn};
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    mem,
+    path::{Path, PathBuf},
+};
+
+const RECORDING_VERSION: u32 = 1;
+
+const VERSION_STRING: &str = "Freedroid Input Recording Version";
+const SEED_STRING: &str = "Seed";
+const DROID_TALK_STRING: &str = "Droid talk";
+const TAKEOVER_ACTIVATES_STRING: &str = "Takeover activates";
+const FIRE_HOLD_TAKEOVER_STRING: &str = "Fire hold takeover";
+const EMPTY_LEVEL_SPEEDUP_STRING: &str = "Empty level speedup";
+const KEY_COMMANDS_STRING: &str = "Key commands";
+
+const FRAME_STRING: &str = "Frame:";
+const EVENT_STRING: &str = "Event:";
+
+const START_OF_FRAMES_STRING: &[u8] = b"*** Beginning of Frames ***";
+const END_OF_RECORDING_STRING: &[u8] = b"*** End of Input Recording ***";
+
+type KeyCmds = [[u16; 3]; Cmds::Last as usize];
+
+/// Everything besides the input that has to be the same for a replay to give
+/// the same game as the recording.
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    seed: u64,
+    droid_talk: bool,
+    takeover_activates: bool,
+    fire_hold_takeover: bool,
+    empty_level_speedup: f32,
+    key_cmds: KeyCmds,
+}
+
+/// The input of a single frame.
+///
+/// Every event is stored together with the number of the input poll within
+/// the frame in which it has been received.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Frame {
+    delay: i64,
+    events: Vec<(u32, InputEvent)>,
+}
+
+#[derive(Debug)]
+pub struct Recorder {
+    path: PathBuf,
+    header: Header,
+    frames: Vec<Frame>,
+    current: Frame,
+    poll: u32,
+    active: bool,
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    frames: Vec<Frame>,
+    frame_index: usize,
+    poll: u32,
+    active: bool,
+}
+
+/// An input recording or replay.
+///
+/// Both only cover the frames of the game itself: the session is started when
+/// the game loop begins and paused when it ends.  Outside of that, and once a
+/// replay runs out of frames, the input comes from the devices as usual.
+///
+/// All the games played in one run end up in the same recording, one after
+/// the other.  The menus in between are not recorded, so a replay of more
+/// than one game needs the same choices there as the recording.
+///
+/// Screens that wait for a fixed amount of wall-clock time while polling the
+/// input (for example the takeover countdown) can make a replay diverge.
+#[derive(Debug)]
+pub enum Session {
+    Record(Recorder),
+    Replay(Replay),
+}
+
+impl Session {
+    fn start(&mut self) {
+        match self {
+            Self::Record(recorder) => recorder.active = true,
+            Self::Replay(replay) => replay.active = true,
+        }
+    }
+
+    /// Remembers an event that has been read from the devices.
+    pub fn record(&mut self, event: InputEvent) {
+        if let Self::Record(Recorder {
+            active: true,
+            current,
+            poll,
+            ..
+        }) = self
+        {
+            current.events.push((*poll, event));
+        }
+    }
+
+    /// Marks the end of an input poll.
+    pub fn end_poll(&mut self) {
+        if let Self::Record(Recorder {
+            active: true, poll, ..
+        }) = self
+        {
+            *poll += 1;
+        }
+    }
+
+    /// The recorded events for the current input poll, or `None` if the
+    /// input has to be read from the devices.
+    pub fn replayed_events(&mut self) -> Option<Vec<InputEvent>> {
+        let Self::Replay(replay) = self else {
+            return None;
+        };
+        let frame = replay.current_frame()?;
+
+        let poll = replay.poll;
+        let events = frame
+            .events
+            .iter()
+            .filter(|&&(event_poll, _)| event_poll == poll)
+            .map(|&(_, event)| event)
+            .collect();
+        replay.poll += 1;
+        Some(events)
+    }
+
+    /// Marks the end of a frame.
+    ///
+    /// `delay` is the measured duration of the frame in ms, the returned value
+    /// is the duration that should be used by the game.
+    pub fn end_frame(&mut self, delay: i64) -> i64 {
+        match self {
+            Self::Record(recorder) => {
+                if recorder.active {
+                    recorder.current.delay = delay;
+                    recorder.frames.push(mem::take(&mut recorder.current));
+                    recorder.poll = 0;
+                }
+                delay
+            }
+
+            Self::Replay(replay) => {
+                let Some(recorded_delay) = replay.current_frame().map(|frame| frame.delay) else {
+                    return delay;
+                };
+
+                replay.frame_index += 1;
+                replay.poll = 0;
+                if replay.frame_index == replay.frames.len() {
+                    info!("Replay finished, the input is read from the devices again.");
+                }
+                recorded_delay
+            }
+        }
+    }
+}
+
+impl Replay {
+    fn current_frame(&self) -> Option<&Frame> {
+        if self.active {
+            self.frames.get(self.frame_index)
+        } else {
+            None
+        }
+    }
+}
+
+impl crate::Data<'_> {
+    /// Prepares the recording of the input into the file at `path`.
+    pub fn start_recording(&mut self, path: &Path, seed: u64) {
+        let header = Header::new(seed, &self.global.game_config, &self.input.key_cmds);
+        self.input.replay = Some(Session::Record(Recorder {
+            path: path.to_owned(),
+            header,
+            frames: Vec::new(),
+            current: Frame::default(),
+            poll: 0,
+            active: false,
+        }));
+        info!("Input will be recorded to '{}'", path.display());
+    }
+
+    /// Prepares the replay of the input recorded in the file at `path`.
+    ///
+    /// The settings stored in the recording are applied and its random seed
+    /// is returned, which must be used for the game.
+    pub fn start_replay(&mut self, path: &Path) -> Option<u64> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(err) => {
+                error!(
+                    "Unable to read input recording '{}': {}",
+                    path.display(),
+                    err
+                );
+                return None;
+            }
+        };
+
+        let Some((header, frames)) = parse_recording(&data) else {
+            error!(
+                "Input recording '{}' is corrupt or outdated",
+                path.display()
+            );
+            return None;
+        };
+
+        let config = &mut self.global.game_config;
+        config.droid_talk = header.droid_talk;
+        config.takeover_activates = header.takeover_activates;
+        config.fire_hold_takeover = header.fire_hold_takeover;
+        config.empty_level_speedup = header.empty_level_speedup;
+        self.input.key_cmds = header.key_cmds;
+
+        info!(
+            "Replaying {} frames of input from '{}'",
+            frames.len(),
+            path.display()
+        );
+        self.input.replay = Some(Session::Replay(Replay {
+            frames,
+            frame_index: 0,
+            poll: 0,
+            active: false,
+        }));
+        Some(header.seed)
+    }
+
+    /// Starts the recording or replay, if any, with the next frame.
+    pub fn start_input_session(&mut self) {
+        if let Some(session) = &mut self.input.replay {
+            session.start();
+        }
+    }
+
+    /// Pauses the recording or replay, if any, at the end of a game.  A
+    /// recording is written to disk with all the games recorded so far.
+    pub fn finish_input_session(&mut self) {
+        let Some(session) = &mut self.input.replay else {
+            return;
+        };
+
+        match session {
+            Session::Record(recorder) => {
+                recorder.active = false;
+                recorder.current = Frame::default();
+                recorder.poll = 0;
+                match write_recording(recorder) {
+                    Ok(()) => info!(
+                        "{} frames of input recorded to '{}'",
+                        recorder.frames.len(),
+                        recorder.path.display()
+                    ),
+                    Err(err) => error!(
+                        "Unable to write input recording '{}': {}",
+                        recorder.path.display(),
+                        err
+                    ),
+                }
+            }
+            Session::Replay(replay) => {
+                replay.active = false;
+                replay.poll = 0;
+                if replay.frame_index < replay.frames.len() {
+                    info!(
+                        "Game ended after {} of {} replayed frames, the next game goes on with \
+                         the rest.",
+                        replay.frame_index,
+                        replay.frames.len()
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Header {
+    fn new(seed: u64, config: &Config, key_cmds: &KeyCmds) -> Self {
+        Self {
+            seed,
+            droid_talk: config.droid_talk,
+            takeover_activates: config.takeover_activates,
+            fire_hold_takeover: config.fire_hold_takeover,
+            empty_level_speedup: config.empty_level_speedup,
+            key_cmds: *key_cmds,
+        }
+    }
+}
+
+fn write_recording(recorder: &Recorder) -> Result<(), io::Error> {
+    let mut file = BufWriter::new(fs::File::create(&recorder.path)?);
+    let header = &recorder.header;
+
+    writeln!(file, "{VERSION_STRING} = {RECORDING_VERSION}")?;
+    writeln!(file, "{SEED_STRING} = {}", header.seed)?;
+    writeln!(file, "{DROID_TALK_STRING} = {}", header.droid_talk)?;
+    writeln!(
+        file,
+        "{TAKEOVER_ACTIVATES_STRING} = {}",
+        header.takeover_activates
+    )?;
+    writeln!(
+        file,
+        "{FIRE_HOLD_TAKEOVER_STRING} = {}",
+        header.fire_hold_takeover
+    )?;
+    writeln!(
+        file,
+        "{EMPTY_LEVEL_SPEEDUP_STRING} = {}",
+        header.empty_level_speedup
+    )?;
+    write!(file, "{KEY_COMMANDS_STRING} =")?;
+    for key in header.key_cmds.iter().flatten() {
+        write!(file, " {key}")?;
+    }
+    writeln!(file)?;
+
+    file.write_all(START_OF_FRAMES_STRING)?;
+    writeln!(file)?;
+    for frame in &recorder.frames {
+        writeln!(file, "{FRAME_STRING} Delay={}", frame.delay)?;
+        for &(poll, event) in &frame.events {
+            write!(file, "{EVENT_STRING} Poll={poll} ")?;
+            write_event(&mut file, event)?;
+        }
+    }
+    file.write_all(END_OF_RECORDING_STRING)?;
+    writeln!(file)?;
+    file.flush()
+}
+
+fn write_event(file: &mut impl Write, event: InputEvent) -> Result<(), io::Error> {
+    match event {
+        InputEvent::Quit => writeln!(file, "Type=Quit"),
+        InputEvent::Keyboard {
+            key,
+            modifiers,
+            pressed,
+        } => writeln!(
+            file,
+            "Type=Key Code={key} Mod={modifiers} Pressed={pressed}"
+        ),
+        InputEvent::JoyAxis { axis, value } => {
+            writeln!(file, "Type=JoyAxis Code={axis} Value={value}")
+        }
+        InputEvent::JoyButton { button, pressed } => {
+            writeln!(file, "Type=JoyButton Code={button} Pressed={pressed}")
+        }
+        InputEvent::MouseMotion { x, y } => writeln!(file, "Type=MouseMotion X={x} Y={y}"),
+        InputEvent::MouseButton { button, pressed } => {
+            writeln!(file, "Type=MouseButton Code={button} Pressed={pressed}")
+        }
+    }
+}
+
+fn parse_recording(data: &[u8]) -> Option<(Header, Vec<Frame>)> {
+    let end = find_subslice(data, END_OF_RECORDING_STRING)?;
+    let frames_start = find_subslice(data, START_OF_FRAMES_STRING)?;
+    let header_data = &data[..frames_start];
+
+    let version: u32 = parse_value(header_data, VERSION_STRING)?;
+    if version != RECORDING_VERSION {
+        warn!(
+            "Input recording version {} is not supported, expected version {}",
+            version, RECORDING_VERSION
+        );
+        return None;
+    }
+
+    let mut keys = std::str::from_utf8(read_variable(header_data, KEY_COMMANDS_STRING)?)
+        .ok()?
+        .split_whitespace()
+        .map(str::parse);
+    let mut key_cmds: KeyCmds = [[0; 3]; Cmds::Last as usize];
+    for key in key_cmds.iter_mut().flatten() {
+        *key = keys.next()?.ok().filter(|&key| is_key(key))?;
+    }
+    let header = Header {
+        seed: parse_value(header_data, SEED_STRING)?,
+        droid_talk: parse_value(header_data, DROID_TALK_STRING)?,
+        takeover_activates: parse_value(header_data, TAKEOVER_ACTIVATES_STRING)?,
+        fire_hold_takeover: parse_value(header_data, FIRE_HOLD_TAKEOVER_STRING)?,
+        empty_level_speedup: parse_value(header_data, EMPTY_LEVEL_SPEEDUP_STRING)?,
+        key_cmds,
+    };
+
+    let mut frames = Vec::new();
+    for line in data[frames_start..end].lines() {
+        if let Some(line) = line.strip_prefix(FRAME_STRING.as_bytes()) {
+            frames.push(Frame {
+                delay: parse_field(line, "Delay")?,
+                events: Vec::new(),
+            });
+        } else if let Some(line) = line.strip_prefix(EVENT_STRING.as_bytes()) {
+            let poll = parse_field(line, "Poll")?;
+            let event = parse_event(line)?;
+            frames.last_mut()?.events.push((poll, event));
+        }
+    }
+
+    Some((header, frames))
+}
+
+/// Whether `key` has an input state, a recording from elsewhere may have
+/// anything.
+fn is_key(key: u16) -> bool {
+    usize::from(key) < PointerStates::Last.to_usize()
+}
+
+fn parse_event(line: &[u8]) -> Option<InputEvent> {
+    let ty: String = parse_field(line, "Type")?;
+    let event = match ty.as_str() {
+        "Quit" => InputEvent::Quit,
+        "Key" => InputEvent::Keyboard {
+            key: parse_field(line, "Code").filter(|&key| is_key(key))?,
+            modifiers: parse_field(line, "Mod")?,
+            pressed: parse_field(line, "Pressed")?,
+        },
+        "JoyAxis" => InputEvent::JoyAxis {
+            axis: parse_field(line, "Code")?,
+            value: parse_field(line, "Value")?,
+        },
+        "JoyButton" => InputEvent::JoyButton {
+            button: parse_field(line, "Code")?,
+            pressed: parse_field(line, "Pressed")?,
+        },
+        "MouseMotion" => InputEvent::MouseMotion {
+            x: parse_field(line, "X")?,
+            y: parse_field(line, "Y")?,
+        },
+        "MouseButton" => InputEvent::MouseButton {
+            button: parse_field(line, "Code")?,
+            pressed: parse_field(line, "Pressed")?,
+        },
+        _ => return None,
+    };
+
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::ScratchDir;
+    use std::array;
+
+    #[test]
+    fn recording_roundtrip() {
+        let header = Header {
+            seed: 42,
+            droid_talk: true,
+            takeover_activates: false,
+            fire_hold_takeover: true,
+            empty_level_speedup: 1.5,
+            key_cmds: array::from_fn(|cmd| {
+                array::from_fn(|index| u16::try_from(cmd * 3 + index).unwrap())
+            }),
+        };
+        let frames = vec![
+            Frame {
+                delay: 16,
+                events: vec![
+                    (
+                        0,
+                        InputEvent::Keyboard {
+                            key: 32,
+                            modifiers: 1,
+                            pressed: true,
+                        },
+                    ),
+                    (2, InputEvent::MouseMotion { x: 100, y: 200 }),
+                ],
+            },
+            Frame {
+                delay: 17,
+                events: vec![(
+                    0,
+                    InputEvent::JoyAxis {
+                        axis: 1,
+                        value: -3000,
+                    },
+                )],
+            },
+        ];
+
+        let dir = ScratchDir::new("recording");
+        let path = dir.join("roundtrip.rec");
+        let recorder = Recorder {
+            path: path.clone(),
+            header: header.clone(),
+            frames: frames.clone(),
+            current: Frame::default(),
+            poll: 0,
+            active: false,
+        };
+        write_recording(&recorder).unwrap();
+        let data = fs::read(&path).unwrap();
+
+        assert_eq!(parse_recording(&data), Some((header, frames)));
+    }
+
+    #[test]
+    fn out_of_range_keys() {
+        let key = |code: usize| format!(" Poll=0 Type=Key Code={code} Mod=0 Pressed=true");
+        let last = PointerStates::Last.to_usize();
+        assert!(parse_event(key(last - 1).as_bytes()).is_some());
+        assert_eq!(parse_event(key(last).as_bytes()), None);
+        assert_eq!(parse_event(key(usize::from(u16::MAX)).as_bytes()), None);
+    }
+}
@@ -0,0 +1,500 @@
+use crate::{
+    defs::{self, Droid, MAX_LEVELS, MAXBULLETS, Status},
+    error::LoadError,
+    find_subslice,
+    map::{levels_from_data, struct_to_mem, write_file_atomically},
+    misc::read_variable,
+    structs::{Enemy, Finepoint, Level, TextToBeDisplayed},
+};
+
+use arrayvec::ArrayVec;
+use bstr::ByteSlice;
+use log::{error, info, warn};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+
+const SAVEGAME_FILE: &str = "savegame";
+const SAVEGAME_VERSION: u32 = 2;
+
+const VERSION_STRING: &str = "Freedroid Savegame Version";
+const MISSION_STRING: &str = "Mission";
+const AREA_NAME_STRING: &str = "Area name";
+const CUR_LEVEL_INDEX_STRING: &str = "Current level index";
+const REAL_SCORE_STRING: &str = "Score";
+const SHOW_SCORE_STRING: &str = "Shown score";
+const DEATH_COUNT_STRING: &str = "Death count";
+const INFLU_TYPE_STRING: &str = "Influencer type";
+const INFLU_POS_X_STRING: &str = "Influencer PosX";
+const INFLU_POS_Y_STRING: &str = "Influencer PosY";
+const INFLU_SPEED_X_STRING: &str = "Influencer SpeedX";
+const INFLU_SPEED_Y_STRING: &str = "Influencer SpeedY";
+const INFLU_HEALTH_STRING: &str = "Influencer health";
+const INFLU_ENERGY_STRING: &str = "Influencer energy";
+const INFLU_FIREWAIT_STRING: &str = "Influencer firewait";
+const INFLU_PHASE_STRING: &str = "Influencer phase";
+const INFLU_TIMER_STRING: &str = "Influencer timer";
+
+const ENEMY_STRING: &str = "Enemy:";
+const LEVEL_STATE_STRING: &str = "Level state:";
+
+const START_OF_ENEMY_DATA_STRING: &[u8] = b"*** Beginning of Enemy Data ***";
+const START_OF_LEVEL_STATE_STRING: &[u8] = b"*** Beginning of Level State ***";
+const START_OF_SHIP_DATA_STRING: &[u8] = b"*** Beginning of Ship Data ***";
+const END_OF_SAVEGAME_STRING: &[u8] = b"*** End of Savegame ***";
+
+impl crate::Data<'_> {
+    /// Writes a snapshot of the game in progress to the data-dir.
+    ///
+    /// The snapshot contains the mission name, the score, the alert state,
+    /// the influencer, all the enemys and the complete (possibly modified)
+    /// ship, so that `load_game` can continue exactly from here.
+    pub fn save_game(&self) -> i32 {
+        let Some(data_dir) = self.main.get_data_dir() else {
+            warn!("No data-dir found, cannot save the game!");
+            return defs::ERR.into();
+        };
+
+        let path = data_dir.join(SAVEGAME_FILE);
+        match self.write_savegame(&path) {
+            Ok(()) => {
+                info!("Successfully saved the game to '{}'", path.display());
+                defs::OK.into()
+            }
+            Err(err) => {
+                error!("Failed to write savegame '{}': {}", path.display(), err);
+                defs::ERR.into()
+            }
+        }
+    }
+
+    fn write_savegame(&self, path: &Path) -> Result<(), io::Error> {
+        let me = &self.vars.me;
+        let savegame = Savegame {
+            mission: self.mission_name().to_owned(),
+            area_name: self.main.cur_ship.area_name.to_str().unwrap().to_owned(),
+            cur_level_index: self.main.cur_level_index.unwrap(),
+            real_score: self.main.real_score,
+            show_score: self.main.show_score,
+            death_count: self.main.death_count,
+            influencer: SavedInfluencer {
+                ty: me.ty,
+                pos: me.pos,
+                speed: me.speed,
+                health: me.health,
+                energy: me.energy,
+                firewait: me.firewait,
+                phase: me.phase,
+                timer: me.timer,
+            },
+            enemys: self.main.enemys.to_vec(),
+            levels: self.main.cur_ship.levels.clone(),
+        };
+
+        // a game saved over an older one must not leave a broken savegame behind
+        write_file_atomically(path, |file| savegame.write(file))
+    }
+
+    /// Restores the game saved by `save_game`, if there is any.
+    ///
+    /// If the savegame belongs to a different mission than the current one,
+    /// the rules of that mission are loaded first.
+    pub fn load_game(&mut self) -> i32 {
+        let Some(data_dir) = self.main.get_data_dir() else {
+            warn!("No data-dir found, cannot load a saved game!");
+            return defs::ERR.into();
+        };
+
+        let path = data_dir.join(SAVEGAME_FILE);
+        let Ok(data) = fs::read(&path) else {
+            warn!("No savegame found in '{}'", path.display());
+            return defs::ERR.into();
+        };
+
+        let Some(savegame) = Savegame::parse(&data) else {
+            error!("Savegame '{}' is corrupt or outdated", path.display());
+            return defs::ERR.into();
+        };
+
+        if let Err(err) = self.restore_savegame(savegame) {
+            self.show_load_error(&err);
+            return defs::ERR.into();
+        }
+        info!("Successfully restored the game from '{}'", path.display());
+        defs::OK.into()
+    }
+
+    fn restore_savegame(&mut self, savegame: Savegame) -> Result<(), LoadError> {
+        let Savegame {
+            mission,
+            area_name,
+            cur_level_index,
+            real_score,
+            show_score,
+            death_count,
+            influencer,
+            enemys,
+            levels,
+        } = savegame;
+
+        if mission != self.mission_name() {
+            let current_mission = self.mission_name().to_owned();
+            if let Err(err) = self.load_mission_rules(&mission) {
+                // The game in progress goes on, so it needs its own rules back.
+                // Without them it cannot, and it ends.
+                if let Err(err) = self.load_mission_rules(&current_mission) {
+                    error!("Unable to reload the rules of mission {current_mission}: {err}");
+                    self.game_over = true;
+                    return Err(err);
+                }
+                return Err(err);
+            }
+        }
+
+        self.activate_conservative_frame_computation();
+        self.set_time_factor(1.0);
+
+        for bullet in 0..MAXBULLETS {
+            self.delete_bullet(bullet);
+        }
+        for blast in &mut self.main.all_blasts {
+            blast.phase = (Status::Out as u8).into();
+            blast.ty = None;
+        }
+
+        self.main.cur_ship.area_name.set_slice(area_name);
+        self.main.cur_ship.levels = levels;
+        self.main.cur_level_index = Some(cur_level_index);
+        self.main.enemys.clear();
+        self.main.enemys.extend(enemys);
+        self.main.real_score = real_score;
+        self.main.show_score = show_score;
+        self.main.death_count = death_count;
+
+        let me = &mut self.vars.me;
+        me.ty = influencer.ty;
+        me.pos = influencer.pos;
+        me.speed = influencer.speed;
+        me.health = influencer.health;
+        me.energy = influencer.energy;
+        me.firewait = influencer.firewait;
+        me.phase = influencer.phase;
+        me.timer = influencer.timer;
+        me.status = Status::Mobile;
+        me.text_visible_time = 0.;
+        me.text_to_be_displayed = TextToBeDisplayed::None;
+        self.init_influ_position_history();
+
+        let song = self.main.cur_level().background_song_name.clone();
+        self.switch_background_music_to(Some(song.to_bytes()));
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct SavedInfluencer {
+    ty: Droid,
+    pos: Finepoint,
+    speed: Finepoint,
+    health: f32,
+    energy: f32,
+    firewait: f32,
+    phase: f32,
+    timer: f32,
+}
+
+#[derive(Debug)]
+struct Savegame {
+    mission: String,
+    area_name: String,
+    cur_level_index: usize,
+    real_score: f32,
+    show_score: u32,
+    death_count: f32,
+    influencer: SavedInfluencer,
+    enemys: Vec<Enemy>,
+    levels: ArrayVec<Level, MAX_LEVELS>,
+}
+
+impl Savegame {
+    fn write(&self, file: &mut impl Write) -> io::Result<()> {
+        macro_rules! write_key_value {
+            ($($key:ident = $value:expr),+ $(,)?) => {
+                $(
+                    writeln!(file, "{} = {}", $key, $value)?;
+                )+
+            };
+        }
+
+        let me = &self.influencer;
+        write_key_value!(
+            VERSION_STRING = SAVEGAME_VERSION,
+            MISSION_STRING = self.mission,
+            AREA_NAME_STRING = self.area_name,
+            CUR_LEVEL_INDEX_STRING = self.cur_level_index,
+            REAL_SCORE_STRING = self.real_score,
+            SHOW_SCORE_STRING = self.show_score,
+            DEATH_COUNT_STRING = self.death_count,
+            INFLU_TYPE_STRING = me.ty.to_u16(),
+            INFLU_POS_X_STRING = me.pos.x,
+            INFLU_POS_Y_STRING = me.pos.y,
+            INFLU_SPEED_X_STRING = me.speed.x,
+            INFLU_SPEED_Y_STRING = me.speed.y,
+            INFLU_HEALTH_STRING = me.health,
+            INFLU_ENERGY_STRING = me.energy,
+            INFLU_FIREWAIT_STRING = me.firewait,
+            INFLU_PHASE_STRING = me.phase,
+            INFLU_TIMER_STRING = me.timer,
+        );
+
+        file.write_all(START_OF_ENEMY_DATA_STRING)?;
+        writeln!(file)?;
+        for enemy in &self.enemys {
+            writeln!(
+                file,
+                "{ENEMY_STRING} Type={} Level={} PosX={} PosY={} SpeedX={} SpeedY={} Energy={} \
+                 Phase={} NextWaypoint={} LastWaypoint={} Status={} Wait={} Firewait={} Target={}",
+                enemy.ty.to_u16(),
+                enemy.levelnum,
+                enemy.pos.x,
+                enemy.pos.y,
+                enemy.speed.x,
+                enemy.speed.y,
+                enemy.energy,
+                enemy.phase,
+                enemy.nextwaypoint,
+                enemy.lastwaypoint,
+                enemy.status as u8,
+                enemy.warten,
+                enemy.firewait,
+                // -1 for a droid without a target
+                enemy.target.map_or(-1, i16::from),
+            )?;
+        }
+
+        file.write_all(START_OF_LEVEL_STATE_STRING)?;
+        writeln!(file)?;
+        for level in &self.levels {
+            writeln!(
+                file,
+                "{LEVEL_STATE_STRING} Levelnumber={} Empty={} Timer={}",
+                level.levelnum, level.empty, level.timer,
+            )?;
+        }
+
+        file.write_all(START_OF_SHIP_DATA_STRING)?;
+        writeln!(file)?;
+        for level in &self.levels {
+            // struct_to_mem closes all the doors, we do not want that to happen in the running game
+            let level_mem = struct_to_mem(&mut level.clone());
+            let end = level_mem
+                .iter()
+                .position(|&c| c == b'\0')
+                .unwrap_or(level_mem.len());
+            file.write_all(&level_mem[..end])?;
+        }
+
+        file.write_all(END_OF_SAVEGAME_STRING)?;
+        writeln!(file)
+    }
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        let end = find_subslice(data, END_OF_SAVEGAME_STRING)?;
+        let data = &data[..end];
+
+        let enemy_start = find_subslice(data, START_OF_ENEMY_DATA_STRING)?;
+        let level_state_start = find_subslice(data, START_OF_LEVEL_STATE_STRING)?;
+        let ship_start = find_subslice(data, START_OF_SHIP_DATA_STRING)?;
+        let header = &data[..enemy_start];
+
+        let version: u32 = parse_value(header, VERSION_STRING)?;
+        if version != SAVEGAME_VERSION {
+            warn!(
+                "Savegame version {} is not supported, expected version {}",
+                version, SAVEGAME_VERSION
+            );
+            return None;
+        }
+
+        let influencer = SavedInfluencer {
+            ty: Droid::try_from(parse_value::<u8>(header, INFLU_TYPE_STRING)?).ok()?,
+            pos: Finepoint {
+                x: parse_value(header, INFLU_POS_X_STRING)?,
+                y: parse_value(header, INFLU_POS_Y_STRING)?,
+            },
+            speed: Finepoint {
+                x: parse_value(header, INFLU_SPEED_X_STRING)?,
+                y: parse_value(header, INFLU_SPEED_Y_STRING)?,
+            },
+            health: parse_value(header, INFLU_HEALTH_STRING)?,
+            energy: parse_value(header, INFLU_ENERGY_STRING)?,
+            firewait: parse_value(header, INFLU_FIREWAIT_STRING)?,
+            phase: parse_value(header, INFLU_PHASE_STRING)?,
+            timer: parse_value(header, INFLU_TIMER_STRING)?,
+        };
+
+        let enemys = data[enemy_start..level_state_start]
+            .lines()
+            .filter_map(|line| line.strip_prefix(ENEMY_STRING.as_bytes()))
+            .map(parse_enemy)
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut levels = levels_from_data(&data[ship_start..]).ok()?;
+        for line in data[level_state_start..ship_start]
+            .lines()
+            .filter_map(|line| line.strip_prefix(LEVEL_STATE_STRING.as_bytes()))
+        {
+            let levelnum: u8 = parse_field(line, "Levelnumber")?;
+            let level = levels.iter_mut().find(|level| level.levelnum == levelnum)?;
+            level.empty = parse_field(line, "Empty")?;
+            level.timer = parse_field(line, "Timer")?;
+        }
+
+        let cur_level_index = parse_value(header, CUR_LEVEL_INDEX_STRING)?;
+        if cur_level_index >= levels.len() {
+            return None;
+        }
+
+        Some(Self {
+            mission: parse_value(header, MISSION_STRING)?,
+            area_name: parse_value(header, AREA_NAME_STRING)?,
+            cur_level_index,
+            real_score: parse_value(header, REAL_SCORE_STRING)?,
+            show_score: parse_value(header, SHOW_SCORE_STRING)?,
+            death_count: parse_value(header, DEATH_COUNT_STRING)?,
+            influencer,
+            enemys,
+            levels,
+        })
+    }
+}
+
+pub fn parse_value<T: FromStr>(data: &[u8], key: &str) -> Option<T> {
+    read_variable(data, key)
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// Parses a `Key=Value` field out of a line of whitespace separated fields.
+pub fn parse_field<T: FromStr>(line: &[u8], key: &str) -> Option<T> {
+    line.fields()
+        .filter_map(|field| field.strip_prefix(key.as_bytes()))
+        .filter_map(|value| value.strip_prefix(b"="))
+        .find_map(|value| std::str::from_utf8(value).ok()?.parse().ok())
+}
+
+fn parse_enemy(line: &[u8]) -> Option<Enemy> {
+    let ty = Droid::try_from(parse_field::<u8>(line, "Type")?).ok()?;
+    let mut enemy = Enemy::new(ty, parse_field(line, "Level")?);
+    enemy.pos = Finepoint {
+        x: parse_field(line, "PosX")?,
+        y: parse_field(line, "PosY")?,
+    };
+    enemy.speed = Finepoint {
+        x: parse_field(line, "SpeedX")?,
+        y: parse_field(line, "SpeedY")?,
+    };
+    enemy.energy = parse_field(line, "Energy")?;
+    enemy.phase = parse_field(line, "Phase")?;
+    enemy.nextwaypoint = parse_field(line, "NextWaypoint")?;
+    enemy.lastwaypoint = parse_field(line, "LastWaypoint")?;
+    enemy.status = Status::try_from(parse_field::<u8>(line, "Status")?).ok()?;
+    enemy.warten = parse_field(line, "Wait")?;
+    enemy.firewait = parse_field(line, "Firewait")?;
+    enemy.target = match parse_field::<i16>(line, "Target")? {
+        -1 => None,
+        target => Some(u8::try_from(target).ok()?),
+    };
+    Some(enemy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_level;
+
+    #[test]
+    fn parse_field_simple() {
+        let line = b" Type=3 Level=12 PosX=1.5 Wait=0";
+        assert_eq!(parse_field::<u8>(line, "Type"), Some(3));
+        assert_eq!(parse_field::<u8>(line, "Level"), Some(12));
+        assert_eq!(parse_field::<f32>(line, "PosX"), Some(1.5));
+        assert_eq!(parse_field::<f32>(line, "PosY"), None);
+    }
+
+    #[test]
+    fn parse_enemy_roundtrip_fields() {
+        let line = b" Type=2 Level=4 PosX=3.25 PosY=7 SpeedX=0 SpeedY=-1.5 Energy=42 Phase=1 \
+            NextWaypoint=5 LastWaypoint=6 Status=13 Wait=0.5 Firewait=0 Target=-1";
+        let enemy = parse_enemy(line).unwrap();
+        assert_eq!(enemy.ty, Droid::Droid139);
+        assert_eq!(enemy.levelnum, 4);
+        assert_eq!(enemy.pos, Finepoint { x: 3.25, y: 7. });
+        assert_eq!(enemy.speed, Finepoint { x: 0., y: -1.5 });
+        assert_eq!(enemy.nextwaypoint, 5);
+        assert_eq!(enemy.lastwaypoint, 6);
+        assert_eq!(enemy.status, Status::Out);
+        assert_eq!(enemy.target, None);
+    }
+
+    #[test]
+    fn savegame_roundtrip() {
+        let mut level = test_level(4, 3, crate::defs::MapTile::Floor);
+        level.levelnum = 2;
+        level.levelname = std::ffi::CString::new("Bridge").unwrap();
+        level.empty = true;
+        level.timer = 4.5;
+
+        let mut enemy = Enemy::new(Droid::Droid139, 2);
+        enemy.pos = Finepoint { x: 1.5, y: 2.25 };
+        enemy.energy = 17.;
+        enemy.nextwaypoint = 3;
+        enemy.status = Status::Mobile;
+        enemy.target = Some(4);
+
+        let savegame = Savegame {
+            mission: "Paradroid".to_owned(),
+            area_name: "Paradroid ship".to_owned(),
+            cur_level_index: 0,
+            real_score: 1234.5,
+            show_score: 1200,
+            death_count: 3.,
+            influencer: SavedInfluencer {
+                ty: Droid::Droid001,
+                pos: Finepoint { x: 2., y: 1.5 },
+                speed: Finepoint { x: -0.5, y: 0. },
+                health: 40.,
+                energy: 35.5,
+                firewait: 0.25,
+                phase: 7.,
+                timer: 99.75,
+            },
+            enemys: vec![enemy],
+            levels: [level].into_iter().collect(),
+        };
+
+        let mut data = Vec::new();
+        savegame.write(&mut data).unwrap();
+        let parsed = Savegame::parse(&data).unwrap();
+        assert_eq!(parsed.mission, savegame.mission);
+        assert_eq!(parsed.area_name, savegame.area_name);
+        assert_eq!(parsed.cur_level_index, savegame.cur_level_index);
+        assert_eq!(parsed.real_score.to_bits(), savegame.real_score.to_bits());
+        assert_eq!(parsed.show_score, savegame.show_score);
+        assert_eq!(parsed.death_count.to_bits(), savegame.death_count.to_bits());
+        assert_eq!(parsed.influencer, savegame.influencer);
+        assert_eq!(parsed.enemys, savegame.enemys);
+        assert_eq!(parsed.levels.len(), 1);
+        assert_eq!(parsed.levels[0].levelnum, 2);
+        assert!(parsed.levels[0].empty);
+        assert_eq!(parsed.levels[0].timer.to_bits(), 4.5f32.to_bits());
+
+        let mut rewritten = Vec::new();
+        parsed.write(&mut rewritten).unwrap();
+        assert_eq!(rewritten, data);
+    }
+}
@@ -3,7 +3,7 @@ use crate::{
     cur_level,
     defs::{
         AlertLevel, AssembleCombatWindowFlags, DROID_ROTATION_TIME, DisplayBannerFlags, Droid,
-        MAXBLASTS, MAXBULLETS, MenuAction, SoundType, Status, TEXT_STRETCH,
+        LIFT_OUT_OF_ORDER_X, MAXBLASTS, MAXBULLETS, MenuAction, SoundType, Status, TEXT_STRETCH,
     },
     graphics::{Graphics, scale_pic},
     map::get_map_brick,
@@ -805,16 +805,33 @@ impl crate::Data<'_> {
     ///  if level==-1: don't highlight any level
     ///  if liftrow==-1: dont' highlight any liftrows
     pub fn show_lifts(&mut self, level: u8, liftrow: i32) {
+        self.sdl.cursor().hide();
+        self.draw_lifts(level, liftrow);
+        assert!(self.graphics.ne_screen.as_mut().unwrap().flip());
+    }
+
+    /// The position of the side-view of the ship on the screen, the lift
+    /// and deck rectangles are relative to it.
+    pub fn ship_view_origin(&self) -> (i16, i16) {
+        let x_offs: i16 = (self.vars.user_rect.width() / 20).try_into().unwrap();
+        let y_offs: i16 = (self.vars.user_rect.height() / 5).try_into().unwrap();
+        (
+            self.vars.user_rect.x() + x_offs,
+            self.vars.user_rect.y() + y_offs,
+        )
+    }
+
+    /// Draws the side-view of the ship like `show_lifts`, without
+    /// flipping the screen.
+    pub fn draw_lifts(&mut self, level: u8, liftrow: i32) {
         let lift_bg_color = SDL_Color {
             r: 0,
             g: 0,
             b: 0,
             unused: 0,
         }; /* black... */
-        let x_offs: i16 = (self.vars.user_rect.width() / 20).try_into().unwrap();
-        let y_offs: i16 = (self.vars.user_rect.height() / 5).try_into().unwrap();
+        let (origin_x, origin_y) = self.ship_view_origin();
 
-        self.sdl.cursor().hide();
         // fill the user fenster with some color
         self.fill_rect(self.vars.user_rect, lift_bg_color);
 
@@ -826,8 +843,8 @@ impl crate::Data<'_> {
             .unwrap()
             .set_clip_rect(&dst);
         dst = self.vars.user_rect;
-        dst.inc_x(x_offs);
-        dst.inc_y(y_offs);
+        dst.set_x(origin_x);
+        dst.set_y(origin_y);
 
         let Graphics {
             ship_off_pic,
@@ -843,8 +860,8 @@ impl crate::Data<'_> {
         let level_rects = &self.main.cur_ship.level_rects[usize::from(level)];
         for src in level_rects {
             dst = *src;
-            dst.inc_x(self.vars.user_rect.x() + x_offs); /* offset respective to User-Rectangle */
-            dst.inc_y(self.vars.user_rect.y() + y_offs);
+            dst.inc_x(origin_x); /* offset respective to User-Rectangle */
+            dst.inc_y(origin_y);
             ship_on_pic
                 .as_mut()
                 .unwrap()
@@ -854,15 +871,13 @@ impl crate::Data<'_> {
         if liftrow >= 0 {
             let src = self.main.cur_ship.lift_row_rects[usize::try_from(liftrow).unwrap()];
             dst = src;
-            dst.inc_x(self.vars.user_rect.x() + x_offs); /* offset respective to User-Rectangle */
-            dst.inc_y(self.vars.user_rect.y() + y_offs);
+            dst.inc_x(origin_x); /* offset respective to User-Rectangle */
+            dst.inc_y(origin_y);
             ship_on_pic
                 .as_mut()
                 .unwrap()
                 .blit_from_to(&src, ne_screen.as_mut().unwrap(), &mut dst);
         }
-
-        assert!(self.graphics.ne_screen.as_mut().unwrap().flip());
     }
 
     /// diese Funktion zeigt die m"oglichen Auswahlpunkte des Menus
@@ -1076,7 +1091,8 @@ impl crate::Data<'_> {
     ) {
         self.ship.enter_lift_last_move_tick = self.sdl.ticks_ms();
         if *up_lift != -1 {
-            if self.main.cur_ship.lifts[usize::try_from(*up_lift).unwrap()].x == 99 {
+            if self.main.cur_ship.lifts[usize::try_from(*up_lift).unwrap()].x == LIFT_OUT_OF_ORDER_X
+            {
                 error!("Lift out of order, so sorry ..");
             } else {
                 *down_lift = (*cur_lift).try_into().unwrap();
@@ -1099,7 +1115,9 @@ impl crate::Data<'_> {
     ) {
         self.ship.enter_lift_last_move_tick = self.sdl.ticks_ms();
         if *down_lift != -1 {
-            if self.main.cur_ship.lifts[usize::try_from(*down_lift).unwrap()].x == 99 {
+            if self.main.cur_ship.lifts[usize::try_from(*down_lift).unwrap()].x
+                == LIFT_OUT_OF_ORDER_X
+            {
                 error!("Lift Out of order, so sorry ..");
             } else {
                 *up_lift = (*cur_lift).try_into().unwrap();
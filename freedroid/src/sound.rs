@@ -95,6 +95,10 @@ impl crate::Data<'_> {
 
     #[inline]
     pub fn play_sound(&self, tune: SoundType) {
+        if self.main.sound_on.not() {
+            return;
+        }
+
         Self::play_sound_static(
             self.main.sound_on,
             self.sdl,
@@ -221,6 +225,10 @@ impl crate::Data<'_> {
 
     #[inline]
     pub fn move_lift_sound(&self) {
+        if self.main.sound_on.not() {
+            return;
+        }
+
         Self::move_lift_sound_static(self.main.sound_on, self.sdl, self.sound.as_ref().unwrap());
     }
 
@@ -292,6 +300,10 @@ impl crate::Data<'_> {
     }
 
     pub fn switch_background_music_to(&mut self, filename_raw: Option<&[u8]>) {
+        if self.main.sound_on.not() {
+            return;
+        }
+
         let Self {
             sdl,
             sound,
@@ -142,6 +142,8 @@ pub struct Enemy {
     pub firewait: f32,    /* gibt die Zeit bis zum naechsten Schuss an */
     pub text_visible_time: f32,
     pub text_to_be_displayed: &'static str,
+    // the waypoint the droid is sent to, it wanders at random without one
+    pub target: Option<u8>,
 }
 
 impl Enemy {
@@ -160,6 +162,7 @@ impl Enemy {
             firewait: 0.,
             text_visible_time: 0.,
             text_to_be_displayed: "",
+            target: None,
         }
     }
 }
@@ -289,6 +292,14 @@ pub struct Level {
     pub waypoints: ArrayVec<Waypoint, { u8_to_usize(MAXWAYPOINTS) }>,
 }
 
+/// The random droids of a level, as given in the crew file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Crew {
+    pub min_rand: i32,     /* the minimum number of random droids */
+    pub max_rand: i32,     /* the maximum number of random droids */
+    pub types: Vec<Droid>, /* the types the random droids are chosen from */
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Ship {
     pub area_name: ArrayCString<100>,
@@ -296,4 +307,5 @@ pub struct Ship {
     pub lifts: ArrayVec<Lift, MAX_LIFTS>,
     pub lift_row_rects: ArrayVec<Rect, MAX_LIFT_ROWS>, /* the lift-row rectangles */
     pub level_rects: [ArrayVec<Rect, MAX_LEVEL_RECTS>; MAX_LEVELS], /* level rectangles */
+    pub crews: [Crew; MAX_LEVELS],                     /* the random droids of every level */
 }
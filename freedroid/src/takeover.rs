@@ -6,10 +6,7 @@ use crate::{
     structs::Point,
 };
 
-use rand::{
-    seq::{IteratorRandom, SliceRandom},
-    thread_rng,
-};
+use rand::seq::{IteratorRandom, SliceRandom};
 use sdl::{Rect, Surface, convert::u8_to_usize};
 use sdl_sys::SDL_Color;
 use std::{
@@ -888,25 +885,24 @@ impl crate::Data<'_> {
             return;
         }
 
-        let mut rng = thread_rng();
         match [
             Action::Move,
             Action::Turn,
             Action::SetCapsule,
             Action::Nothing,
         ]
-        .choose(&mut rng)
+        .choose(&mut self.rng)
         .unwrap()
         {
             Action::Move => {
-                if (0..=100).choose(&mut rng).unwrap() <= MOVE_PROBABILITY {
+                if (0..=100).choose(&mut self.rng).unwrap() <= MOVE_PROBABILITY {
                     row.move_toward(self.takeover.direction);
                 }
             }
 
             Action::Turn => {
                 /* Turn around */
-                if (0..=100).choose(&mut rng).unwrap() <= TURN_PROBABILITY {
+                if (0..=100).choose(&mut self.rng).unwrap() <= TURN_PROBABILITY {
                     self.takeover.direction.invert();
                 }
             }
@@ -916,7 +912,7 @@ impl crate::Data<'_> {
 
                 match row.row_index().map(usize::from) {
                     Some(row_index)
-                        if (0..=100).choose(&mut rng).unwrap() <= SET_PROBABILITY
+                        if (0..=100).choose(&mut self.rng).unwrap() <= SET_PROBABILITY
                             && self.takeover.playground[opponent_color][0][row_index]
                                 != Block::CableEnd
                             && self.takeover.activation_map[opponent_color][0][row_index]
@@ -1085,7 +1081,7 @@ impl crate::Data<'_> {
         /* first clear the playground: we depend on this !! */
         self.clear_playground();
 
-        let mut rng = thread_rng();
+        let rng = &mut self.rng;
         self.takeover
             .playground
             .iter_mut()
@@ -1104,8 +1100,8 @@ impl crate::Data<'_> {
                             continue;
                         }
 
-                        let new_element = (0..TO_ELEMENTS).choose(&mut rng).unwrap();
-                        if (0..=MAX_PROB).choose(&mut rng).unwrap()
+                        let new_element = (0..TO_ELEMENTS).choose(rng).unwrap();
+                        if (0..=MAX_PROB).choose(rng).unwrap()
                             > ELEMENTS_PROBABILITIES[usize::from(new_element)]
                         {
                             continue;
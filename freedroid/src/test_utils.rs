@@ -0,0 +1,58 @@
+//! Helpers shared by the tests of several modules.
+
+use crate::{
+    defs::MapTile,
+    map::new_level,
+    structs::{Level, Waypoint},
+};
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// A level of `xlen` x `ylen` tiles, all of them `tile`.
+pub fn test_level(xlen: u8, ylen: u8, tile: MapTile) -> Level {
+    new_level(0, xlen, ylen, tile)
+}
+
+/// A waypoint at `x`, `y` which leads to the waypoints `connections`.
+pub fn waypoint(x: u8, y: u8, connections: &[u8]) -> Waypoint {
+    Waypoint {
+        x,
+        y,
+        connections: connections.iter().copied().collect(),
+    }
+}
+
+/// An empty directory for the files of a test, which is removed together with
+/// its contents when dropped.
+///
+/// The `name` must be unique among the tests, as they run at the same time.
+#[derive(Debug)]
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("freedroid-{name}-{}", std::process::id()));
+        // the leftovers of a run that was interrupted
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
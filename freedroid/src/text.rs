@@ -10,7 +10,7 @@ use crate::{
 };
 
 use log::{error, info, trace};
-use rand::{seq::SliceRandom, thread_rng};
+use rand::seq::SliceRandom;
 use sdl::{
     Event, Rect, Rgba, Surface,
     convert::i32_to_u8,
@@ -554,7 +554,7 @@ impl crate::Data<'_> {
             "System error. Pleae buy an upgrade from MS.",
             "System error. Press any key to reboot.",
         ]
-        .choose(&mut thread_rng())
+        .choose(&mut self.rng)
         .unwrap();
         robot.text_to_be_displayed = text;
     }
@@ -571,7 +571,7 @@ impl crate::Data<'_> {
             "Hey, I'm from MS! Walk outa my way!",
             "Hey, I know the big MS boss! You better go.",
         ]
-        .choose(&mut thread_rng())
+        .choose(&mut self.rng)
         .unwrap();
         robot.text_to_be_displayed = text;
     }
@@ -592,7 +592,7 @@ impl crate::Data<'_> {
             c"I hope that didn't melt any circuits!",
             c"So that gives some more black scars on me ol' dented chassis!",
         ]
-        .choose(&mut thread_rng())
+        .choose(&mut self.rng)
         .copied()
         .unwrap();
         self.vars.me.text_to_be_displayed = TextToBeDisplayed::String(new_text);
@@ -0,0 +1,220 @@
+//! The directories where the configuration, the highscores and the saved
+//! games are kept.
+//!
+//! By default they follow the XDG base directory specification: the
+//! configuration goes into `$XDG_CONFIG_HOME/freedroid-classic`, the
+//! highscores and the saved games into `$XDG_DATA_HOME/freedroid-classic`.  The
+//! content of the directory used by the older versions, `$HOME/.freedroidClassic`,
+//! is moved there the first time.  A single directory can be chosen instead
+//! with `--config-dir` or `FREEDROID_CONFIG_DIR`, for portable installs and
+//! separate profiles.
+//!
+//! The game data is searched in the `data` subdirectory of the config
+//! directory first, so single files can be overridden, then in the
+//! directories given with `--data-dir`, then in the installed data.  The
+//! level editor saves its ships into the `map` directory of the former.
+
+use crate::defs::DATA_DIRS;
+use log::{info, warn};
+use std::{
+    env,
+    ffi::OsString,
+    fs, io,
+    ops::Not,
+    path::{Path, PathBuf},
+};
+
+const APP_DIR: &str = "freedroid-classic";
+const LEGACY_DIR: &str = ".freedroidClassic";
+const CONFIG_FILE: &str = "config";
+const USER_DATA_DIR: &str = "data";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDirs {
+    /// The directory of the config file.
+    pub config: PathBuf,
+    /// The directory of the highscores and the saved games.
+    pub data: PathBuf,
+    /// The directory used by the older versions, which has to be migrated.
+    legacy: Option<PathBuf>,
+}
+
+impl UserDirs {
+    /// Finds the directories from the environment, unless `config_dir` is
+    /// given.
+    pub fn locate(config_dir: Option<&Path>) -> Self {
+        Self::from_env(config_dir, |name| env::var_os(name))
+    }
+
+    fn from_env(config_dir: Option<&Path>, var: impl Fn(&str) -> Option<OsString>) -> Self {
+        if let Some(config_dir) = config_dir {
+            return Self {
+                config: config_dir.to_owned(),
+                data: config_dir.to_owned(),
+                legacy: None,
+            };
+        }
+
+        let home = if let Some(home) = var("HOME") {
+            info!("found environment HOME = '{}'", home.to_string_lossy());
+            PathBuf::from(home)
+        } else {
+            warn!("Environment does not contain HOME variable...using local dir");
+            PathBuf::from(".")
+        };
+        // relative paths must be ignored, as the specification says
+        let xdg_dir = |name: &str, default: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .unwrap_or_else(|| home.join(default))
+                .join(APP_DIR)
+        };
+
+        Self {
+            config: xdg_dir("XDG_CONFIG_HOME", ".config"),
+            data: xdg_dir("XDG_DATA_HOME", ".local/share"),
+            legacy: Some(home.join(LEGACY_DIR)),
+        }
+    }
+
+    /// The directories searched for the game data, in order: the user
+    /// overrides, the `mods` directories, then the installed data.
+    pub fn data_search_path(&self, mods: &[PathBuf]) -> Vec<PathBuf> {
+        [user_data_dir(&self.config)]
+            .into_iter()
+            .chain(mods.iter().cloned())
+            .chain(DATA_DIRS.map(PathBuf::from))
+            .collect()
+    }
+
+    /// Creates the directories if they do not exist yet, moving there the
+    /// files of the legacy directory.
+    ///
+    /// Returns `false` if the directories did not exist, so there is no
+    /// config to read.
+    pub fn prepare(&self) -> io::Result<bool> {
+        if self.config.exists() {
+            fs::create_dir_all(&self.data)?;
+            return Ok(true);
+        }
+
+        warn!(
+            "Couldn't stat Config-dir {}, I'll try to create it...",
+            self.config.display()
+        );
+        fs::create_dir_all(&self.config)?;
+        fs::create_dir_all(&self.data)?;
+        info!(
+            "Successfully created config-dir '{}'",
+            self.config.display()
+        );
+
+        match &self.legacy {
+            Some(legacy) if legacy.is_dir() => {
+                self.migrate(legacy)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn migrate(&self, legacy: &Path) -> io::Result<()> {
+        info!(
+            "Moving the files of {} to {} and {}",
+            legacy.display(),
+            self.config.display(),
+            self.data.display()
+        );
+
+        for entry in fs::read_dir(legacy)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file().not() {
+                continue;
+            }
+
+            let name = entry.file_name();
+            let dir = if name == CONFIG_FILE {
+                &self.config
+            } else {
+                &self.data
+            };
+            let target = dir.join(&name);
+            // renaming fails when the directories are on different file systems
+            if fs::rename(entry.path(), &target).is_err() {
+                fs::copy(entry.path(), &target)?;
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        if fs::remove_dir(legacy).is_err() {
+            warn!("The legacy directory {} is not empty", legacy.display());
+        }
+        Ok(())
+    }
+}
+
+/// The directory of the data overridden by the user in the config directory
+/// `config`, the first one searched for the game data.
+pub fn user_data_dir(config: &Path) -> PathBuf {
+    config.join(USER_DATA_DIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.into())
+        }
+    }
+
+    #[test]
+    fn locate_dirs() {
+        let dirs = UserDirs::from_env(None, env(&[("HOME", "/home/paul")]));
+        assert_eq!(
+            dirs.config,
+            Path::new("/home/paul/.config/freedroid-classic")
+        );
+        assert_eq!(
+            dirs.data,
+            Path::new("/home/paul/.local/share/freedroid-classic")
+        );
+        assert_eq!(
+            dirs.legacy.as_deref(),
+            Some(Path::new("/home/paul/.freedroidClassic"))
+        );
+
+        let dirs = UserDirs::from_env(
+            None,
+            env(&[
+                ("HOME", "/home/paul"),
+                ("XDG_CONFIG_HOME", "/etc/paul"),
+                ("XDG_DATA_HOME", "relative/data"),
+            ]),
+        );
+        assert_eq!(dirs.config, Path::new("/etc/paul/freedroid-classic"));
+        assert_eq!(
+            dirs.data,
+            Path::new("/home/paul/.local/share/freedroid-classic")
+        );
+
+        let dirs = UserDirs::from_env(Some(Path::new("profile")), env(&[("HOME", "/home/paul")]));
+        assert_eq!(dirs.config, Path::new("profile"));
+        assert_eq!(dirs.data, Path::new("profile"));
+        assert_eq!(dirs.legacy, None);
+    }
+
+    #[test]
+    fn data_search_path() {
+        let dirs = UserDirs::from_env(Some(Path::new("profile")), env(&[]));
+        let mods = [PathBuf::from("mods/first"), PathBuf::from("mods/second")];
+        let path = dirs.data_search_path(&mods);
+        assert_eq!(path[0], Path::new("profile/data"));
+        assert_eq!(path[1..3], mods);
+        assert_eq!(path[3..], DATA_DIRS.map(PathBuf::from));
+    }
+}
@@ -0,0 +1,517 @@
+//! Checking of a mission and all the data files it refers to, without
+//! starting the game.
+//!
+//! The files are read by the loaders of the game, which keep going after a
+//! problem and collect all of them.  On top of that the waypoints, the lifts,
+//! the crews and the start points are checked against the ship.  Every problem
+//! is reported with the file and the line it was found on.
+
+use crate::{
+    Data,
+    defs::{MAP_DIR_C, MAX_ENEMYS_ON_SHIP, Themed},
+    error::{DataProblem, LoadError, expect_entry},
+    find_subslice,
+    global::Global,
+    init::{
+        CREWNAME_INDICATION_STRING, ELEVATORNAME_INDICATION_STRING, END_OF_GAME_DAT_STRING,
+        END_OF_MISSION_DATA_STRING, GAMEDATANAME_INDICATION_STRING, MISSION_BRIEFING_BEGIN_STRING,
+        MISSION_ENDTITLE_BEGIN_STRING, MISSION_ENDTITLE_END_STRING,
+        MISSION_ENDTITLE_SONG_NAME_STRING, MISSION_START_POINT_STRING, SHIPNAME_INDICATION_STRING,
+        read_ruleset, read_start_point,
+    },
+    lifts::{LiftProblem, check_lift_list},
+    map::{
+        END_OF_DROID_DATA_STRING, END_OF_LIFT_DATA_STRING, END_OF_SHIP_DATA_STRING, read_crews,
+        read_lifts, read_ship,
+    },
+    misc::{Misc, try_read_and_malloc_string_from_data, try_read_string_from_string},
+    read_and_malloc_and_terminate_file,
+    structs::{Crew, Level, Ship},
+    waypoints::check_waypoints,
+};
+
+use bstr::ByteSlice;
+use std::{fmt, ops::Not, path::PathBuf};
+
+/// A problem found in one of the data files of a mission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub reason: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.reason),
+            None => write!(f, "{}: {}", self.file.display(), self.reason),
+        }
+    }
+}
+
+/// Checks the given mission and every file it refers to.
+///
+/// All the problems that are found are returned, an empty list means that the
+/// mission can be loaded by the game.  The files are searched in `data_dirs`,
+/// in order.
+#[allow(clippy::too_many_lines)]
+pub fn validate_mission(mission_name: &str, data_dirs: Vec<PathBuf>) -> Vec<Problem> {
+    let global = Global::default();
+    let mut misc = Misc::default();
+    misc.data_dirs = data_dirs;
+    let mut problems = Vec::new();
+
+    let Some(path) = Source::find(&global, &mut misc, mission_name.as_bytes()) else {
+        problems.push(Problem {
+            file: mission_name.into(),
+            line: None,
+            reason: "mission file not found in the map directory".to_owned(),
+        });
+        return problems;
+    };
+    let Some(mission) = Source::read(path, END_OF_MISSION_DATA_STRING, &mut problems) else {
+        return problems;
+    };
+
+    let data = &*mission.data;
+    let mut found = Vec::new();
+    let ruleset = file_name(data, GAMEDATANAME_INDICATION_STRING, &mut found);
+    let ship_file = file_name(data, SHIPNAME_INDICATION_STRING, &mut found);
+    let lift = file_name(data, ELEVATORNAME_INDICATION_STRING, &mut found);
+    let crew = file_name(data, CREWNAME_INDICATION_STRING, &mut found);
+    file_name(data, MISSION_ENDTITLE_SONG_NAME_STRING, &mut found);
+    let end_title = try_read_and_malloc_string_from_data(
+        data,
+        MISSION_ENDTITLE_BEGIN_STRING,
+        MISSION_ENDTITLE_END_STRING,
+    );
+    expect_entry(end_title, MISSION_ENDTITLE_BEGIN_STRING, 0, &mut found);
+    let briefing = find_subslice(data, MISSION_BRIEFING_BEGIN_STRING);
+    expect_entry(briefing, MISSION_BRIEFING_BEGIN_STRING, 0, &mut found);
+
+    let mut start_points = Vec::new();
+    for offset in data.find_iter(MISSION_START_POINT_STRING) {
+        let start_point = &data[offset + MISSION_START_POINT_STRING.len()..];
+        match read_start_point(start_point, &mission.path) {
+            Ok(start_point) => start_points.push((offset, start_point)),
+            Err(err) => found.push(DataProblem::at(offset, load_error_reason(err))),
+        }
+    }
+    if data.find(MISSION_START_POINT_STRING).is_none() {
+        found.push(DataProblem::in_file(format!(
+            "no \"{}\" entry found",
+            MISSION_START_POINT_STRING.to_str_lossy().trim()
+        )));
+    }
+    mission.report(found, &mut problems);
+
+    let mut open =
+        |reference: Option<(usize, &[u8])>, end_string: &[u8], problems: &mut Vec<Problem>| {
+            let (offset, name) = reference?;
+            let Some(path) = Source::find(&global, &mut misc, name) else {
+                problems.push(Problem {
+                    file: mission.path.clone(),
+                    line: Some(mission.line(offset)),
+                    reason: format!(
+                        "file `{}` not found in the map directory",
+                        name.to_str_lossy()
+                    ),
+                });
+                return None;
+            };
+            Source::read(path, end_string, problems)
+        };
+
+    let ruleset = open(ruleset, END_OF_GAME_DAT_STRING, &mut problems).map(|source| {
+        let mut found = Vec::new();
+        let ruleset = read_ruleset(&source.data, &mut found);
+        for (index, droid) in ruleset.droids.iter().enumerate() {
+            let name = droid.druidname.to_bytes();
+            if ruleset.droids[..index]
+                .iter()
+                .any(|other| other.druidname.to_bytes() == name)
+            {
+                found.push(DataProblem::in_file(format!(
+                    "droid `{}` is defined more than once",
+                    name.to_str_lossy()
+                )));
+            }
+        }
+        source.report(found, &mut problems);
+        ruleset
+    });
+
+    let mut ship = Ship::default();
+    let ship_source = open(ship_file, END_OF_SHIP_DATA_STRING.as_bytes(), &mut problems);
+    if let Some(source) = &ship_source {
+        check_ship(source, &mut ship, &mut problems);
+    }
+    let ship_read = ship_source.is_some();
+
+    if let Some(source) = open(lift, END_OF_LIFT_DATA_STRING.as_bytes(), &mut problems) {
+        check_lifts(&source, &mut ship, ship_read, &mut problems);
+    }
+
+    if let Some(source) = open(crew, END_OF_DROID_DATA_STRING, &mut problems) {
+        // without the droids of the ruleset the crew cannot be read
+        if let Some(ruleset) = &ruleset {
+            let droid_names: Vec<_> = ruleset
+                .droids
+                .iter()
+                .map(|droid| droid.druidname.to_bytes())
+                .collect();
+            let decks = ship_read.then_some(ship.levels.len());
+            check_crew(&source, &droid_names, decks, &mut problems);
+        }
+    }
+
+    if ship_read {
+        let mut found = Vec::new();
+        for (offset, [level, x, y]) in start_points {
+            let reason = match usize::try_from(level)
+                .ok()
+                .and_then(|level| ship.levels.get(level))
+            {
+                None => format!("start point on deck {level}, which does not exist"),
+                Some(deck) if contains(deck, x, y).not() => {
+                    format!("start point ({x}, {y}) lies outside of deck {level}")
+                }
+                Some(_) => continue,
+            };
+            found.push(DataProblem::at(offset, reason));
+        }
+        mission.report(found, &mut problems);
+    }
+
+    problems
+}
+
+/// The name of a file the mission refers to, with the position of its entry.
+fn file_name<'a>(
+    data: &'a [u8],
+    label: &[u8],
+    found: &mut Vec<DataProblem>,
+) -> Option<(usize, &'a [u8])> {
+    let pos = expect_entry(find_subslice(data, label), label, 0, found)?;
+    let name =
+        try_read_string_from_string(&data[pos..], label).filter(|name| name.is_empty().not());
+    expect_entry(name, label, pos, found).map(|name| (pos, name))
+}
+
+fn contains(level: &Level, x: i32, y: i32) -> bool {
+    usize::try_from(x).is_ok_and(|x| x < usize::from(level.xlen))
+        && usize::try_from(y).is_ok_and(|y| y < usize::from(level.ylen))
+}
+
+/// What `err` says is wrong with its file.
+fn load_error_reason(err: LoadError) -> String {
+    match err {
+        LoadError::NotFound { .. } => "file not found".to_owned(),
+        LoadError::Io { error, .. } => format!("unable to read file: {error}"),
+        LoadError::Unterminated { end_string, .. } => {
+            format!("the file is not terminated by `{end_string}`")
+        }
+        LoadError::InvalidEntry { entry, .. } => format!("entry \"{entry}\" is missing or invalid"),
+        LoadError::InvalidData { reason, .. } => reason,
+    }
+}
+
+/// A data file, read completely into memory.
+struct Source {
+    path: PathBuf,
+    data: Box<[u8]>,
+}
+
+impl Source {
+    /// Looks for a file in the map directory, like the loaders of the game do.
+    fn find(global: &Global, misc: &mut Misc, name: &[u8]) -> Option<PathBuf> {
+        Data::try_find_file_static(global, misc, name, Some(MAP_DIR_C), Themed::NoTheme, false)
+            .map(|path| PathBuf::from(path.to_string_lossy().into_owned()))
+    }
+
+    /// Reads a file like the loaders of the game do, a file that cannot be
+    /// read is reported.
+    fn read(path: PathBuf, end_string: &[u8], problems: &mut Vec<Problem>) -> Option<Self> {
+        match read_and_malloc_and_terminate_file(&path, end_string) {
+            Ok(data) => Some(Self { path, data }),
+            Err(err) => {
+                problems.push(Problem {
+                    file: path,
+                    line: None,
+                    reason: load_error_reason(err),
+                });
+                None
+            }
+        }
+    }
+
+    fn line(&self, offset: usize) -> usize {
+        self.data[..offset.min(self.data.len())]
+            .find_iter(b"\n")
+            .count()
+            + 1
+    }
+
+    /// Adds the problems `found` in this file to `problems`.
+    fn report(&self, found: Vec<DataProblem>, problems: &mut Vec<Problem>) {
+        problems.extend(found.into_iter().map(|problem| Problem {
+            file: self.path.clone(),
+            line: problem.offset.map(|offset| self.line(offset)),
+            reason: problem.reason,
+        }));
+    }
+}
+
+/// Reads the ship with the loader of the game, and checks the waypoints of
+/// its levels.
+fn check_ship(source: &Source, ship: &mut Ship, problems: &mut Vec<Problem>) {
+    let mut found = Vec::new();
+    let waypoint_offsets = read_ship(&source.data, ship, &mut found);
+
+    // a waypoint left out would change the numbers of the following ones
+    if found.is_empty() {
+        for (level, offsets) in ship.levels.iter().zip(&waypoint_offsets) {
+            for problem in check_waypoints(&level.map, &level.waypoints) {
+                let offset = offsets[usize::from(problem.waypoints()[0])];
+                found.push(DataProblem::at(offset, problem.to_string()));
+            }
+        }
+    }
+
+    source.report(found, problems);
+}
+
+/// Reads the lifts with the loader of the game, and checks them against the
+/// decks of the ship if it has been read.
+fn check_lifts(source: &Source, ship: &mut Ship, ship_read: bool, problems: &mut Vec<Problem>) {
+    let mut found = Vec::new();
+    let offsets = read_lifts(&source.data, ship, &mut found);
+
+    // a lift left out would change the numbers of the following ones
+    if found.is_empty() {
+        let decks = ship
+            .levels
+            .iter()
+            .enumerate()
+            .filter_map(|(deck, level)| Some((u8::try_from(deck).ok()?, level.map.as_slice())));
+        for problem in check_lift_list(&ship.lifts, decks, ship.lift_row_rects.len()) {
+            let offset = match problem {
+                LiftProblem::Unregistered { .. } => None,
+                // Without the ship only the lifts themselves can be checked
+                LiftProblem::NoDeck { .. } if ship_read.not() => continue,
+                LiftProblem::NoDeck { lift }
+                | LiftProblem::NoLiftTile { lift }
+                | LiftProblem::Dangling { lift, .. }
+                | LiftProblem::OneWay { lift, .. }
+                | LiftProblem::NoRow { lift, .. } => Some(offsets[lift]),
+            };
+            found.push(DataProblem {
+                offset,
+                reason: problem.to_string(),
+            });
+        }
+    }
+
+    if ship_read {
+        for (deck, rects) in ship.level_rects.iter().enumerate() {
+            if deck >= ship.levels.len() && rects.is_empty().not() {
+                found.push(DataProblem::in_file(format!(
+                    "rectangles for deck {deck}, which does not exist in the ship"
+                )));
+            }
+        }
+    }
+
+    source.report(found, problems);
+}
+
+/// Reads the crew with the loader of the game, and checks it against the
+/// number of `decks` of the ship if it has been read.
+fn check_crew(
+    source: &Source,
+    droid_names: &[&[u8]],
+    decks: Option<usize>,
+    problems: &mut Vec<Problem>,
+) {
+    let mut found = Vec::new();
+    let crews = read_crews(&source.data, droid_names, &mut found);
+
+    if let Some(decks) = decks {
+        for (deck, crew) in crews.iter().enumerate().skip(decks) {
+            if *crew != Crew::default() {
+                found.push(DataProblem::in_file(format!(
+                    "droids for deck {deck}, which does not exist in the ship"
+                )));
+            }
+        }
+    }
+
+    let max_droids: i64 = crews
+        .iter()
+        .map(|crew| i64::from(crew.max_rand.max(0)))
+        .sum();
+    if usize::try_from(max_droids).is_ok_and(|max| max > MAX_ENEMYS_ON_SHIP) {
+        found.push(DataProblem::in_file(format!(
+            "up to {max_droids} droids can be placed on the ship, but at most \
+             {MAX_ENEMYS_ON_SHIP} are allowed"
+        )));
+    }
+
+    source.report(found, problems);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::waypoints::WaypointProblem;
+
+    const SHIP: &str = "Area name=\"Test ship\"
+Levelnumber: 0
+xlen of this level: 3
+ylen of this level: 2
+color of this level: 0
+Name of this level=Bridge
+Comment of the Influencer on entering this level=\"Hello\"
+Name of background song for this level=bridge.mod
+begin_map
+00 00 32 
+00 00 00 
+begin_waypoints
+Nr.=  0 x=   0 y=   0\t connections:  1 
+Nr.=  1 x=   1 y=   1\t connections:  0 
+end_level
+----------------------------------------------------------------------
+*** End of Ship Data ***
+";
+
+    const LIFTS: &str = "*** Beginning of elevator rectangles ***
+Elevator Number=0 ElRowX=0 ElRowY=0 ElRowW=10 ElRowH=10
+DeckNr=0 RectNumber=0 DeckX=0 DeckY=0 DeckW=10 DeckH=10
+*** Beginning of Lift Data ***
+Label=0 Deck=0 PosX=2 PosY=0 LevelUp=-1 LevelDown=-1 LiftRow=0
+*** End of elevator specification file ***
+";
+
+    const CREW: &str = "** Beginning of new Level **
+Level=0
+Maximum number of Random Droids=3
+Minimum number of Random Droids=1
+Allowed Type of Random Droid for this level: 123
+** End of this levels droid data **
+*** End of Droid Data ***
+";
+
+    fn source(name: &str, data: &str) -> Source {
+        Source {
+            path: name.into(),
+            data: data.as_bytes().into(),
+        }
+    }
+
+    fn ship(data: &str) -> (Ship, Vec<Problem>) {
+        let mut ship = Ship::default();
+        let mut problems = Vec::new();
+        check_ship(&source("test.shp", data), &mut ship, &mut problems);
+        (ship, problems)
+    }
+
+    fn lifts(data: &str) -> Vec<Problem> {
+        let (mut ship, _) = ship(SHIP);
+        let mut problems = Vec::new();
+        check_lifts(&source("test.elv", data), &mut ship, true, &mut problems);
+        problems
+    }
+
+    fn crew(data: &str) -> Vec<Problem> {
+        let (ship, _) = ship(SHIP);
+        let droid_names: [&[u8]; 2] = [b"001", b"123"];
+        let mut problems = Vec::new();
+        check_crew(
+            &source("test.droids", data),
+            &droid_names,
+            Some(ship.levels.len()),
+            &mut problems,
+        );
+        problems
+    }
+
+    fn problem(file: &str, line: Option<usize>, reason: impl Into<String>) -> Problem {
+        Problem {
+            file: file.into(),
+            line,
+            reason: reason.into(),
+        }
+    }
+
+    #[test]
+    fn valid_files() {
+        assert_eq!(ship(SHIP).1, []);
+        assert_eq!(lifts(LIFTS), []);
+        assert_eq!(crew(CREW), []);
+    }
+
+    #[test]
+    fn unknown_droid_type() {
+        let data = CREW.replace("level: 123", "level: 777");
+        assert_eq!(
+            crew(&data),
+            [problem("test.droids", Some(5), "unknown droid type `777`")]
+        );
+    }
+
+    #[test]
+    fn bad_tile() {
+        let data = SHIP.replace("00 00 32", "00 77 32");
+        assert_eq!(
+            ship(&data).1,
+            [problem(
+                "test.shp",
+                Some(10),
+                "invalid map tile `77` at (1, 0)"
+            )]
+        );
+    }
+
+    #[test]
+    fn dangling_waypoint() {
+        let data = SHIP.replace("connections:  0", "connections:  0  5");
+        let dangling = WaypointProblem::Dangling {
+            waypoint: 1,
+            connection: 5,
+        };
+        assert_eq!(
+            ship(&data).1,
+            [problem("test.shp", Some(14), dangling.to_string())]
+        );
+    }
+
+    #[test]
+    fn lift_to_missing_deck() {
+        let data = LIFTS.replace("Deck=0", "Deck=3");
+        let unregistered = LiftProblem::Unregistered {
+            level: 0,
+            x: 2,
+            y: 0,
+        };
+        let no_deck = LiftProblem::NoDeck { lift: 0 };
+        assert_eq!(
+            lifts(&data),
+            [
+                problem("test.elv", None, unregistered.to_string()),
+                problem("test.elv", Some(5), no_deck.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn all_problems_of_a_file() {
+        let data = SHIP
+            .replace("00 00 32", "00 77 32")
+            .replace("y=   1", "y=   x");
+        let problems = ship(&data).1;
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert_eq!(problems[0].line, Some(10));
+        assert_eq!(problems[1].line, Some(14));
+    }
+}
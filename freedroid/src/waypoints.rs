@@ -0,0 +1,696 @@
+//! Checks and generation of the waypoint graph of a level.
+//!
+//! The droids walk from waypoint to waypoint along the connections, choosing
+//! one of them at random.  Nothing stops a level from being saved with a
+//! graph the droids cannot use: connections to waypoints which do not exist,
+//! waypoints inside walls, or groups of waypoints droids can enter but never
+//! leave.
+
+use crate::{
+    defs::{MAX_WP_CONNECTIONS, MAXWAYPOINTS, MapTile},
+    map::is_walkable,
+    structs::Waypoint,
+};
+
+use std::{cmp::Reverse, collections::VecDeque, fmt, ops::Not};
+
+/// How far a generated waypoint looks for tiles to cover, in tiles.  Longer
+/// corridors and larger rooms get more than one waypoint.
+const SIGHT_RANGE: i32 = 8;
+/// A droid walking straight between two waypoints needs this much room to the
+/// left and the right of the line, in tiles.
+const SIGHT_HALF_WIDTH: f32 = 0.3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaypointProblem {
+    /// A connection leads to a waypoint which does not exist.
+    Dangling { waypoint: u8, connection: u8 },
+    /// The waypoint lies outside of the level.
+    OffMap { waypoint: u8 },
+    /// The waypoint lies on a tile droids cannot walk on.
+    Blocked { waypoint: u8 },
+    /// A connection has no counterpart in the opposite direction.
+    OneWay { from: u8, to: u8 },
+    /// The waypoints are not connected to the others of the level.
+    Disconnected { waypoints: Vec<u8> },
+}
+
+impl WaypointProblem {
+    /// The waypoints with the problem.
+    pub fn waypoints(&self) -> &[u8] {
+        match self {
+            Self::Dangling { waypoint, .. }
+            | Self::OffMap { waypoint }
+            | Self::Blocked { waypoint }
+            | Self::OneWay { from: waypoint, .. } => std::slice::from_ref(waypoint),
+            Self::Disconnected { waypoints } => waypoints,
+        }
+    }
+}
+
+impl fmt::Display for WaypointProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dangling {
+                waypoint,
+                connection,
+            } => write!(
+                f,
+                "waypoint {waypoint} is connected to waypoint {connection}, which does not exist"
+            ),
+            Self::OffMap { waypoint } => write!(f, "waypoint {waypoint} lies outside of the level"),
+            Self::Blocked { waypoint } => {
+                write!(
+                    f,
+                    "waypoint {waypoint} lies on a tile droids cannot walk on"
+                )
+            }
+            Self::OneWay { from, to } => write!(
+                f,
+                "waypoint {from} is connected to waypoint {to}, but not the other way round"
+            ),
+            Self::Disconnected { waypoints } => {
+                let list = waypoints
+                    .iter()
+                    .map(u8::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if waypoints.len() == 1 {
+                    write!(f, "waypoint {list} is not connected to the other waypoints")
+                } else {
+                    write!(
+                        f,
+                        "waypoints {list} are not connected to the other waypoints"
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Checks the `waypoints` of a level with the given `map`, which is a list of
+/// rows.
+pub fn check_waypoints(map: &[Vec<MapTile>], waypoints: &[Waypoint]) -> Vec<WaypointProblem> {
+    let mut problems = Vec::new();
+    let exists = |connection: u8| usize::from(connection) < waypoints.len();
+
+    for (index, waypoint) in (0..=u8::MAX).zip(waypoints) {
+        let tile = map
+            .get(usize::from(waypoint.y))
+            .and_then(|row| row.get(usize::from(waypoint.x)));
+        match tile {
+            None => problems.push(WaypointProblem::OffMap { waypoint: index }),
+            Some(&tile) if is_walkable(tile) => {}
+            Some(_) => problems.push(WaypointProblem::Blocked { waypoint: index }),
+        }
+
+        for &connection in &waypoint.connections {
+            if exists(connection).not() {
+                problems.push(WaypointProblem::Dangling {
+                    waypoint: index,
+                    connection,
+                });
+            } else if waypoints[usize::from(connection)]
+                .connections
+                .contains(&index)
+                .not()
+            {
+                problems.push(WaypointProblem::OneWay {
+                    from: index,
+                    to: connection,
+                });
+            }
+        }
+    }
+
+    // The groups of waypoints connected to each other, in either direction.
+    // All but the largest one are reported.
+    let mut group_of: Vec<Option<usize>> = vec![None; waypoints.len()];
+    let mut groups: Vec<Vec<u8>> = Vec::new();
+    for start in 0..waypoints.len() {
+        if group_of[start].is_some() {
+            continue;
+        }
+
+        let group = groups.len();
+        let mut members = Vec::new();
+        let mut pending = vec![start];
+        group_of[start] = Some(group);
+        while let Some(current) = pending.pop() {
+            members.push(u8::try_from(current).unwrap());
+            let outgoing = waypoints[current]
+                .connections
+                .iter()
+                .copied()
+                .filter(|&connection| exists(connection))
+                .map(usize::from);
+            let incoming = (0..waypoints.len()).filter(|&other| {
+                waypoints[other]
+                    .connections
+                    .contains(&u8::try_from(current).unwrap())
+            });
+            for neighbour in outgoing.chain(incoming).collect::<Vec<_>>() {
+                if group_of[neighbour].is_none() {
+                    group_of[neighbour] = Some(group);
+                    pending.push(neighbour);
+                }
+            }
+        }
+        members.sort_unstable();
+        groups.push(members);
+    }
+
+    if let Some(largest) = groups
+        .iter()
+        .enumerate()
+        .max_by_key(|&(index, members)| (members.len(), Reverse(index)))
+        .map(|(index, _)| index)
+    {
+        problems.extend(
+            groups
+                .into_iter()
+                .enumerate()
+                .filter(|&(index, _)| index != largest)
+                .map(|(_, waypoints)| WaypointProblem::Disconnected { waypoints }),
+        );
+    }
+
+    problems
+}
+
+/// The position of a tile, column first.
+type Tile = (i32, i32);
+
+/// The walkable tiles of a map, see [`is_walkable`].
+struct Walkable {
+    tiles: Vec<Vec<bool>>,
+}
+
+impl Walkable {
+    fn new(map: &[Vec<MapTile>]) -> Self {
+        Self {
+            tiles: map
+                .iter()
+                .map(|row| row.iter().copied().map(is_walkable).collect())
+                .collect(),
+        }
+    }
+
+    fn contains(&self, (x, y): Tile) -> bool {
+        usize::try_from(y)
+            .ok()
+            .and_then(|y| self.tiles.get(y))
+            .zip(usize::try_from(x).ok())
+            .and_then(|(row, x)| row.get(x))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// All walkable tiles, row by row.
+    fn all(&self) -> impl Iterator<Item = Tile> + '_ {
+        (0..).zip(&self.tiles).flat_map(|(y, row)| {
+            (0..)
+                .zip(row)
+                .filter(|&(_, &walkable)| walkable)
+                .map(move |(x, _)| (x, y))
+        })
+    }
+
+    /// The walkable tiles next to `tile`, without the diagonal ones.
+    fn neighbours(&self, (x, y): Tile) -> impl Iterator<Item = Tile> + '_ {
+        [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)]
+            .into_iter()
+            .filter(|&tile| self.contains(tile))
+    }
+
+    /// Whether all eight tiles around `tile` are walkable as well.
+    fn is_open(&self, (x, y): Tile) -> bool {
+        (-1..=1).all(|dy| (-1..=1).all(|dx| self.contains((x + dx, y + dy))))
+    }
+
+    /// The number of separate walkable stretches in the eight tiles around
+    /// `tile`: two in a corridor, three or more where corridors meet.
+    fn exits(&self, (x, y): Tile) -> usize {
+        const RING: [Tile; 8] = [
+            (0, -1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+        ];
+
+        let ring = RING.map(|(dx, dy)| self.contains((x + dx, y + dy)));
+        (0..ring.len())
+            .filter(|&index| ring[index] && ring[(index + ring.len() - 1) % ring.len()].not())
+            .count()
+    }
+
+    /// Whether a droid can walk straight from the middle of `from` to the
+    /// middle of `to`.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn in_sight(&self, from: Tile, to: Tile) -> bool {
+        let (dx, dy) = ((to.0 - from.0) as f32, (to.1 - from.1) as f32);
+        let length = dx.hypot(dy);
+        if length == 0. {
+            return self.contains(from);
+        }
+
+        let (side_x, side_y) = (
+            -dy / length * SIGHT_HALF_WIDTH,
+            dx / length * SIGHT_HALF_WIDTH,
+        );
+        let steps = (length * 4.).ceil() as i32;
+        (0..=steps).all(|step| {
+            let part = step as f32 / steps as f32;
+            let (x, y) = (from.0 as f32 + dx * part, from.1 as f32 + dy * part);
+            [-1., 0., 1.].into_iter().all(|side: f32| {
+                self.contains((
+                    (x + side_x * side).round() as i32,
+                    (y + side_y * side).round() as i32,
+                ))
+            })
+        })
+    }
+
+    /// Visits the walkable tiles reachable from `starts` through the tiles
+    /// accepted by `allowed`.  Returns the tiles in the order they were
+    /// reached, each together with the tile it was reached from.
+    fn search(&self, starts: &[Tile], allowed: impl Fn(Tile) -> bool) -> Vec<(Tile, Tile)> {
+        let mut reached = self.mask();
+        let mut pending = VecDeque::new();
+        for &start in starts {
+            if mark(&mut reached, start) {
+                pending.push_back((start, start));
+            }
+        }
+
+        let mut visited = Vec::new();
+        while let Some((tile, from)) = pending.pop_front() {
+            visited.push((tile, from));
+            for neighbour in self.neighbours(tile) {
+                if allowed(neighbour) && mark(&mut reached, neighbour) {
+                    pending.push_back((neighbour, tile));
+                }
+            }
+        }
+        visited
+    }
+
+    /// A mask over all tiles of the map, none of them set.
+    fn mask(&self) -> Vec<Vec<bool>> {
+        self.tiles
+            .iter()
+            .map(|row| vec![false; row.len()])
+            .collect()
+    }
+}
+
+/// Sets `tile` in `mask`, returns if it was not set before.
+fn mark(mask: &mut [Vec<bool>], tile: Tile) -> bool {
+    let (x, y) = (
+        usize::try_from(tile.0).unwrap(),
+        usize::try_from(tile.1).unwrap(),
+    );
+    let was_set = mask[y][x];
+    mask[y][x] = true;
+    was_set.not()
+}
+
+fn is_marked(mask: &[Vec<bool>], (x, y): Tile) -> bool {
+    usize::try_from(y)
+        .ok()
+        .and_then(|y| mask.get(y))
+        .zip(usize::try_from(x).ok())
+        .and_then(|(row, x)| row.get(x))
+        .copied()
+        .unwrap_or(false)
+}
+
+fn distance((x1, y1): Tile, (x2, y2): Tile) -> i32 {
+    (x1 - x2).pow(2) + (y1 - y2).pow(2)
+}
+
+/// The path back from `tile` to the start of a search, `tile` first.
+fn path_back(visited: &[(Tile, Tile)], mut tile: Tile) -> Vec<Tile> {
+    let mut path = vec![tile];
+    while let Some(&(_, from)) = visited.iter().find(|&&(reached, _)| reached == tile) {
+        if from == tile {
+            break;
+        }
+        path.push(from);
+        tile = from;
+    }
+    path
+}
+
+/// Proposes a waypoint graph for a level with the given `map`, which is a
+/// list of rows.
+///
+/// Every room, that is a group of tiles with walkable tiles all around them,
+/// gets a waypoint in its middle and every junction of corridors gets one.
+/// The corridors and corners which cannot be seen from those get a waypoint
+/// in their middle.  The waypoints are connected to those in sight, unless a
+/// third waypoint lies in between.  At last waypoints are added around the
+/// bends, until the droids can walk everywhere the walkable tiles lead.
+pub fn generate_waypoints(map: &[Vec<MapTile>]) -> Vec<Waypoint> {
+    let walkable = Walkable::new(map);
+    let mut tiles = Vec::new();
+
+    let room_zone = add_rooms(&walkable, &mut tiles);
+
+    // the junctions of corridors, the tiles next to a junction are part of it
+    for tile in walkable.all() {
+        if is_marked(&room_zone, tile).not()
+            && walkable.exits(tile) >= 3
+            && tiles
+                .iter()
+                .all(|&other| (other.0 - tile.0).abs() > 1 || (other.1 - tile.1).abs() > 1)
+        {
+            add_tile(&mut tiles, tile);
+        }
+    }
+
+    add_stretches(&walkable, &mut tiles);
+    let connections = add_corners(&walkable, &mut tiles);
+
+    tiles
+        .iter()
+        .zip(connections)
+        .map(|(&(x, y), connections)| Waypoint {
+            x: u8::try_from(x).unwrap(),
+            y: u8::try_from(y).unwrap(),
+            connections: connections.into_iter().collect(),
+        })
+        .collect()
+}
+
+/// Adds a waypoint on `tile`, unless there is one already or there are too
+/// many.  Returns if it was added.
+fn add_tile(tiles: &mut Vec<Tile>, tile: Tile) -> bool {
+    let added = tiles.len() < usize::from(MAXWAYPOINTS) && tiles.contains(&tile).not();
+    if added {
+        tiles.push(tile);
+    }
+    added
+}
+
+/// Adds a waypoint in the middle of every room.  Returns the tiles of the
+/// rooms together with the tiles around them.
+fn add_rooms(walkable: &Walkable, tiles: &mut Vec<Tile>) -> Vec<Vec<bool>> {
+    let mut open = walkable.mask();
+    let mut room_zone = walkable.mask();
+    for tile in walkable.all() {
+        if walkable.is_open(tile) {
+            mark(&mut open, tile);
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    mark(&mut room_zone, (tile.0 + dx, tile.1 + dy));
+                }
+            }
+        }
+    }
+
+    let mut in_room = walkable.mask();
+    for start in walkable.all() {
+        if is_marked(&open, start).not() || is_marked(&in_room, start) {
+            continue;
+        }
+
+        let room: Vec<_> = walkable
+            .search(&[start], |tile| is_marked(&open, tile))
+            .into_iter()
+            .map(|(tile, _)| tile)
+            .collect();
+        let count = i64::try_from(room.len()).unwrap();
+        let (sum_x, sum_y) = room.iter().fold((0, 0), |(sum_x, sum_y), &(x, y)| {
+            (sum_x + i64::from(x), sum_y + i64::from(y))
+        });
+        let middle = room
+            .iter()
+            .copied()
+            .min_by_key(|&(x, y)| {
+                (i64::from(x) * count - sum_x).pow(2) + (i64::from(y) * count - sum_y).pow(2)
+            })
+            .unwrap();
+        add_tile(tiles, middle);
+        for tile in room {
+            mark(&mut in_room, tile);
+        }
+    }
+
+    room_zone
+}
+
+/// Adds waypoints in the middle of the stretches no waypoint is in sight of,
+/// until every walkable tile is seen.
+fn add_stretches(walkable: &Walkable, tiles: &mut Vec<Tile>) {
+    let mut seen = walkable.mask();
+    let mut covered = 0;
+    loop {
+        for &from in &tiles[covered..] {
+            for y in from.1 - SIGHT_RANGE..=from.1 + SIGHT_RANGE {
+                for x in from.0 - SIGHT_RANGE..=from.0 + SIGHT_RANGE {
+                    if distance(from, (x, y)) <= SIGHT_RANGE.pow(2)
+                        && walkable.in_sight(from, (x, y))
+                    {
+                        mark(&mut seen, (x, y));
+                    }
+                }
+            }
+        }
+        covered = tiles.len();
+
+        let Some(start) = walkable.all().find(|&tile| is_marked(&seen, tile).not()) else {
+            return;
+        };
+
+        // the middle of the longest path through the unseen tiles around the start
+        let unseen = |tile| is_marked(&seen, tile).not();
+        let (end, _) = *walkable.search(&[start], unseen).last().unwrap();
+        let stretch = walkable.search(&[end], unseen);
+        let (other_end, _) = *stretch.last().unwrap();
+        let path = path_back(&stretch, other_end);
+        if add_tile(tiles, path[path.len() / 2]).not() {
+            return;
+        }
+    }
+}
+
+/// Connects the waypoints and adds waypoints at the corners between those
+/// which cannot see each other, as long as walkable tiles lead from one to
+/// the other.  Returns the connections.
+fn add_corners(walkable: &Walkable, tiles: &mut Vec<Tile>) -> Vec<Vec<u8>> {
+    let mut connections = connect(walkable, tiles);
+    'corners: loop {
+        for group in &connected_groups(&connections) {
+            let starts: Vec<_> = group.iter().map(|&index| tiles[index]).collect();
+            let visited = walkable.search(&starts, |_| true);
+            let Some(&(target, _)) = visited.iter().find(|&&(tile, _)| {
+                tiles
+                    .iter()
+                    .position(|&other| other == tile)
+                    .is_some_and(|index| group.contains(&index).not())
+            }) else {
+                continue;
+            };
+
+            // the path ends at a waypoint of the group, the farthest tile in
+            // sight of it becomes a waypoint
+            let path = path_back(&visited, target);
+            let source = *path.last().unwrap();
+            let corner = *path
+                .iter()
+                .find(|&&tile| walkable.in_sight(source, tile))
+                .unwrap();
+            if corner != target && add_tile(tiles, corner) {
+                connections = connect(walkable, tiles);
+                continue 'corners;
+            }
+        }
+        return connections;
+    }
+}
+
+/// The connections between the waypoints on `tiles` in sight of each
+/// other, without those with a waypoint in between that is closer to both.
+fn connect(walkable: &Walkable, tiles: &[Tile]) -> Vec<Vec<u8>> {
+    let sight: Vec<Vec<bool>> = tiles
+        .iter()
+        .map(|&from| {
+            tiles
+                .iter()
+                .map(|&to| walkable.in_sight(from, to))
+                .collect()
+        })
+        .collect();
+
+    let mut links = Vec::new();
+    for first in 0..tiles.len() {
+        for second in first + 1..tiles.len() {
+            let length = distance(tiles[first], tiles[second]);
+            let shortcut = (0..tiles.len()).any(|between| {
+                sight[first][between]
+                    && sight[between][second]
+                    && distance(tiles[first], tiles[between]) < length
+                    && distance(tiles[between], tiles[second]) < length
+            });
+            if sight[first][second] && shortcut.not() {
+                links.push((length, first, second));
+            }
+        }
+    }
+    links.sort_unstable();
+
+    let mut connections = vec![Vec::new(); tiles.len()];
+    let full = |connections: &Vec<u8>| connections.len() >= usize::from(MAX_WP_CONNECTIONS);
+    for (_, first, second) in links {
+        if full(&connections[first]) || full(&connections[second]) {
+            continue;
+        }
+        connections[first].push(u8::try_from(second).unwrap());
+        connections[second].push(u8::try_from(first).unwrap());
+    }
+    connections
+}
+
+/// The groups of waypoints connected to each other.
+fn connected_groups(connections: &[Vec<u8>]) -> Vec<Vec<usize>> {
+    let mut grouped = vec![false; connections.len()];
+    let mut groups = Vec::new();
+    for start in 0..connections.len() {
+        if grouped[start] {
+            continue;
+        }
+
+        grouped[start] = true;
+        let mut members = Vec::new();
+        let mut pending = vec![start];
+        while let Some(current) = pending.pop() {
+            members.push(current);
+            for &neighbour in &connections[current] {
+                let neighbour = usize::from(neighbour);
+                if grouped[neighbour].not() {
+                    grouped[neighbour] = true;
+                    pending.push(neighbour);
+                }
+            }
+        }
+        groups.push(members);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::waypoint;
+
+    use MapTile::{Floor, HWall};
+
+    #[test]
+    fn sound_graph() {
+        let map = vec![vec![Floor; 3]];
+        let waypoints = [
+            waypoint(0, 0, &[1]),
+            waypoint(1, 0, &[0, 2]),
+            waypoint(2, 0, &[1]),
+        ];
+        assert_eq!(check_waypoints(&map, &waypoints), []);
+        assert_eq!(check_waypoints(&map, &[]), []);
+    }
+
+    #[test]
+    fn broken_graph() {
+        let map = vec![vec![Floor, HWall, Floor, Floor]];
+        let waypoints = [
+            waypoint(0, 0, &[1, 9]),
+            waypoint(1, 0, &[0]),
+            waypoint(2, 0, &[3]),
+            waypoint(3, 0, &[]),
+            waypoint(3, 1, &[]),
+        ];
+        assert_eq!(
+            check_waypoints(&map, &waypoints),
+            [
+                WaypointProblem::Dangling {
+                    waypoint: 0,
+                    connection: 9
+                },
+                WaypointProblem::Blocked { waypoint: 1 },
+                WaypointProblem::OneWay { from: 2, to: 3 },
+                WaypointProblem::OffMap { waypoint: 4 },
+                WaypointProblem::Disconnected {
+                    waypoints: vec![2, 3]
+                },
+                WaypointProblem::Disconnected { waypoints: vec![4] },
+            ]
+        );
+    }
+
+    #[test]
+    fn disconnected_groups() {
+        let map = vec![vec![Floor; 5]];
+        let waypoints = [
+            waypoint(0, 0, &[1]),
+            waypoint(1, 0, &[0]),
+            waypoint(2, 0, &[3]),
+            waypoint(3, 0, &[2, 4]),
+            waypoint(4, 0, &[3]),
+        ];
+        let problems = check_waypoints(&map, &waypoints);
+        assert_eq!(
+            problems,
+            [WaypointProblem::Disconnected {
+                waypoints: vec![0, 1]
+            }]
+        );
+        assert_eq!(problems[0].waypoints(), [0, 1]);
+        assert_eq!(
+            problems[0].to_string(),
+            "waypoints 0, 1 are not connected to the other waypoints"
+        );
+    }
+
+    fn map(rows: &[&str]) -> Vec<Vec<MapTile>> {
+        rows.iter()
+            .map(|row| {
+                row.bytes()
+                    .map(|tile| if tile == b'.' { Floor } else { HWall })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generated_room() {
+        let map = map(&[
+            "#######", "#.....#", "#.....#", "#.....#", "#.....#", "#.....#", "#######",
+        ]);
+        assert_eq!(generate_waypoints(&map), [waypoint(3, 3, &[])]);
+        assert_eq!(generate_waypoints(&[vec![HWall; 3]]), []);
+    }
+
+    #[test]
+    fn generated_corridors() {
+        let map = map(&[
+            "##########################",
+            "#.....#############......#",
+            "#.....#############......#",
+            "#..........#######.......#",
+            "#.....####.#######.......#",
+            "#.....####.#######.......#",
+            "##########.......######.##",
+            "################.######.##",
+            "################.........#",
+            "######################...#",
+            "######################...#",
+            "##########################",
+        ]);
+        let waypoints = generate_waypoints(&map);
+        assert!(waypoints.len() > 3);
+        assert_eq!(check_waypoints(&map, &waypoints), []);
+    }
+}